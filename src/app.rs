@@ -1,11 +1,53 @@
-use rubbl_casatables::{GlueDataType, Table, TableOpenMode};
-use rubbl_core::ndarray::{Array1, Array2, Array3, Ix1, Ix2};
-use rubbl_core::{Array, Complex};
+use std::collections::HashMap;
+use std::path::Path;
+
+use rubbl_casatables::{Table, TableOpenMode};
+
+use crate::compare::{ColumnComparison, ColumnPair, ComparisonJob};
+use crate::diff::{compare_structure, DiffView};
+use crate::dump::describe_ms;
+use crate::error::AppError;
+use crate::export::{parse_row_range, ExportForm, ExportFormat, ExportJob, TextOptions};
+use crate::flagstats::FlagStats;
+use crate::foreign_keys::load_foreign_keys;
+use crate::fuzzy::fuzzy_match;
+use crate::inspector::CellInspector;
+use crate::keywords::KeywordTree;
+use crate::loader::{
+    handle_table_request, load_values, ColumnSummary, LoadOutcome, LoadRequest, Loader,
+    TableOutcome, TableRequest, TableSummary, ViewRequest,
+};
+use crate::measures::{column_unit, parse_epoch, ColumnUnit};
+use crate::plot::{PlotQuantity, Visibilities, VisibilityPlot};
+use crate::query::RowFilter;
+use crate::reader::DisplayOptions;
+use crate::stats::{ColumnStats, StatsJob};
+use crate::storage::StorageLayout;
+use crate::tables::TableTree;
+use crate::uvplot::UvPlot;
+use crate::waterfall::Waterfall;
 
 pub enum CurrentScreen {
     Main,
     Exiting,
     Help,
+    Inspector,
+    Plot,
+    Waterfall,
+    UvCoverage,
+    FlagStats,
+    ColumnStats,
+    Keywords,
+    Storage,
+    Query,
+    GoTo,
+    StatsRange,
+    Search,
+    Export,
+    Diff,
+    CompareColumns,
+    Comparison,
+    Error,
 }
 
 pub enum CurrentlyEditing {
@@ -14,47 +56,187 @@ pub enum CurrentlyEditing {
     Information,
 }
 
+/// The table shown in the Column Values panel, which the interface opens only once it reads it.
+///
+/// The table loader opens tables and describes their columns, so moving through the Tables and
+/// Fields lists never waits for a table to open.
+pub struct LazyTable {
+    pub path: String,
+    table: Option<Table>,
+}
+
+impl LazyTable {
+    /// The table, opened first if it is not open yet.
+    pub fn get(&mut self) -> Result<&mut Table, AppError> {
+        let table = match self.table.take() {
+            Some(table) => table,
+            None => Table::open(&self.path, TableOpenMode::Read)
+                .map_err(|e| AppError::open_table(&self.path, e))?,
+        };
+        Ok(self.table.insert(table))
+    }
+}
+
 pub struct App {
     pub current_screen: CurrentScreen, // the current screen the user is looking at, and will later determine what is rendered.
     pub currently_editing: CurrentlyEditing, // the optional state containing which of the key or value pair the user is editing. It is an option, because when the user is not directly editing a key-value pair, this will be set to `None`.
     pub ms_name: String,
-    pub ms_table: Table,
-    pub tables: Vec<String>,
+    pub ms_table: LazyTable,
+    /// Number of rows of the table that is shown.
+    pub n_rows: u64,
+    pub tables: TableTree,
     pub columns: Vec<String>,
+    pub column_summaries: Vec<ColumnSummary>,
     pub current_table: usize,
+    /// Index of the table that is shown, which differs from `current_table` while the table
+    /// loader opens the selected table.
+    pub shown_table: usize,
     pub current_column: usize,
     pub text_buffer_head: String,
     pub text_buffer: String,
+    /// Unit of the values shown, e.g. "m", or the frame of an epoch column such as UTC.
+    pub values_unit: Option<String>,
     pub text_scroll: u16,
     pub tab_scroll: u64,
     pub line_height: u16,
+    pub raw_values: bool,
+    pub foreign_keys: HashMap<String, Vec<String>>,
+    pub inspector: Option<CellInspector>,
+    pub plot: Option<VisibilityPlot>,
+    pub waterfall: Option<Waterfall>,
+    pub uv_plot: Option<UvPlot>,
+    pub flag_stats: Option<FlagStats>,
+    pub flag_table: usize,
+    pub flag_row: usize,
+    pub flag_by_percentage: bool,
+    pub stats_job: Option<StatsJob>,
+    pub column_stats: Option<ColumnStats>,
+    pub keywords: Option<KeywordTree>,
+    pub storage: Option<StorageLayout>,
+    pub filter: Option<RowFilter>,
+    pub query_input: String,
+    pub goto_input: String,
+    /// Rows to compute column statistics over, as start:end.
+    pub stats_range_input: String,
+    pub search_input: String,
+    /// Entries of the searched list that match, best first, with the matched character positions.
+    pub search_matches: Vec<(usize, Vec<usize>)>,
+    pub search_selected: usize,
+    pub export_form: Option<ExportForm>,
+    pub export_job: Option<ExportJob>,
+    /// Outcome of the last export, shown until the export popup is closed.
+    pub export_message: Option<String>,
+    /// Second Measurement Set to compare with, if one was given.
+    pub compare_ms: Option<String>,
+    /// Differences in the structure of the two Measurement Sets, found when first compared.
+    pub ms_differences: Option<Vec<String>>,
+    pub diff_view: Option<DiffView>,
+    /// Columns typed in the comparison prompt, e.g. DATA - MODEL_DATA.
+    pub compare_input: String,
+    /// Whether the comparison adds a table with a line per row, which may be millions of lines.
+    pub compare_per_row: bool,
+    /// Columns that are compared; the plot views show their combination from the comparison.
+    pub column_pair: Option<ColumnPair>,
+    pub comparison_job: Option<ComparisonJob>,
+    pub comparison: Option<ColumnComparison>,
+    pub comparison_table: usize,
+    pub comparison_row: usize,
+    pub loader: Loader<LoadRequest, String>,
+    /// Thread that opens tables and reads the data of the views, which may scan a whole table.
+    pub table_loader: Loader<TableRequest, TableOutcome>,
+    /// Path of the table selected in the Tables panel while the table loader opens it.
+    pub opening_table: Option<String>,
+    /// Description of the view whose data the table loader is reading, e.g. "plot DATA".
+    pub reading_view: Option<String>,
+    pub spinner: usize,
+    pub error_message: String,
 }
 
 impl App {
-    pub fn new(ms_in: String) -> App {
-        let mut t = Table::open(ms_in.clone(), TableOpenMode::Read).expect("Failed to read MS.");
+    pub fn new(ms_in: String) -> Result<App, AppError> {
+        let mut t = Table::open(ms_in.clone(), TableOpenMode::Read)
+            .map_err(|e| AppError::open_table(&ms_in, e))?;
 
-        let mut tables = vec!["MAIN".to_string()];
-        tables.append(&mut t.table_keyword_names().expect("Failed"));
-        let columns = t.column_names().expect("Failed to read column names.");
+        let ms_name = ms_in.trim_end_matches("/").to_string();
+        let subtables = t
+            .table_keyword_names()
+            .map_err(|e| AppError::read_table(&ms_in, e))?;
+        let summary =
+            TableSummary::read(&ms_name, &mut t).map_err(|e| AppError::read_table(&ms_in, e))?;
+        let foreign_keys = load_foreign_keys(&ms_in);
 
-        let mut app = App {
+        let app = App {
             current_screen: CurrentScreen::Main,
             currently_editing: CurrentlyEditing::Table,
-            ms_name: ms_in.trim_end_matches("/").to_string(),
-            ms_table: t,
-            tables,
-            columns,
+            tables: TableTree::new(&ms_name, subtables),
+            ms_table: LazyTable {
+                path: ms_name.clone(),
+                table: Some(t),
+            },
+            ms_name,
+            n_rows: summary.n_rows,
+            columns: summary.columns.iter().map(|c| c.name.clone()).collect(),
+            column_summaries: summary.columns,
             current_column: 0,
             current_table: 0,
+            shown_table: 0,
             text_buffer_head: "".to_string(),
             text_buffer: "".to_string(),
+            values_unit: None,
             text_scroll: 0,
             tab_scroll: 0,
             line_height: 50,
+            raw_values: false,
+            foreign_keys,
+            inspector: None,
+            plot: None,
+            waterfall: None,
+            uv_plot: None,
+            flag_stats: None,
+            flag_table: 0,
+            flag_row: 0,
+            flag_by_percentage: false,
+            stats_job: None,
+            column_stats: None,
+            keywords: None,
+            storage: None,
+            filter: None,
+            query_input: String::new(),
+            goto_input: String::new(),
+            stats_range_input: String::new(),
+            search_input: String::new(),
+            search_matches: Vec::new(),
+            search_selected: 0,
+            export_form: None,
+            export_job: None,
+            export_message: None,
+            compare_ms: None,
+            ms_differences: None,
+            diff_view: None,
+            compare_input: String::new(),
+            compare_per_row: false,
+            column_pair: None,
+            comparison_job: None,
+            comparison: None,
+            comparison_table: 0,
+            comparison_row: 0,
+            loader: Loader::spawn(load_values),
+            table_loader: Loader::spawn(handle_table_request),
+            opening_table: None,
+            reading_view: None,
+            spinner: 0,
+            error_message: String::new(),
         };
-        app.select(true);
-        app
+        Ok(app)
+    }
+
+    /// Print a JSON description of the table tree of the opened MS to stdout.
+    pub fn print_json(&self) -> anyhow::Result<()> {
+        let mut tables = vec!["MAIN".to_string()];
+        tables.extend(self.tables.nodes[0].subtables.clone().unwrap_or_default());
+        let description = describe_ms(&self.ms_name, &tables)?;
+        println!("{}", serde_json::to_string_pretty(&description)?);
+        Ok(())
     }
 
     pub fn toggle_editing(&mut self, forwards: bool) {
@@ -89,7 +271,7 @@ impl App {
                 if scroll == "view" {
                     self.text_scroll += amount;
                 } else if scroll == "data" {
-                    self.tab_scroll += amount as u64;
+                    self.tab_scroll = (self.tab_scroll + amount as u64).min(self.last_position());
                 }
             }
             CurrentlyEditing::Column => {
@@ -100,7 +282,7 @@ impl App {
             }
             CurrentlyEditing::Table => {
                 self.current_table += 1;
-                if self.current_table >= self.tables.len() {
+                if self.current_table >= self.tables.nodes.len() {
                     self.current_table = 0;
                 }
             }
@@ -130,7 +312,7 @@ impl App {
             }
             CurrentlyEditing::Table => {
                 if self.current_table == 0 {
-                    self.current_table = self.tables.len() - 1;
+                    self.current_table = self.tables.nodes.len() - 1;
                 } else {
                     self.current_table -= 1;
                 }
@@ -147,540 +329,1019 @@ impl App {
         }
     }
 
-    pub fn update_soltabs(&mut self) {
+    /// Toggle between raw values and values decoded using the column units, e.g. TIME as UTC.
+    pub fn toggle_raw_values(&mut self) -> Result<(), AppError> {
+        self.raw_values = !self.raw_values;
         match &self.currently_editing {
-            _ => {}
+            CurrentlyEditing::Table => Ok(()),
+            _ => self.select(false),
         }
     }
 
-    pub fn read_scalar_value_into_buffer(
-        &mut self,
-        buf: &mut String,
-        column_name: &str,
-        start_row: u64,
-        end_row: u64,
-    ) -> String {
-        let col_desc = self.ms_table.get_col_desc(column_name).expect("Failed");
-        let mut main_row = self.ms_table.get_row_reader().expect("Failed");
-
-        buf.push_str(&format!(":{:^5}: ", "ROW"));
-        buf.push_str("VALUE");
-        buf.push_str("\n");
-        for row_num in start_row..end_row {
-            buf.push_str(&format!(":{:>5}: ", row_num));
-            self.ms_table
-                .read_row(&mut main_row, row_num)
-                .expect("Failed");
-            match col_desc.data_type() {
-                GlueDataType::TpChar => {
-                    let data = main_row.get_cell::<i8>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpUChar => {
-                    let data = main_row.get_cell::<u8>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpShort => {
-                    let data = main_row.get_cell::<i16>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpUShort => {
-                    let data = main_row.get_cell::<u16>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpInt => {
-                    let data = main_row.get_cell::<i32>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpInt64 => {
-                    let data = main_row.get_cell::<i64>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpUInt => {
-                    let data = main_row.get_cell::<u32>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpFloat => {
-                    let data = main_row.get_cell::<f32>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpDouble => {
-                    let data = main_row.get_cell::<f64>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpBool => {
-                    let data = main_row.get_cell::<bool>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpString => {
-                    let data = main_row.get_cell::<String>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpComplex => {
-                    let data = main_row
-                        .get_cell::<Complex<f32>>(column_name)
-                        .expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                GlueDataType::TpDComplex => {
-                    let data = main_row
-                        .get_cell::<Complex<f64>>(column_name)
-                        .expect("Failed");
-                    buf.push_str(&format!("{}", data));
-                }
-                _ => {
-                    let data = "Not implemented";
-                    buf.push_str(&format!("{}", data));
-                }
-            }
-            buf.push_str("\n");
-        }
-        buf.to_string()
-    }
-
-    fn read_var_col<
-        T: rubbl_casatables::CasaScalarData + Copy + std::fmt::Debug + std::fmt::Display,
-    >(
-        &mut self,
-        buf: &mut String,
-        column_name: &str,
-        row_num: u64,
-    ) {
-        let mut main_row = self.ms_table.get_row_reader().expect("Failed");
-        let _data = self.ms_table.read_row(&mut main_row, row_num).unwrap();
-        let mdata = main_row.get_cell::<Array1<T>>(column_name);
-        match mdata {
-            Ok(x) => buf.push_str(&format!("{}\n", x)),
-            Err(..) => {
-                let mdata = main_row.get_cell::<Array2<T>>(column_name);
-                match mdata {
-                    Ok(x) => buf.push_str(&format!("{}\n", x)),
-                    Err(..) => {
-                        let mdata = main_row.get_cell::<Array3<T>>(column_name);
-                        match mdata {
-                            Ok(x) => buf.push_str(&format!("{}\n", x)),
-                            Err(..) => buf.push_str("Failed to parse field.\n"),
+    /// Open the cell inspector on the row at a position in the rows that are shown, so the
+    /// matching rows under a filter.
+    ///
+    /// The axis selection is kept when moving between cells of the same shape.
+    pub fn inspect_cell(&mut self, position: u64) -> Result<(), AppError> {
+        if position >= self.n_visible_rows() {
+            return Ok(());
+        }
+        let row = self.row_at(position);
+        let column_name = self.columns[self.current_column].clone();
+        match CellInspector::new(&self.ms_name, self.ms_table.get()?, &column_name, row) {
+            Ok(mut inspector) => {
+                if let Some(previous) = &self.inspector {
+                    if previous.column == inspector.column && previous.shape() == inspector.shape()
+                    {
+                        for (axis, old) in inspector.axes.iter_mut().zip(previous.axes.iter()) {
+                            axis.start = old.start;
+                            axis.end = old.end;
                         }
+                        inspector.current_axis = previous.current_axis;
                     }
                 }
+                self.inspector = Some(inspector);
+                self.current_screen = CurrentScreen::Inspector;
+                Ok(())
             }
+            Err(e) => Err(AppError::view(
+                format!("inspect {} row {}", column_name, row),
+                e,
+            )),
         }
     }
 
-    pub fn read_array_value_into_buffer(
-        &mut self,
-        buf: &mut String,
-        column_name: &str,
-        row_start: u64,
-        row_end: u64,
-    ) -> String {
-        let col_desc = self.ms_table.get_col_desc(column_name).expect("Failed");
-        if !col_desc.is_fixed_shape() {
-            buf.push_str(&format!(":{:^5}: ", "ROW"));
-            buf.push_str("VALUE");
-            buf.push_str("\n");
-            for row_num in row_start..row_end {
-                buf.push_str(&format!(":{:>5}: ", row_num));
-                match col_desc.data_type() {
-                    GlueDataType::TpBool => self.read_var_col::<bool>(buf, column_name, row_num),
-                    GlueDataType::TpChar => self.read_var_col::<i8>(buf, column_name, row_num),
-                    GlueDataType::TpUChar => self.read_var_col::<u8>(buf, column_name, row_num),
-                    GlueDataType::TpShort => self.read_var_col::<i16>(buf, column_name, row_num),
-                    GlueDataType::TpUShort => self.read_var_col::<u16>(buf, column_name, row_num),
-                    GlueDataType::TpInt => self.read_var_col::<i32>(buf, column_name, row_num),
-                    GlueDataType::TpUInt => self.read_var_col::<u32>(buf, column_name, row_num),
-                    GlueDataType::TpInt64 => self.read_var_col::<i64>(buf, column_name, row_num),
-                    GlueDataType::TpFloat => self.read_var_col::<f32>(buf, column_name, row_num),
-                    GlueDataType::TpDouble => self.read_var_col::<f64>(buf, column_name, row_num),
-                    GlueDataType::TpComplex => {
-                        self.read_var_col::<Complex<f32>>(buf, column_name, row_num)
-                    }
-                    GlueDataType::TpDComplex => {
-                        self.read_var_col::<Complex<f64>>(buf, column_name, row_num)
-                    }
-                    GlueDataType::TpString => {
-                        let mut main_row = self.ms_table.get_row_reader().expect("Failed");
-                        let _data = self.ms_table.read_row(&mut main_row, row_num).unwrap();
-                        let mdata = main_row
-                            .get_cell::<Vec<String>>(column_name)
-                            .expect("Failed to parse string column.");
-                        buf.push_str(&format!("[{}]\n", mdata.join(", ")));
-                    }
-                    _ => {
-                        let data = format!("Not implemented for {}", col_desc.data_type());
-                        buf.push_str(&format!("{}\n", data));
-                    }
-                }
+    /// Move the cell inspector to the next or previous row that is shown.
+    pub fn inspect_next_cell(&mut self, forwards: bool) -> Result<(), AppError> {
+        let Some(row) = self.inspector.as_ref().map(|i| i.row) else {
+            return Ok(());
+        };
+        // The inspected row need not match the filter any more, e.g. after it was changed.
+        let (position, found) = match &self.filter {
+            Some(filter) => match filter.rows.binary_search(&row) {
+                Ok(p) => (p as u64, true),
+                Err(p) => (p as u64, false),
+            },
+            None => (row, true),
+        };
+        let next = if forwards {
+            Some(if found { position + 1 } else { position })
+        } else {
+            position.checked_sub(1)
+        };
+        match next {
+            Some(position) => self.inspect_cell(position),
+            None => Ok(()),
+        }
+    }
+
+    /// Data shown by the plot views: the compared columns when the comparison is shown, or else
+    /// the selected column. Also returns the number of antennas, if known.
+    fn visibilities(&self) -> (Visibilities, usize) {
+        let n_antennas = self.labels("ANTENNA1").map_or(0, |a| a.len());
+        let visibilities = match (&self.current_screen, &self.column_pair) {
+            (CurrentScreen::Comparison, Some(pair)) => Visibilities::Pair(pair.clone()),
+            _ => Visibilities::Column(self.columns[self.current_column].clone()),
+        };
+        (visibilities, n_antennas)
+    }
+
+    /// Ask the table loader for the data of a view of the current table and show the view, which
+    /// is filled in by `poll_table_loader`.
+    fn request_view(&mut self, description: String, view: ViewRequest, screen: CurrentScreen) {
+        self.table_loader.request(TableRequest::View {
+            ms_name: self.ms_name.clone(),
+            table_path: self.table_path(),
+            view,
+        });
+        self.reading_view = Some(description);
+        self.current_screen = screen;
+    }
+
+    /// Stop reading the data of a view that is being opened.
+    pub fn cancel_view(&mut self) {
+        if self.reading_view.take().is_some() {
+            self.table_loader.cancel();
+        }
+    }
+
+    /// Open the amplitude/phase against time plot for the selected complex column, or for the
+    /// compared columns.
+    pub fn open_plot(&mut self) -> Result<(), AppError> {
+        let (visibilities, n_antennas) = self.visibilities();
+        self.plot = None;
+        self.request_view(
+            format!("plot {}", visibilities.label()),
+            ViewRequest::Plot {
+                visibilities,
+                n_antennas,
+            },
+            CurrentScreen::Plot,
+        );
+        Ok(())
+    }
+
+    /// Re-read the plotted data after the baseline, correlation or channel selection changed.
+    pub fn reload_plot(&mut self) {
+        if let Some(plot) = self.plot.take() {
+            self.request_view(
+                format!("plot {}", plot.source.label()),
+                ViewRequest::ReloadPlot(Box::new(plot)),
+                CurrentScreen::Plot,
+            );
+        }
+    }
+
+    pub fn set_plot_quantity(&mut self, quantity: PlotQuantity) {
+        if let Some(plot) = self.plot.as_mut() {
+            plot.quantity = quantity;
+        }
+        self.reload_plot()
+    }
+
+    /// Open the time-frequency waterfall for the selected complex column, or for the compared
+    /// columns.
+    pub fn open_waterfall(&mut self) -> Result<(), AppError> {
+        let (visibilities, n_antennas) = self.visibilities();
+        self.waterfall = None;
+        self.request_view(
+            format!("show waterfall of {}", visibilities.label()),
+            ViewRequest::Waterfall {
+                visibilities,
+                n_antennas,
+            },
+            CurrentScreen::Waterfall,
+        );
+        Ok(())
+    }
+
+    /// Re-read the waterfall after the baseline or correlation selection changed.
+    pub fn reload_waterfall(&mut self) {
+        if let Some(waterfall) = self.waterfall.take() {
+            self.request_view(
+                format!("show waterfall of {}", waterfall.source.label()),
+                ViewRequest::ReloadWaterfall(Box::new(waterfall)),
+                CurrentScreen::Waterfall,
+            );
+        }
+    }
+
+    /// Open the UV coverage plot of the current table.
+    pub fn open_uv_plot(&mut self) -> Result<(), AppError> {
+        let station_names = self.labels("ANTENNA1").cloned().unwrap_or_default();
+        self.uv_plot = None;
+        self.request_view(
+            "plot UV coverage".to_string(),
+            ViewRequest::UvPlot { station_names },
+            CurrentScreen::UvCoverage,
+        );
+        Ok(())
+    }
+
+    /// Compute flag occupancy statistics of the current table and show them.
+    pub fn open_flag_stats(&mut self) -> Result<(), AppError> {
+        let station_names = self.labels("ANTENNA1").cloned().unwrap_or_default();
+        self.flag_stats = None;
+        self.flag_row = 0;
+        self.request_view(
+            "compute flag statistics".to_string(),
+            ViewRequest::FlagStats { station_names },
+            CurrentScreen::FlagStats,
+        );
+        Ok(())
+    }
+
+    /// Switch to the next or previous table of flag statistics.
+    pub fn next_flag_table(&mut self, forwards: bool) {
+        let n_tables = self.flag_stats.as_ref().map_or(1, |f| f.tables.len());
+        self.flag_table = if forwards {
+            (self.flag_table + 1) % n_tables
+        } else {
+            (self.flag_table + n_tables - 1) % n_tables
+        };
+        self.flag_row = 0;
+    }
+
+    /// Move the selected row of the flag statistics table.
+    pub fn move_flag_row(&mut self, amount: usize, forwards: bool) {
+        let n_rows = self
+            .flag_stats
+            .as_ref()
+            .and_then(|f| f.tables.get(self.flag_table))
+            .map_or(0, |t| t.rows.len());
+        if forwards {
+            self.flag_row = (self.flag_row + amount).min(n_rows.saturating_sub(1));
+        } else {
+            self.flag_row = self.flag_row.saturating_sub(amount);
+        }
+    }
+
+    /// Toggle sorting of the flag statistics between natural order and flagged percentage.
+    pub fn toggle_flag_sort(&mut self) {
+        self.flag_by_percentage = !self.flag_by_percentage;
+        if let Some(flag_stats) = self.flag_stats.as_mut() {
+            flag_stats.sort(self.flag_by_percentage);
+        }
+        self.flag_row = 0;
+    }
+
+    /// Start computing statistics of the selected column over rows `start_row..end_row`.
+    ///
+    /// The work is done in steps by `step_column_stats`, so progress can be drawn in between.
+    pub fn start_column_stats(&mut self, start_row: u64, end_row: u64) -> Result<(), AppError> {
+        let column_name = self.columns[self.current_column].clone();
+        let job = StatsJob::new(
+            self.ms_table.get()?,
+            &column_name,
+            start_row,
+            end_row,
+            self.filter.as_ref(),
+        )
+        .map_err(|e| AppError::view(format!("compute statistics of {}", column_name), e))?;
+        self.stats_job = Some(job);
+        self.column_stats = None;
+        self.current_screen = CurrentScreen::ColumnStats;
+        Ok(())
+    }
+
+    /// Open the prompt for the rows to compute statistics over, starting with the rows in view.
+    pub fn open_stats_range(&mut self) {
+        self.stats_range_input = format!(
+            "{}:{}",
+            self.tab_scroll,
+            self.tab_scroll + self.line_height as u64
+        );
+        self.current_screen = CurrentScreen::StatsRange;
+    }
+
+    /// Start computing statistics over the rows typed in the statistics prompt.
+    ///
+    /// With a filter, the rows index into the matching rows, as in the Column Values panel.
+    pub fn start_column_stats_in_range(&mut self) -> Result<(), AppError> {
+        self.current_screen = CurrentScreen::Main;
+        let range = parse_row_range(&self.stats_range_input).map_err(|e| {
+            AppError::view(
+                format!("compute statistics over rows {}", self.stats_range_input),
+                e,
+            )
+        })?;
+        self.start_column_stats(range.start, range.end.min(self.n_visible_rows()))
+    }
+
+    /// Process the next batch of rows of the running statistics job, if there is one.
+    pub fn step_column_stats(&mut self) -> Result<(), AppError> {
+        let Some(job) = self.stats_job.as_mut() else {
+            return Ok(());
+        };
+        if let Err(e) = job.step(self.ms_table.get()?) {
+            let column_name = job.column.clone();
+            self.stats_job = None;
+            return Err(AppError::view(
+                format!("compute statistics of {}", column_name),
+                e,
+            ));
+        }
+        if job.is_done() {
+            self.column_stats = self.stats_job.take().map(|j| j.finish());
+        }
+        Ok(())
+    }
+
+    /// Open the export prompt for the selected column, over the filtered rows if there is a filter.
+    pub fn open_export(&mut self) {
+        let Some(column_name) = self.columns.get(self.current_column) else {
+            return;
+        };
+        let table_name = &self.tables.nodes[self.shown_table].name;
+        let path = format!("{}_{}.csv", table_name, column_name);
+        self.export_form = Some(ExportForm::new(column_name, path));
+        self.export_message = None;
+        self.current_screen = CurrentScreen::Export;
+    }
+
+    /// Start exporting the columns in the export prompt to the file named there.
+    ///
+    /// The work is done in steps by `step_export`, so progress can be drawn in between.
+    pub fn start_export(&mut self) -> Result<(), AppError> {
+        let Some(form) = self.export_form.as_ref() else {
+            return Ok(());
+        };
+        let table = self.ms_table.get()?;
+        let job = form
+            .row_range()
+            .and_then(|positions| {
+                ExportJob::new(
+                    table,
+                    &form.column_names(),
+                    positions,
+                    self.filter.as_ref(),
+                    ExportFormat::from_path(&form.path),
+                    TextOptions {
+                        complex: form.complex,
+                        raw_values: self.raw_values,
+                    },
+                    Some(Path::new(&form.path)),
+                )
+            })
+            .map_err(|e| AppError::view(format!("export to {}", form.path), e))?;
+        self.export_job = Some(job);
+        Ok(())
+    }
+
+    /// Export the next batch of rows of the running export, if there is one.
+    pub fn step_export(&mut self) -> Result<(), AppError> {
+        let Some(job) = self.export_job.as_mut() else {
+            return Ok(());
+        };
+        let path = job
+            .path
+            .as_ref()
+            .map_or(String::new(), |p| p.display().to_string());
+        if let Err(e) = job.step(self.ms_table.get()?) {
+            self.cancel_export();
+            return Err(AppError::view(format!("export to {}", path), e));
+        }
+        if job.is_done() {
+            let job = self.export_job.take().expect("export job is running");
+            let written = job
+                .finish()
+                .map_err(|e| AppError::view(format!("export to {}", path), e))?;
+            self.export_message = Some(format!("Wrote {} to {}", written, path));
+        }
+        Ok(())
+    }
+
+    /// Stop the running export and remove the partly written file.
+    pub fn cancel_export(&mut self) {
+        if let Some(job) = self.export_job.take() {
+            job.cancel();
+        }
+    }
+
+    /// Compare the selected column of the current table with the same table in the second MS.
+    ///
+    /// The rows are compared in steps by `step_diff`, so the view can be used in between.
+    pub fn open_diff(&mut self) -> Result<(), AppError> {
+        let Some(other_ms) = self.compare_ms.clone() else {
+            return Err(AppError::view(
+                "compare Measurement Sets",
+                anyhow::anyhow!(
+                    "Start with two Measurement Sets to compare them, e.g. lofar-msexplorer a.ms b.ms"
+                ),
+            ));
+        };
+        if self.ms_differences.is_none() {
+            let differences = compare_structure(&self.ms_name, &other_ms)
+                .map_err(|e| AppError::view(format!("compare with {}", other_ms), e))?;
+            self.ms_differences = Some(differences);
+        }
+        let Some(column_name) = self.columns.get(self.current_column).cloned() else {
+            return Ok(());
+        };
+        // The same table in the second MS, e.g. b.ms/ANTENNA for a.ms/ANTENNA.
+        let path = self.table_path();
+        let other_path = format!("{}{}", other_ms, &path[self.ms_name.len()..]);
+        let view = DiffView::new(self.ms_table.get()?, &other_path, &column_name).map_err(|e| {
+            AppError::view(format!("compare {} with {}", column_name, other_path), e)
+        })?;
+        self.diff_view = Some(view);
+        self.current_screen = CurrentScreen::Diff;
+        self.update_diff_lines()
+    }
+
+    /// Compare the next batch of rows of the side-by-side view, if it is still running.
+    pub fn step_diff(&mut self) -> Result<(), AppError> {
+        let Some(view) = self.diff_view.as_mut() else {
+            return Ok(());
+        };
+        view.step(self.ms_table.get()?).map_err(|e| {
+            let column_name = view.column.clone();
+            view.job = None;
+            AppError::view(format!("compare {}", column_name), e)
+        })
+    }
+
+    /// Scroll the side-by-side view by a number of rows, negative to go up.
+    pub fn scroll_diff(&mut self, amount: i64) -> Result<(), AppError> {
+        if let Some(view) = self.diff_view.as_mut() {
+            view.scroll_by(self.ms_table.get()?, amount);
+        }
+        self.update_diff_lines()
+    }
+
+    /// Scroll the side-by-side view to the next or previous row that differs.
+    pub fn jump_diff(&mut self, forwards: bool) -> Result<(), AppError> {
+        if let Some(view) = self.diff_view.as_mut() {
+            view.jump(forwards);
+        }
+        self.update_diff_lines()
+    }
+
+    /// Format the rows in view of the side-by-side view, as in the Column Values panel.
+    fn update_diff_lines(&mut self) -> Result<(), AppError> {
+        let labels_apply = self.labels_apply();
+        let Some(view) = self.diff_view.as_mut() else {
+            return Ok(());
+        };
+        let table = self.ms_table.get()?;
+        let unit = if self.raw_values {
+            ColumnUnit::None
+        } else {
+            column_unit(table, &view.column)
+        };
+        let labels = if self.raw_values || !labels_apply {
+            None
+        } else {
+            self.foreign_keys.get(&view.column)
+        };
+        let display = DisplayOptions { unit, labels };
+        view.update_lines(table, &display, self.line_height as u64)
+            .map_err(|e| AppError::read_column(&view.column, e))
+    }
+
+    /// Open the prompt for two columns to compare, starting from the selected column.
+    pub fn open_compare_columns(&mut self) {
+        self.compare_input = match &self.column_pair {
+            Some(pair) => pair.label(),
+            None => {
+                let first = self
+                    .columns
+                    .get(self.current_column)
+                    .cloned()
+                    .unwrap_or_default();
+                // Suggest the usual calibration columns, e.g. DATA - CORRECTED_DATA.
+                let second = ["CORRECTED_DATA", "MODEL_DATA", "DATA"]
+                    .into_iter()
+                    .find(|c| *c != first && self.columns.iter().any(|name| name == c))
+                    .unwrap_or("MODEL_DATA");
+                format!("{} - {}", first, second)
             }
-            return buf.to_string();
-        }
-        let mut main_row = self.ms_table.get_row_reader().expect("Failed");
-        buf.push_str(&format!(":{:^5}: ", "ROW"));
-        buf.push_str("VALUE");
-        buf.push_str("\n");
-        for row_num in row_start..row_end {
-            self.ms_table
-                .read_row(&mut main_row, row_num)
-                .expect("Failed");
-
-            buf.push_str(&format!(":{:>5}: ", row_num));
-            match col_desc.data_type() {
-                GlueDataType::TpArrayChar | GlueDataType::TpChar => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<i8, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<i8, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayUChar | GlueDataType::TpUChar => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<u8, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<u8, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayShort | GlueDataType::TpShort => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<i16, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<i16, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayUShort | GlueDataType::TpUShort => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<u16, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<u16, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayInt | GlueDataType::TpInt => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<i32, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<i32, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayInt64 | GlueDataType::TpInt64 => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<i64, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<i64, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayUInt | GlueDataType::TpUInt => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<u32, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<u32, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayFloat | GlueDataType::TpFloat => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<f32, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<f32, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayDouble | GlueDataType::TpDouble => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<f64, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<f64, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
-                }
-                GlueDataType::TpArrayBool | GlueDataType::TpBool => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row.get_cell::<Vec<bool>>(column_name).expect("Failed");
-                            buf.push_str(&format!("{:?}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<bool, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {
-                            let data = main_row.get_cell::<Vec<bool>>(column_name).expect("Failed");
-                            buf.push_str(&format!("{:?}", data));
-                        }
-                    };
+        };
+        self.current_screen = CurrentScreen::CompareColumns;
+    }
+
+    /// Start comparing the columns typed in the prompt over all or the filtered rows.
+    ///
+    /// The work is done in steps by `step_comparison`, so progress can be drawn in between.
+    pub fn start_comparison(&mut self) -> Result<(), AppError> {
+        let action = format!("compare {}", self.compare_input.trim());
+        let pair =
+            ColumnPair::parse(&self.compare_input).map_err(|e| AppError::view(&action, e))?;
+        let station_names = self.labels("ANTENNA1").cloned().unwrap_or_default();
+        let job = ComparisonJob::new(
+            &self.ms_name,
+            self.ms_table.get()?,
+            &pair,
+            self.filter.as_ref(),
+            &station_names,
+            self.compare_per_row,
+        )
+        .map_err(|e| AppError::view(&action, e))?;
+        // The names as spelled in the table, for the plots of the pair.
+        self.column_pair = Some(job.pair.clone());
+        self.comparison_job = Some(job);
+        self.comparison = None;
+        self.comparison_table = 0;
+        self.comparison_row = 0;
+        self.current_screen = CurrentScreen::Comparison;
+        Ok(())
+    }
+
+    /// Process the next batch of rows of the running comparison, if there is one.
+    pub fn step_comparison(&mut self) -> Result<(), AppError> {
+        let Some(job) = self.comparison_job.as_mut() else {
+            return Ok(());
+        };
+        if let Err(e) = job.step(self.ms_table.get()?) {
+            let label = job.pair.label();
+            self.comparison_job = None;
+            return Err(AppError::view(format!("compare {}", label), e));
+        }
+        if job.is_done() {
+            self.comparison = self.comparison_job.take().map(|j| j.finish());
+        }
+        Ok(())
+    }
+
+    /// Switch to the next or previous table of the comparison.
+    pub fn next_comparison_table(&mut self, forwards: bool) {
+        let n_tables = self
+            .comparison
+            .as_ref()
+            .map_or(1, |c| c.tables.len())
+            .max(1);
+        self.comparison_table = if forwards {
+            (self.comparison_table + 1) % n_tables
+        } else {
+            (self.comparison_table + n_tables - 1) % n_tables
+        };
+        self.comparison_row = 0;
+    }
+
+    /// Move the selected row of the comparison table.
+    pub fn move_comparison_row(&mut self, amount: usize, forwards: bool) {
+        let n_rows = self
+            .comparison
+            .as_ref()
+            .and_then(|c| c.tables.get(self.comparison_table))
+            .map_or(0, |t| t.rows.len());
+        if forwards {
+            self.comparison_row = (self.comparison_row + amount).min(n_rows.saturating_sub(1));
+        } else {
+            self.comparison_row = self.comparison_row.saturating_sub(amount);
+        }
+    }
+
+    /// Open the keyword browser for the current table and the selected column.
+    ///
+    /// Column keywords are only shown when a column is selected.
+    pub fn open_keywords(&mut self) -> Result<(), AppError> {
+        let path = self.table_path();
+        let column_name = match self.currently_editing {
+            CurrentlyEditing::Table => None,
+            _ => self.columns.get(self.current_column).cloned(),
+        };
+        let keywords = KeywordTree::load(&path, self.ms_table.get()?, column_name.as_deref())
+            .map_err(|e| AppError::read_table(&path, e))?;
+        self.keywords = Some(keywords);
+        self.current_screen = CurrentScreen::Keywords;
+        Ok(())
+    }
+
+    /// Open the storage manager and on-disk layout view of the current table.
+    pub fn open_storage(&mut self) -> Result<(), AppError> {
+        let path = self.table_path();
+        let storage = StorageLayout::read(&path, self.ms_table.get()?)
+            .map_err(|e| AppError::view(format!("read the storage layout of {}", path), e))?;
+        self.storage = Some(storage);
+        self.current_screen = CurrentScreen::Storage;
+        Ok(())
+    }
+
+    /// Show the subtables of the selected table in the Tables panel.
+    ///
+    /// The tree is left alone while a table is being opened, so `shown_table` stays valid.
+    pub fn expand_table(&mut self) -> Result<(), AppError> {
+        if self.opening_table.is_some() {
+            return Ok(());
+        }
+        let path = self.table_path();
+        self.tables
+            .expand(self.current_table)
+            .map_err(|e| AppError::read_table(&path, e))
+    }
+
+    /// Hide the subtables of the selected table, or go up to its parent if they are hidden.
+    pub fn collapse_table(&mut self) -> Result<(), AppError> {
+        if self.tables.nodes[self.current_table].expanded {
+            if self.opening_table.is_none() {
+                self.tables.collapse(self.current_table);
+            }
+            Ok(())
+        } else if let Some(parent) = self.tables.parent(self.current_table) {
+            self.current_table = parent;
+            self.select(true)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Path of the table that is shown.
+    fn table_path(&self) -> String {
+        self.ms_table.path.clone()
+    }
+
+    /// Open the query bar to filter the rows of the current table.
+    pub fn open_query(&mut self) {
+        self.query_input = self
+            .filter
+            .as_ref()
+            .map(|f| f.query.clone())
+            .unwrap_or_default();
+        self.current_screen = CurrentScreen::Query;
+    }
+
+    /// Filter the rows of the current table with the query that was typed; an empty query
+    /// removes the filter.
+    pub fn apply_query(&mut self) -> Result<(), AppError> {
+        self.current_screen = CurrentScreen::Main;
+        if self.query_input.trim().is_empty() {
+            self.filter = None;
+        } else {
+            let no_labels = HashMap::new();
+            let labels = if self.labels_apply() {
+                &self.foreign_keys
+            } else {
+                &no_labels
+            };
+            let filter = RowFilter::new(&self.query_input, self.ms_table.get()?, labels)
+                .map_err(|e| AppError::view(format!("filter rows with {}", self.query_input), e))?;
+            self.filter = Some(filter);
+        }
+        self.tab_scroll = 0;
+        if let CurrentlyEditing::Table = self.currently_editing {
+            Ok(())
+        } else {
+            self.select(false)
+        }
+    }
+
+    /// Start searching the Tables or Fields list, whichever is active, by name.
+    pub fn open_search(&mut self) {
+        if let CurrentlyEditing::Information = self.currently_editing {
+            return;
+        }
+        self.search_input.clear();
+        self.update_search();
+        self.current_screen = CurrentScreen::Search;
+    }
+
+    /// Names in the list that is being searched.
+    fn search_names(&self) -> Vec<&str> {
+        match self.currently_editing {
+            CurrentlyEditing::Table => self.tables.nodes.iter().map(|n| n.name.as_str()).collect(),
+            _ => self.columns.iter().map(|c| c.as_str()).collect(),
+        }
+    }
+
+    /// Match the names against the search text after it was edited.
+    pub fn update_search(&mut self) {
+        let mut matches: Vec<(i64, usize, Vec<usize>)> = self
+            .search_names()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                let (score, positions) = fuzzy_match(&self.search_input, name)?;
+                Some((score, i, positions))
+            })
+            .collect();
+        // Stable sort, so equally good matches stay in list order.
+        matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+        self.search_matches = matches.into_iter().map(|(_, i, p)| (i, p)).collect();
+        self.search_selected = 0;
+    }
+
+    pub fn move_search_selection(&mut self, forwards: bool) {
+        if forwards {
+            self.search_selected =
+                (self.search_selected + 1).min(self.search_matches.len().saturating_sub(1));
+        } else {
+            self.search_selected = self.search_selected.saturating_sub(1);
+        }
+    }
+
+    /// Select the highlighted match and end the search.
+    pub fn accept_search(&mut self) -> Result<(), AppError> {
+        self.current_screen = CurrentScreen::Main;
+        let Some((index, _)) = self.search_matches.get(self.search_selected) else {
+            return Ok(());
+        };
+        match self.currently_editing {
+            CurrentlyEditing::Table => self.current_table = *index,
+            _ => self.current_column = *index,
+        }
+        self.select(true)
+    }
+
+    /// Open the prompt to jump to a row, a percentage of the rows or a time.
+    pub fn open_go_to(&mut self) {
+        self.goto_input.clear();
+        self.current_screen = CurrentScreen::GoTo;
+    }
+
+    /// Jump to the row, percentage (e.g. 50%) or UTC time (e.g. 2018-03-08T11:46:12) typed in
+    /// the go-to prompt.
+    ///
+    /// With a filter, this goes to the first matching row at or after the given row or time.
+    pub fn go_to(&mut self) -> Result<(), AppError> {
+        self.current_screen = CurrentScreen::Main;
+        let input = self.goto_input.trim().to_string();
+        if input.is_empty() {
+            return Ok(());
+        }
+        let n_rows = self.n_visible_rows();
+        let position = if let Some(percentage) = input.strip_suffix('%') {
+            let percentage: f64 = percentage
+                .trim()
+                .parse()
+                .map_err(|e| AppError::view(format!("go to {}", input), e))?;
+            (percentage.clamp(0.0, 100.0) / 100.0 * n_rows as f64) as u64
+        } else if let Ok(row) = input.parse::<u64>() {
+            match &self.filter {
+                Some(filter) => filter.rows.partition_point(|r| *r < row) as u64,
+                None => row,
+            }
+        } else {
+            self.time_position(&input)
+                .map_err(|e| AppError::view(format!("go to {}", input), e))?
+        };
+        self.scroll_to(position)
+    }
+
+    /// Position of the first row at or after a UTC time, found by bisecting on TIME in the MAIN
+    /// table and by a scan of TIME in subtables.
+    fn time_position(&mut self, input: &str) -> anyhow::Result<u64> {
+        if !self.columns.iter().any(|c| c == "TIME") {
+            anyhow::bail!("Table has no TIME column");
+        }
+        let scale = match column_unit(self.ms_table.get()?, "TIME") {
+            ColumnUnit::Epoch(scale, _) => scale,
+            _ => 1.0,
+        };
+        let n_rows = self.n_visible_rows();
+        if n_rows == 0 {
+            return Ok(0);
+        }
+        let first_row = self.row_at(0);
+        let first = self.ms_table.get()?.get_cell::<f64>("TIME", first_row)?;
+        let Some(target) = parse_epoch(input, first * scale) else {
+            anyhow::bail!("Expected a row number, a percentage or a UTC time");
+        };
+        let time_at = |app: &mut App, position: u64| -> anyhow::Result<f64> {
+            let row = app.row_at(position);
+            Ok(app.ms_table.get()?.get_cell::<f64>("TIME", row)? * scale)
+        };
+        // The MAIN table of a Measurement Set is sorted by time, so bisect it. The result is
+        // checked against its neighbours, in case the table is not sorted after all.
+        if self.shown_table == 0 {
+            let (mut low, mut high) = (0, n_rows);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if time_at(self, mid)? < target {
+                    low = mid + 1;
+                } else {
+                    high = mid;
                 }
-                GlueDataType::TpArrayString | GlueDataType::TpString => {
-                    let data = main_row.get_cell::<String>(column_name).expect("Failed");
-                    buf.push_str(&format!("{}", data));
+            }
+            let after = low == n_rows || time_at(self, low)? >= target;
+            let before = low == 0 || time_at(self, low - 1)? < target;
+            if after && before {
+                return Ok(low);
+            }
+        }
+        // Subtables such as POINTING need not be sorted: take the earliest time at or after the
+        // target, or the last row if there is none.
+        let times = self.ms_table.get()?.get_col_as_vec::<f64>("TIME")?;
+        let mut best: Option<(u64, f64)> = None;
+        for position in 0..n_rows {
+            let time = times[self.row_at(position) as usize] * scale;
+            if time >= target && best.is_none_or(|(_, b)| time < b) {
+                best = Some((position, time));
+            }
+        }
+        Ok(best.map_or(n_rows - 1, |(position, _)| position))
+    }
+
+    /// Show the Column Values panel from the given position in the (filtered) rows onwards.
+    pub fn scroll_to(&mut self, position: u64) -> Result<(), AppError> {
+        self.tab_scroll = position.min(self.last_position());
+        self.currently_editing = CurrentlyEditing::Information;
+        self.select(false)
+    }
+
+    /// Whether the labels of the `*_ID` and antenna columns apply to the current table.
+    ///
+    /// They are read from the subtables of the MS, so they describe MAIN and those subtables,
+    /// whose ids refer to each other, but not the subtables of a subtable.
+    fn labels_apply(&self) -> bool {
+        self.tables.nodes[self.shown_table].depth <= 1
+    }
+
+    /// Labels of the values of a column of the current table, e.g. station names for ANTENNA1.
+    pub fn labels(&self, column_name: &str) -> Option<&Vec<String>> {
+        if self.labels_apply() {
+            self.foreign_keys.get(column_name)
+        } else {
+            None
+        }
+    }
+
+    /// Table row at a position in the rows that are shown.
+    fn row_at(&self, position: u64) -> u64 {
+        match &self.filter {
+            Some(filter) => filter.rows[position as usize],
+            None => position,
+        }
+    }
+
+    /// The furthest the Column Values panel can be scrolled: its top row is then the last row.
+    pub fn last_position(&self) -> u64 {
+        self.n_visible_rows().saturating_sub(1)
+    }
+
+    /// Number of rows that can be shown: the rows matching the filter, or all rows.
+    pub fn n_visible_rows(&self) -> u64 {
+        self.filter
+            .as_ref()
+            .map_or(self.n_rows, |f| f.rows.len() as u64)
+    }
+
+    /// Ask the loading thread for the values of rows `start_row..end_row` of a column.
+    ///
+    /// With a filter, these index into the matching rows. The Column Values panel is updated by
+    /// `poll_loader` once they have been read.
+    fn request_values(&mut self, column_name: &str, start_row: u64, end_row: u64) {
+        let labels = if self.raw_values {
+            None
+        } else {
+            self.labels(column_name).cloned()
+        };
+        self.values_unit = if self.raw_values {
+            None
+        } else {
+            self.column_summaries[self.current_column].unit.clone()
+        };
+        self.loader.request(LoadRequest {
+            table_path: self.table_path(),
+            column: column_name.to_string(),
+            rows: match &self.filter {
+                Some(filter) => {
+                    let end = (end_row as usize).min(filter.rows.len());
+                    filter.rows[(start_row as usize).min(end)..end].to_vec()
                 }
-                GlueDataType::TpArrayComplex | GlueDataType::TpComplex => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<Complex<f32>, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<Complex<f32>, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
+                None => (start_row..end_row.min(self.n_rows)).collect(),
+            },
+            raw_values: self.raw_values,
+            labels,
+        });
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_busy()
+    }
+
+    /// Pick up values read by the loading thread and advance the loading spinner.
+    pub fn poll_loader(&mut self) -> Result<(), AppError> {
+        match self.loader.poll() {
+            Some(LoadOutcome::Loaded(text)) => self.text_buffer = text,
+            Some(LoadOutcome::Failed(e)) => {
+                self.text_buffer = String::new();
+                return Err(AppError::read_column(&self.columns[self.current_column], e));
+            }
+            None => {
+                if self.loader.is_busy() {
+                    self.spinner = self.spinner.wrapping_add(1);
                 }
-                GlueDataType::TpArrayDComplex | GlueDataType::TpDComplex => {
-                    match col_desc.shape().unwrap().len() {
-                        1 => {
-                            let data = main_row
-                                .get_cell::<Array<Complex<f64>, Ix1>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        2 => {
-                            let data = main_row
-                                .get_cell::<Array<Complex<f64>, Ix2>>(column_name)
-                                .expect("Failed");
-                            buf.push_str(&format!("{}", data));
-                        }
-                        _ => {}
-                    };
+            }
+        }
+        Ok(())
+    }
+
+    /// Pick up a table or view data read by the table loader.
+    pub fn poll_table_loader(&mut self) -> Result<(), AppError> {
+        let outcome = match self.table_loader.poll() {
+            Some(LoadOutcome::Loaded(outcome)) => outcome,
+            Some(LoadOutcome::Failed(e)) => {
+                if let Some(path) = self.opening_table.take() {
+                    // Select the table that is still shown again.
+                    self.current_table = self.shown_table;
+                    return Err(AppError::open_table(&path, e));
                 }
-                _ => {
-                    let data = format!("Not implemented for {}", col_desc.data_type());
-                    buf.push_str(&format!("{}", data));
+                let description = self.reading_view.take().unwrap_or_default();
+                self.current_screen = CurrentScreen::Main;
+                return Err(AppError::view(description, e));
+            }
+            None => {
+                if self.table_loader.is_busy() {
+                    self.spinner = self.spinner.wrapping_add(1);
                 }
+                return Ok(());
+            }
+        };
+        self.reading_view = None;
+        match outcome {
+            TableOutcome::Opened(summary) => {
+                self.opening_table = None;
+                self.switch_table(summary);
             }
-            buf.push_str("\n");
+            TableOutcome::Plot(plot) => self.plot = Some(plot),
+            TableOutcome::Waterfall(waterfall) => self.waterfall = Some(waterfall),
+            TableOutcome::UvPlot(uv_plot) => self.uv_plot = Some(uv_plot),
+            TableOutcome::FlagStats(mut flag_stats) => {
+                flag_stats.sort(self.flag_by_percentage);
+                self.flag_stats = Some(flag_stats);
+            }
+        }
+        Ok(())
+    }
+
+    /// Show an error in the error popup.
+    pub fn show_error(&mut self, error: AppError) {
+        self.error_message = error.to_string();
+        self.current_screen = CurrentScreen::Error;
+    }
+
+    /// Stop reading the values that are being loaded.
+    pub fn cancel_loading(&mut self) {
+        if self.loader.is_busy() {
+            self.loader.cancel();
+            self.text_buffer = "Loading cancelled.\n".to_string();
+        }
+    }
+
+    pub fn update_soltabs(&mut self) {
+        match &self.currently_editing {
+            _ => {}
         }
-        buf.to_string()
     }
 
-    pub fn select(&mut self, reset_view: bool) {
+    /// Show the table the table loader opened, which is the selected table.
+    fn switch_table(&mut self, summary: TableSummary) {
+        self.ms_table = LazyTable {
+            path: summary.path,
+            table: None,
+        };
+        self.shown_table = self.current_table;
+        self.n_rows = summary.n_rows;
+        self.filter = None;
+        self.columns = summary.columns.iter().map(|c| c.name.clone()).collect();
+        self.column_summaries = summary.columns;
+        self.current_column = 0;
+    }
+
+    pub fn select(&mut self, reset_view: bool) -> Result<(), AppError> {
+        // Until the selected table is open, the columns listed are those of the table shown.
+        if self.opening_table.is_some()
+            && !matches!(self.currently_editing, CurrentlyEditing::Table)
+        {
+            return Ok(());
+        }
         match &self.currently_editing {
             CurrentlyEditing::Information => {
                 let mut buf = "".to_string();
 
-                let column_name = self.columns[self.current_column].clone();
-                let col_desc = self.ms_table.get_col_desc(&column_name).expect("Failed");
-                let col_kw = self
-                    .ms_table
-                    .column_keyword_names(&column_name)
-                    .expect("Failed");
+                let col_desc = &self.column_summaries[self.current_column];
+                let column_name = col_desc.name.clone();
+                let is_scalar = col_desc.is_scalar;
                 buf.push_str(&format!("Column name: {}\n", column_name));
-                buf.push_str(&format!("Column data type: {}\n", col_desc.data_type()));
-                buf.push_str(&format!("Column keywords: {}\n", col_kw.join(", ")));
-                buf.push_str(&format!("Scalar: {}\n", col_desc.is_scalar()));
-                if !col_desc.is_scalar() {
-                    buf.push_str(&format!("Fixed shape: {}\n", col_desc.is_fixed_shape()));
+                buf.push_str(&format!("Column data type: {}\n", col_desc.data_type));
+                buf.push_str(&format!(
+                    "Column keywords: {}\n",
+                    col_desc.keywords.join(", ")
+                ));
+                buf.push_str(&format!("Scalar: {}\n", is_scalar));
+                if !is_scalar {
+                    buf.push_str(&format!("Fixed shape: {}\n", col_desc.is_fixed_shape));
                 }
                 self.text_buffer_head = buf;
-                let mut buf = "".to_string();
 
-                match col_desc.is_scalar() {
-                    true => {
-                        if self.ms_table.n_rows() < self.line_height.into() {
-                            self.read_scalar_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                0,
-                                self.ms_table.n_rows(),
-                            )
-                        } else {
-                            self.read_scalar_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                self.tab_scroll as u64,
-                                self.tab_scroll as u64 + self.line_height as u64,
-                            )
-                        }
-                    }
-                    false => {
-                        if self.ms_table.n_rows() < self.line_height.into() {
-                            self.read_array_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                self.text_scroll as u64,
-                                self.ms_table.n_rows(),
-                            )
-                        } else {
-                            self.read_array_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                self.tab_scroll as u64,
-                                self.tab_scroll as u64 + self.line_height as u64,
-                            )
-                        }
+                let n_rows = self.n_visible_rows();
+                let (start_row, end_row) = if n_rows < self.line_height.into() {
+                    if is_scalar {
+                        (0, n_rows)
+                    } else {
+                        (self.text_scroll as u64, n_rows)
                     }
+                } else {
+                    (
+                        self.tab_scroll as u64,
+                        self.tab_scroll as u64 + self.line_height as u64,
+                    )
                 };
-
-                self.text_buffer = buf;
+                self.request_values(&column_name, start_row, end_row);
             }
             CurrentlyEditing::Column => {
                 let mut buf = "".to_string();
 
-                let column_name = self.columns[self.current_column].clone();
-                let col_desc = self.ms_table.get_col_desc(&column_name).expect("Failed");
-                let col_kw = self
-                    .ms_table
-                    .column_keyword_names(&column_name)
-                    .expect("Failed");
+                let col_desc = &self.column_summaries[self.current_column];
+                let column_name = col_desc.name.clone();
+                let is_scalar = col_desc.is_scalar;
                 buf.push_str(&format!("Column name: {}\n", column_name));
-                buf.push_str(&format!("Column data type: {}\n", col_desc.data_type()));
-                buf.push_str(&format!("Column keywords: {}\n", col_kw.join(", ")));
-                buf.push_str(&format!("Scalar: {}\n", col_desc.is_scalar()));
-                if !col_desc.is_scalar() {
-                    buf.push_str(&format!("Fixed shape: {}\n", col_desc.is_fixed_shape()));
+                buf.push_str(&format!("Column data type: {}\n", col_desc.data_type));
+                buf.push_str(&format!(
+                    "Column keywords: {}\n",
+                    col_desc.keywords.join(", ")
+                ));
+                buf.push_str(&format!("Scalar: {}\n", is_scalar));
+                if !is_scalar {
+                    buf.push_str(&format!("Fixed shape: {}\n", col_desc.is_fixed_shape));
                 }
                 self.text_buffer_head = buf;
-                let mut buf = "".to_string();
 
-                match col_desc.is_scalar() {
-                    true => {
-                        if self.ms_table.n_rows() < self.line_height.into() {
-                            self.read_scalar_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                0,
-                                self.ms_table.n_rows(),
-                            )
-                        } else {
-                            self.read_scalar_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                0,
-                                self.line_height.into(),
-                            )
-                        }
-                    }
-                    false => {
-                        if self.ms_table.n_rows() < self.line_height.into() {
-                            self.read_array_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                self.text_scroll as u64,
-                                self.ms_table.n_rows(),
-                            )
-                        } else {
-                            self.read_array_value_into_buffer(
-                                &mut buf,
-                                &column_name,
-                                0,
-                                self.line_height.into(),
-                            )
-                        }
+                let n_rows = self.n_visible_rows();
+                let (start_row, end_row) = if n_rows < self.line_height.into() {
+                    if is_scalar {
+                        (0, n_rows)
+                    } else {
+                        (self.text_scroll as u64, n_rows)
                     }
+                } else {
+                    (0, self.line_height.into())
                 };
-                self.text_buffer = buf;
+                self.request_values(&column_name, start_row, end_row);
             }
             CurrentlyEditing::Table => {
-                let table_name = &self.tables[self.current_table];
-                let t = if table_name == "MAIN" {
-                    Table::open(format!("{}", self.ms_name), TableOpenMode::Read)
-                        .expect("Failed to read MS.")
-                } else {
-                    Table::open(
-                        format!("{}/{}", self.ms_name, table_name),
-                        TableOpenMode::Read,
-                    )
-                    .expect("Failed to read MS.")
-                };
-                self.ms_table = t;
-                self.columns = self.ms_table.column_names().expect("Failed");
-                self.current_column = 0;
+                // Opening can take a while, e.g. on a network file system, so the table loader
+                // opens the table first and `poll_table_loader` then switches to it.
+                let path = self.table_path();
+                self.table_loader.request(TableRequest::Open(path.clone()));
+                self.opening_table = Some(path);
             }
         }
         if reset_view {
             self.text_scroll = 0;
             self.tab_scroll = 0;
         }
+        Ok(())
     }
 }
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::mem;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use arrow::array::{
+    ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, Float64Array, Int16Array, Int32Array,
+    Int64Array, Int8Array, StringArray, StructArray, UInt16Array, UInt32Array, UInt8Array,
+};
+use arrow::buffer::NullBuffer;
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rubbl_casatables::{GlueDataType, Table};
+use rubbl_core::Complex;
+
+use crate::measures::{column_unit, ColumnUnit};
+use crate::reader::{Block, Cells, ColumnReader};
+
+/// Number of rows per record batch.
+const BATCH_ROWS: usize = 1024;
+
+/// Values of one column for a batch of rows, flattened in row-major order.
+enum Values {
+    Bool(Vec<bool>),
+    Int8(Vec<i8>),
+    UInt8(Vec<u8>),
+    Int16(Vec<i16>),
+    UInt16(Vec<u16>),
+    Int32(Vec<i32>),
+    UInt32(Vec<u32>),
+    Int64(Vec<i64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Complex64(Vec<Complex<f32>>),
+    Complex128(Vec<Complex<f64>>),
+    Utf8(Vec<String>),
+}
+
+/// Fields of the struct that holds a complex value.
+fn complex_fields(data_type: DataType) -> Fields {
+    Fields::from(vec![
+        Field::new("real", data_type.clone(), false),
+        Field::new("imag", data_type, false),
+    ])
+}
+
+fn complex_array<T, A>(values: &[Complex<T>], data_type: DataType) -> Result<ArrayRef>
+where
+    T: Copy,
+    A: From<Vec<T>> + arrow::array::Array + 'static,
+{
+    let real: Vec<T> = values.iter().map(|v| v.re).collect();
+    let imag: Vec<T> = values.iter().map(|v| v.im).collect();
+    let arrays: Vec<ArrayRef> = vec![Arc::new(A::from(real)), Arc::new(A::from(imag))];
+    Ok(Arc::new(StructArray::try_new(
+        complex_fields(data_type),
+        arrays,
+        None,
+    )?))
+}
+
+impl Values {
+    fn new(data_type: GlueDataType) -> Option<Self> {
+        let values = match data_type {
+            GlueDataType::TpBool | GlueDataType::TpArrayBool => Values::Bool(Vec::new()),
+            GlueDataType::TpChar | GlueDataType::TpArrayChar => Values::Int8(Vec::new()),
+            GlueDataType::TpUChar | GlueDataType::TpArrayUChar => Values::UInt8(Vec::new()),
+            GlueDataType::TpShort | GlueDataType::TpArrayShort => Values::Int16(Vec::new()),
+            GlueDataType::TpUShort | GlueDataType::TpArrayUShort => Values::UInt16(Vec::new()),
+            GlueDataType::TpInt | GlueDataType::TpArrayInt => Values::Int32(Vec::new()),
+            GlueDataType::TpUInt | GlueDataType::TpArrayUInt => Values::UInt32(Vec::new()),
+            GlueDataType::TpInt64 | GlueDataType::TpArrayInt64 => Values::Int64(Vec::new()),
+            GlueDataType::TpFloat | GlueDataType::TpArrayFloat => Values::Float32(Vec::new()),
+            GlueDataType::TpDouble | GlueDataType::TpArrayDouble => Values::Float64(Vec::new()),
+            GlueDataType::TpComplex | GlueDataType::TpArrayComplex => Values::Complex64(Vec::new()),
+            GlueDataType::TpDComplex | GlueDataType::TpArrayDComplex => {
+                Values::Complex128(Vec::new())
+            }
+            GlueDataType::TpString | GlueDataType::TpArrayString => Values::Utf8(Vec::new()),
+            _ => return None,
+        };
+        Some(values)
+    }
+
+    /// Arrow type of one element.
+    fn element_type(&self) -> DataType {
+        match self {
+            Values::Bool(_) => DataType::Boolean,
+            Values::Int8(_) => DataType::Int8,
+            Values::UInt8(_) => DataType::UInt8,
+            Values::Int16(_) => DataType::Int16,
+            Values::UInt16(_) => DataType::UInt16,
+            Values::Int32(_) => DataType::Int32,
+            Values::UInt32(_) => DataType::UInt32,
+            Values::Int64(_) => DataType::Int64,
+            Values::Float32(_) => DataType::Float32,
+            Values::Float64(_) => DataType::Float64,
+            Values::Complex64(_) => DataType::Struct(complex_fields(DataType::Float32)),
+            Values::Complex128(_) => DataType::Struct(complex_fields(DataType::Float64)),
+            Values::Utf8(_) => DataType::Utf8,
+        }
+    }
+
+    /// Append the elements of a cell; returns false if the cell could not be read, in which case
+    /// default values are appended in its place.
+    fn push(&mut self, block: &Block, index: usize, shape: &[usize]) -> Result<bool> {
+        match (self, block) {
+            (Values::Bool(v), Block::Bool(cells)) => push_cell(v, cells, index, shape),
+            (Values::Int8(v), Block::Char(cells)) => push_cell(v, cells, index, shape),
+            (Values::UInt8(v), Block::UChar(cells)) => push_cell(v, cells, index, shape),
+            (Values::Int16(v), Block::Short(cells)) => push_cell(v, cells, index, shape),
+            (Values::UInt16(v), Block::UShort(cells)) => push_cell(v, cells, index, shape),
+            (Values::Int32(v), Block::Int(cells)) => push_cell(v, cells, index, shape),
+            (Values::UInt32(v), Block::UInt(cells)) => push_cell(v, cells, index, shape),
+            (Values::Int64(v), Block::Int64(cells)) => push_cell(v, cells, index, shape),
+            (Values::Float32(v), Block::Float(cells)) => push_cell(v, cells, index, shape),
+            (Values::Float64(v), Block::Double(cells)) => push_cell(v, cells, index, shape),
+            (Values::Complex64(v), Block::Complex(cells)) => push_cell(v, cells, index, shape),
+            (Values::Complex128(v), Block::DComplex(cells)) => push_cell(v, cells, index, shape),
+            (Values::Utf8(v), Block::String(cells)) => push_cell(v, cells, index, shape),
+            _ => bail!("Unexpected data type"),
+        }
+    }
+
+    /// Arrow array of the values collected so far, leaving the collection empty.
+    fn take_array(&mut self) -> Result<ArrayRef> {
+        let array: ArrayRef = match self {
+            Values::Bool(v) => Arc::new(BooleanArray::from(mem::take(v))),
+            Values::Int8(v) => Arc::new(Int8Array::from(mem::take(v))),
+            Values::UInt8(v) => Arc::new(UInt8Array::from(mem::take(v))),
+            Values::Int16(v) => Arc::new(Int16Array::from(mem::take(v))),
+            Values::UInt16(v) => Arc::new(UInt16Array::from(mem::take(v))),
+            Values::Int32(v) => Arc::new(Int32Array::from(mem::take(v))),
+            Values::UInt32(v) => Arc::new(UInt32Array::from(mem::take(v))),
+            Values::Int64(v) => Arc::new(Int64Array::from(mem::take(v))),
+            Values::Float32(v) => Arc::new(Float32Array::from(mem::take(v))),
+            Values::Float64(v) => Arc::new(Float64Array::from(mem::take(v))),
+            Values::Complex64(v) => {
+                complex_array::<f32, Float32Array>(&mem::take(v), DataType::Float32)?
+            }
+            Values::Complex128(v) => {
+                complex_array::<f64, Float64Array>(&mem::take(v), DataType::Float64)?
+            }
+            Values::Utf8(v) => Arc::new(StringArray::from(mem::take(v))),
+        };
+        Ok(array)
+    }
+}
+
+fn push_cell<T: Clone + Default>(
+    values: &mut Vec<T>,
+    cells: &Cells<T>,
+    index: usize,
+    shape: &[usize],
+) -> Result<bool> {
+    match cells {
+        Cells::Scalar(scalars) => {
+            values.push(scalars[index].clone());
+            Ok(true)
+        }
+        _ => match cells.view(index) {
+            Some(cell) if cell.shape() == shape => {
+                values.extend(cell.iter().cloned());
+                Ok(true)
+            }
+            Some(cell) => bail!(
+                "cell of shape {:?} in a column exported with shape {:?}",
+                cell.shape(),
+                shape
+            ),
+            None => {
+                let n_elements: usize = shape.iter().product();
+                values.extend(std::iter::repeat_n(T::default(), n_elements));
+                Ok(false)
+            }
+        },
+    }
+}
+
+/// Collects the values of one column for a record batch.
+struct ColumnBuilder {
+    name: String,
+    /// Shape of the cells, empty for scalar columns.
+    shape: Vec<usize>,
+    values: Values,
+    /// Whether each row could be read; rows that could not are null.
+    valid: Vec<bool>,
+    /// Field metadata describing the unit of the values, e.g. that TIME holds MJD seconds.
+    metadata: HashMap<String, String>,
+}
+
+/// Field metadata for a column with the given unit.
+///
+/// Values are written raw, so epochs are described rather than converted: readers such as
+/// pandas or astropy can turn them into timestamps.
+fn unit_metadata(unit: &ColumnUnit) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    if let Some(name) = unit.unit() {
+        metadata.insert("unit".to_string(), name.to_string());
+    }
+    if let ColumnUnit::Epoch(_, frame) = unit {
+        metadata.insert("measure".to_string(), "epoch".to_string());
+        metadata.insert("ref".to_string(), frame.clone());
+        metadata.insert(
+            "description".to_string(),
+            format!(
+                "Epoch in {} as a Modified Julian Date in {}",
+                frame,
+                unit.unit().unwrap_or("s")
+            ),
+        );
+    }
+    metadata
+}
+
+impl ColumnBuilder {
+    /// Arrow field of the column: arrays become nested fixed-size lists, one level per axis.
+    fn field(&self) -> Field {
+        let mut data_type = self.values.element_type();
+        for n in self.shape.iter().rev() {
+            data_type =
+                DataType::FixedSizeList(Arc::new(Field::new("item", data_type, false)), *n as i32);
+        }
+        Field::new(&self.name, data_type, !self.shape.is_empty())
+            .with_metadata(self.metadata.clone())
+    }
+
+    fn take_array(&mut self) -> Result<ArrayRef> {
+        let mut array = self.values.take_array()?;
+        let valid = mem::take(&mut self.valid);
+        for (axis, n) in self.shape.iter().enumerate().rev() {
+            // Only the rows themselves can be null, i.e. the outermost list.
+            let nulls = if axis == 0 && valid.iter().any(|v| !v) {
+                Some(NullBuffer::from(valid.clone()))
+            } else {
+                None
+            };
+            let field = Arc::new(Field::new("item", array.data_type().clone(), false));
+            array = Arc::new(FixedSizeListArray::try_new(field, *n as i32, array, nulls)?);
+        }
+        Ok(array)
+    }
+}
+
+enum BatchWriter {
+    Arrow(FileWriter<Box<dyn Write + Send>>),
+    Parquet(ArrowWriter<Box<dyn Write + Send>>),
+}
+
+/// Writes columns of a table to an Arrow IPC file or a Parquet file, one row at a time.
+///
+/// Scalar columns map to the matching Arrow type, arrays to nested fixed-size lists with one
+/// level per axis, e.g. DATA to lists of channels of lists of correlations, and complex values
+/// to structs of a real and an imaginary part. Cells that cannot be read are null. Columns whose
+/// cells vary in shape are written with the shape of the first row and cannot have others.
+pub struct ColumnarWriter {
+    schema: SchemaRef,
+    columns: Vec<ColumnBuilder>,
+    n_buffered: usize,
+    pub n_rows: u64,
+    writer: BatchWriter,
+}
+
+impl ColumnarWriter {
+    pub fn new(
+        table: &mut Table,
+        reader: &mut ColumnReader,
+        columns: &[String],
+        first_row: Option<u64>,
+        writer: Box<dyn Write + Send>,
+        parquet: bool,
+    ) -> Result<Self> {
+        let mut builders = Vec::new();
+        for name in columns.iter() {
+            let desc = table.get_col_desc(name)?;
+            let Some(values) = Values::new(desc.data_type()) else {
+                bail!(
+                    "Column {} of type {} cannot be exported",
+                    name,
+                    desc.data_type()
+                );
+            };
+            let shape = if desc.is_scalar() {
+                Vec::new()
+            } else {
+                let first_shape = match first_row {
+                    Some(row) => reader.cell_shape(table, name, row)?,
+                    None => None,
+                };
+                match (first_shape, desc.shape()) {
+                    (Some(shape), _) => shape,
+                    // Column descriptions list the fastest varying axis first.
+                    (None, Some(shape)) => shape.iter().rev().map(|n| *n as usize).collect(),
+                    (None, None) => bail!(
+                        "The cell shape of column {} is unknown, as its first row is undefined",
+                        name
+                    ),
+                }
+            };
+            builders.push(ColumnBuilder {
+                name: name.clone(),
+                shape,
+                values,
+                valid: Vec::new(),
+                metadata: unit_metadata(&column_unit(table, name)),
+            });
+        }
+        let schema = Arc::new(Schema::new(
+            builders.iter().map(|b| b.field()).collect::<Vec<Field>>(),
+        ));
+        let writer = if parquet {
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            BatchWriter::Parquet(ArrowWriter::try_new(
+                writer,
+                Arc::clone(&schema),
+                Some(properties),
+            )?)
+        } else {
+            BatchWriter::Arrow(FileWriter::try_new(writer, &schema)?)
+        };
+        Ok(ColumnarWriter {
+            schema,
+            columns: builders,
+            n_buffered: 0,
+            n_rows: 0,
+            writer,
+        })
+    }
+
+    /// Add a row of the table, writing a record batch when enough rows have been collected.
+    pub fn write_row(
+        &mut self,
+        table: &mut Table,
+        reader: &mut ColumnReader,
+        row: u64,
+    ) -> Result<()> {
+        for column in self.columns.iter_mut() {
+            let (block, index) = reader.cell(table, &column.name, row)?;
+            let valid = column
+                .values
+                .push(block, index, &column.shape)
+                .with_context(|| format!("Row {} of {}", row, column.name))?;
+            column.valid.push(valid);
+        }
+        self.n_buffered += 1;
+        self.n_rows += 1;
+        if self.n_buffered >= BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        if self.n_buffered == 0 {
+            return Ok(());
+        }
+        let arrays = self
+            .columns
+            .iter_mut()
+            .map(|c| c.take_array())
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        let batch = RecordBatch::try_new(Arc::clone(&self.schema), arrays)?;
+        match &mut self.writer {
+            BatchWriter::Arrow(writer) => writer.write(&batch)?,
+            BatchWriter::Parquet(writer) => writer.write(&batch)?,
+        }
+        self.n_buffered = 0;
+        Ok(())
+    }
+
+    /// Write the remaining rows and the file footer.
+    pub fn finish(mut self) -> Result<()> {
+        self.write_batch()?;
+        let mut writer = match self.writer {
+            BatchWriter::Arrow(mut writer) => {
+                writer.finish()?;
+                writer.into_inner()?
+            }
+            BatchWriter::Parquet(writer) => writer.into_inner()?,
+        };
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use rubbl_casatables::Table;
+use rubbl_core::Complex;
+use serde_json::{json, Map, Value};
+
+use crate::flagstats::station_name;
+use crate::inspector::spectral_setup;
+use crate::query::RowFilter;
+use crate::reader::{CellValues, ColumnReader, JOB_CACHE_BYTES, JOB_ROWS_PER_STEP};
+
+/// How the values of two columns are combined.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum CompareOp {
+    /// First column minus second column.
+    Difference,
+    /// First column divided by second column.
+    Ratio,
+}
+
+impl CompareOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Difference => "-",
+            CompareOp::Ratio => "/",
+        }
+    }
+
+    pub fn apply(&self, a: Complex<f64>, b: Complex<f64>) -> Complex<f64> {
+        match self {
+            CompareOp::Difference => a - b,
+            CompareOp::Ratio => a / b,
+        }
+    }
+}
+
+/// Two columns of the same table and how they are combined, e.g. DATA - MODEL_DATA.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnPair {
+    pub first: String,
+    pub second: String,
+    pub op: CompareOp,
+}
+
+impl ColumnPair {
+    /// Parse a pair written as `FIRST - SECOND` or `FIRST / SECOND`.
+    pub fn parse(text: &str) -> Result<Self> {
+        for op in [CompareOp::Difference, CompareOp::Ratio] {
+            if let Some((first, second)) = text.split_once(op.symbol()) {
+                let (first, second) = (first.trim(), second.trim());
+                if first.is_empty() || second.is_empty() {
+                    break;
+                }
+                return Ok(ColumnPair {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                    op,
+                });
+            }
+        }
+        bail!(
+            "Expected two columns as FIRST - SECOND or FIRST / SECOND, not {}",
+            text
+        )
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {} {}", self.first, self.op.symbol(), self.second)
+    }
+
+    /// Check that both columns exist and that their cells have the same shape in the first row.
+    ///
+    /// Column names are matched without regard to case and then spelled as in the table.
+    pub fn check(&mut self, table: &mut Table, reader: &mut ColumnReader) -> Result<()> {
+        let columns = table.column_names()?;
+        for name in [&mut self.first, &mut self.second] {
+            match columns.iter().find(|c| c.eq_ignore_ascii_case(name)) {
+                Some(column) => *name = column.clone(),
+                None => bail!("Table has no column {}", name),
+            }
+        }
+        if table.n_rows() == 0 {
+            return Ok(());
+        }
+        let shape_first = reader.cell_shape(table, &self.first, 0)?;
+        let shape_second = reader.cell_shape(table, &self.second, 0)?;
+        if shape_first != shape_second {
+            bail!(
+                "{} has shape {:?}, but {} has shape {:?}",
+                self.first,
+                shape_first.unwrap_or_default(),
+                self.second,
+                shape_second.unwrap_or_default()
+            );
+        }
+        for name in [&self.first, &self.second] {
+            if reader.cell_values(table, name, 0)?.is_none() {
+                bail!("Column {} is not numeric", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sums of the combined values of a set of samples.
+#[derive(Clone, Copy, Default)]
+pub struct PairSum {
+    pub sum: Complex<f64>,
+    pub sum_amplitude: f64,
+    pub sum_sq_amplitude: f64,
+    pub count: u64,
+}
+
+impl PairSum {
+    fn add(&mut self, value: Complex<f64>) {
+        self.sum += value;
+        self.sum_amplitude += value.norm();
+        self.sum_sq_amplitude += value.norm_sqr();
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &PairSum) {
+        self.sum += other.sum;
+        self.sum_amplitude += other.sum_amplitude;
+        self.sum_sq_amplitude += other.sum_sq_amplitude;
+        self.count += other.count;
+    }
+
+    pub fn mean_amplitude(&self) -> f64 {
+        self.sum_amplitude / self.count as f64
+    }
+
+    pub fn rms_amplitude(&self) -> f64 {
+        (self.sum_sq_amplitude / self.count as f64).sqrt()
+    }
+
+    /// Phase of the mean value in degrees, e.g. the average phase of a gain.
+    pub fn mean_phase(&self) -> f64 {
+        self.sum.arg().to_degrees()
+    }
+}
+
+/// Sums of the combined values per row, baseline or channel.
+pub struct ComparisonTable {
+    pub title: &'static str,
+    pub rows: Vec<(String, PairSum)>,
+}
+
+/// Difference or ratio of two columns of one table, per row, baseline and channel.
+///
+/// Flagged samples and samples where either value is not finite are left out.
+pub struct ColumnComparison {
+    pub pair: ColumnPair,
+    pub total: PairSum,
+    pub n_flagged: u64,
+    /// Samples that were skipped because a value or the result is NaN or infinite.
+    pub n_skipped: u64,
+    pub filter: Option<(String, u64)>,
+    pub tables: Vec<ComparisonTable>,
+}
+
+impl ColumnComparison {
+    /// Compare the columns of `pair` over all rows, or those matching a filter.
+    pub fn compute(
+        ms_name: &str,
+        table: &mut Table,
+        pair: &ColumnPair,
+        filter: Option<&RowFilter>,
+        station_names: &[String],
+        per_row: bool,
+    ) -> Result<Self> {
+        let mut job = ComparisonJob::new(ms_name, table, pair, filter, station_names, per_row)?;
+        while !job.is_done() {
+            job.step(table)?;
+        }
+        Ok(job.finish())
+    }
+
+    /// Plain-text report.
+    pub fn to_text(&self) -> String {
+        let mut buf = format!("Columns: {}", self.pair.label());
+        if let Some((query, n_matching)) = &self.filter {
+            buf.push_str(&format!("   Rows matching {}: {}", query, n_matching));
+        }
+        buf.push('\n');
+        buf.push_str(&format!(
+            "Mean amplitude {:.6e}, RMS amplitude {:.6e}, phase of mean {:.3} deg over {} samples\n",
+            self.total.mean_amplitude(),
+            self.total.rms_amplitude(),
+            self.total.mean_phase(),
+            self.total.count
+        ));
+        if self.n_flagged > 0 {
+            buf.push_str(&format!("Left out {} flagged samples\n", self.n_flagged));
+        }
+        if self.n_skipped > 0 {
+            buf.push_str(&format!("Skipped {} non-finite samples\n", self.n_skipped));
+        }
+        for table in self.tables.iter() {
+            buf.push_str(&format!(
+                "\n{:<40} {:>14} {:>14} {:>12} {:>10}\n",
+                table.title, "Mean amp", "RMS amp", "Phase (deg)", "Samples"
+            ));
+            for (name, sum) in table.rows.iter() {
+                buf.push_str(&format!(
+                    "{:<40} {:>14.6e} {:>14.6e} {:>12.3} {:>10}\n",
+                    name,
+                    sum.mean_amplitude(),
+                    sum.rms_amplitude(),
+                    sum.mean_phase(),
+                    sum.count
+                ));
+            }
+        }
+        buf
+    }
+
+    pub fn to_json(&self) -> Value {
+        let sum_json = |s: &PairSum| {
+            // NaN cannot be written as JSON, e.g. for a baseline that is fully flagged.
+            let number = |v: f64| if v.is_finite() { json!(v) } else { Value::Null };
+            json!({
+                "mean_amplitude": number(s.mean_amplitude()),
+                "rms_amplitude": number(s.rms_amplitude()),
+                "mean_phase_deg": number(s.mean_phase()),
+                "samples": s.count,
+            })
+        };
+        let mut map = Map::new();
+        map.insert("columns".to_string(), json!(self.pair.label()));
+        map.insert("total".to_string(), sum_json(&self.total));
+        map.insert("flagged".to_string(), json!(self.n_flagged));
+        map.insert("skipped".to_string(), json!(self.n_skipped));
+        for table in self.tables.iter() {
+            let rows: Vec<Value> = table
+                .rows
+                .iter()
+                .map(|(name, s)| {
+                    let mut row = sum_json(s);
+                    row["name"] = json!(name);
+                    row
+                })
+                .collect();
+            map.insert(table.title.to_lowercase(), Value::Array(rows));
+        }
+        Value::Object(map)
+    }
+}
+
+/// Comparison of two columns in steps, so progress can be shown in between.
+pub struct ComparisonJob {
+    pub pair: ColumnPair,
+    pub next_row: u64,
+    pub end_row: u64,
+    /// Rows matching the active filter; the row numbers above then index into these.
+    rows: Option<Arc<Vec<u64>>>,
+    query: Option<String>,
+    reader: ColumnReader,
+    has_flag: bool,
+    has_baselines: bool,
+    station_names: Vec<String>,
+    chan_freq: Vec<f64>,
+    total: PairSum,
+    n_flagged: u64,
+    n_skipped: u64,
+    /// Sums per row, only collected when asked for, as there can be millions of rows.
+    row_sums: Option<Vec<(u64, PairSum)>>,
+    baselines: BTreeMap<(i32, i32), PairSum>,
+    channels: Vec<PairSum>,
+}
+
+impl ComparisonJob {
+    /// Start comparing the columns of `pair`; `per_row` adds a table with a line per row.
+    pub fn new(
+        ms_name: &str,
+        table: &mut Table,
+        pair: &ColumnPair,
+        filter: Option<&RowFilter>,
+        station_names: &[String],
+        per_row: bool,
+    ) -> Result<Self> {
+        // The reader holds blocks of both columns.
+        let mut reader = ColumnReader::new(2 * JOB_CACHE_BYTES);
+        let mut pair = pair.clone();
+        pair.check(table, &mut reader)?;
+        let columns = table.column_names()?;
+        let has = |name: &str| columns.iter().any(|c| c == name);
+        let rows = filter.map(|f| Arc::clone(&f.rows));
+        let end_row = rows.as_ref().map_or(table.n_rows(), |r| r.len() as u64);
+        let chan_freq = if table.n_rows() > 0 && has("DATA_DESC_ID") {
+            spectral_setup(ms_name, table, 0).unwrap_or_default().0
+        } else {
+            Vec::new()
+        };
+        Ok(ComparisonJob {
+            pair,
+            next_row: 0,
+            end_row,
+            rows,
+            query: filter.map(|f| f.query.clone()),
+            reader,
+            has_flag: has("FLAG"),
+            has_baselines: has("ANTENNA1") && has("ANTENNA2"),
+            station_names: station_names.to_vec(),
+            chan_freq,
+            total: PairSum::default(),
+            n_flagged: 0,
+            n_skipped: 0,
+            row_sums: per_row.then(Vec::new),
+            baselines: BTreeMap::new(),
+            channels: Vec::new(),
+        })
+    }
+
+    /// Fraction of the rows processed so far.
+    pub fn progress(&self) -> f64 {
+        if self.end_row == 0 {
+            1.0
+        } else {
+            self.next_row as f64 / self.end_row as f64
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_row >= self.end_row
+    }
+
+    /// Process the next batch of rows.
+    pub fn step(&mut self, table: &mut Table) -> Result<()> {
+        let end = (self.next_row + JOB_ROWS_PER_STEP).min(self.end_row);
+        for i in self.next_row..end {
+            let row = self.rows.as_ref().map_or(i, |r| r[i as usize]);
+            self.process_row(table, row)?;
+        }
+        self.next_row = end;
+        Ok(())
+    }
+
+    fn process_row(&mut self, table: &mut Table, row: u64) -> Result<()> {
+        let shape = self.reader.cell_shape(table, &self.pair.first, row)?;
+        let shape_second = self.reader.cell_shape(table, &self.pair.second, row)?;
+        if shape != shape_second {
+            bail!(
+                "Row {} of {} and {} have different shapes",
+                row,
+                self.pair.first,
+                self.pair.second
+            );
+        }
+        // Cells of (channel, correlation); other shapes are not broken down per channel.
+        let n_corr = match shape.as_deref() {
+            Some([_, n_corr]) => *n_corr,
+            _ => 0,
+        };
+        let values = |reader: &mut ColumnReader, table: &mut Table, name: &str| {
+            reader
+                .cell_values(table, name, row)
+                .map(|v| v.map(CellValues::into_complex).unwrap_or_default())
+        };
+        let first = values(&mut self.reader, table, &self.pair.first)?;
+        let second = values(&mut self.reader, table, &self.pair.second)?;
+        let flags = if self.has_flag {
+            match self.reader.cell_values(table, "FLAG", row)? {
+                Some(CellValues::Real(flags)) if flags.len() == first.len() => flags,
+                _ => Vec::new(),
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut row_sum = PairSum::default();
+        for (i, (a, b)) in first.iter().zip(second.iter()).enumerate() {
+            if flags.get(i).is_some_and(|f| *f != 0.0) {
+                self.n_flagged += 1;
+                continue;
+            }
+            let value = self.pair.op.apply(*a, *b);
+            if !value.re.is_finite() || !value.im.is_finite() {
+                self.n_skipped += 1;
+                continue;
+            }
+            row_sum.add(value);
+            if let Some(chan) = i.checked_div(n_corr) {
+                if self.channels.len() <= chan {
+                    self.channels.resize(chan + 1, PairSum::default());
+                }
+                self.channels[chan].add(value);
+            }
+        }
+        self.total.merge(&row_sum);
+        if let Some(row_sums) = self.row_sums.as_mut() {
+            row_sums.push((row, row_sum));
+        }
+        if self.has_baselines {
+            let antenna = |reader: &mut ColumnReader, table: &mut Table, name: &str| {
+                reader.cell_values(table, name, row).map(|v| match v {
+                    Some(CellValues::Real(v)) => v.first().map_or(-1, |a| *a as i32),
+                    _ => -1,
+                })
+            };
+            let antenna1 = antenna(&mut self.reader, table, "ANTENNA1")?;
+            let antenna2 = antenna(&mut self.reader, table, "ANTENNA2")?;
+            self.baselines
+                .entry((antenna1.min(antenna2), antenna1.max(antenna2)))
+                .or_default()
+                .merge(&row_sum);
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> ColumnComparison {
+        let mut tables = Vec::new();
+        if self.has_baselines {
+            tables.push(ComparisonTable {
+                title: "Baseline",
+                rows: self
+                    .baselines
+                    .into_iter()
+                    .map(|((a1, a2), s)| {
+                        let name = format!(
+                            "{}-{}",
+                            station_name(&self.station_names, a1),
+                            station_name(&self.station_names, a2)
+                        );
+                        (name, s)
+                    })
+                    .collect(),
+            });
+        }
+        if !self.channels.is_empty() {
+            tables.push(ComparisonTable {
+                title: "Channel",
+                rows: self
+                    .channels
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, s)| match self.chan_freq.get(i) {
+                        Some(f) => (format!("{} ({:.6} MHz)", i, f / 1e6), s),
+                        None => (i.to_string(), s),
+                    })
+                    .collect(),
+            });
+        }
+        if let Some(row_sums) = self.row_sums {
+            tables.push(ComparisonTable {
+                title: "Row",
+                rows: row_sums
+                    .into_iter()
+                    .map(|(row, s)| (row.to_string(), s))
+                    .collect(),
+            });
+        }
+        ColumnComparison {
+            pair: self.pair,
+            total: self.total,
+            n_flagged: self.n_flagged,
+            n_skipped: self.n_skipped,
+            filter: self
+                .query
+                .zip(self.rows)
+                .map(|(query, rows)| (query, rows.len() as u64)),
+            tables,
+        }
+    }
+}
@@ -0,0 +1,549 @@
+use anyhow::{bail, Context, Result};
+use rubbl_casatables::{Table, TableOpenMode};
+use rubbl_core::Complex;
+use serde_json::{json, Value};
+
+use crate::dump::{describe_table, table_names};
+use crate::measures::ColumnUnit;
+use crate::reader::{
+    ColumnReader, DisplayOptions, ElementValues, JOB_CACHE_BYTES, JOB_ROWS_PER_STEP,
+};
+
+/// Number of rows formatted for the side-by-side view.
+const VIEW_ROWS: u64 = 200;
+/// Longest value shown in a structural difference.
+const MAX_VALUE_CHARS: usize = 60;
+
+fn table_path(ms_name: &str, table_name: &str) -> String {
+    if table_name == "MAIN" {
+        ms_name.to_string()
+    } else {
+        format!("{}/{}", ms_name, table_name)
+    }
+}
+
+/// Compare the table trees, column sets, column descriptions, keywords and row counts of two
+/// Measurement Sets, and describe each difference on one line.
+pub fn compare_structure(ms_a: &str, ms_b: &str) -> Result<Vec<String>> {
+    let tables_a = table_names(ms_a)?;
+    let tables_b = table_names(ms_b)?;
+    let mut differences = Vec::new();
+    for table_name in tables_a.iter() {
+        if !tables_b.contains(table_name) {
+            differences.push(format!("{}: only in {}", table_name, ms_a));
+            continue;
+        }
+        let describe = |ms_name: &str| -> Result<Value> {
+            let path = table_path(ms_name, table_name);
+            let mut t = Table::open(&path, TableOpenMode::Read)
+                .with_context(|| format!("failed to open {}", path))?;
+            describe_table(&mut t).with_context(|| format!("failed to describe {}", path))
+        };
+        let a = describe(ms_a)?;
+        let b = describe(ms_b)?;
+        compare_values(table_name, &a, &b, ms_a, ms_b, &mut differences);
+    }
+    for table_name in tables_b.iter().filter(|t| !tables_a.contains(t)) {
+        differences.push(format!("{}: only in {}", table_name, ms_b));
+    }
+    Ok(differences)
+}
+
+/// Walk two JSON descriptions in step and record where they differ.
+fn compare_values(
+    path: &str,
+    a: &Value,
+    b: &Value,
+    ms_a: &str,
+    ms_b: &str,
+    differences: &mut Vec<String>,
+) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a.iter() {
+                let path = format!("{}/{}", path, key);
+                match b.get(key) {
+                    Some(other) => compare_values(&path, value, other, ms_a, ms_b, differences),
+                    None => differences.push(format!("{}: only in {}", path, ms_a)),
+                }
+            }
+            for key in b.keys().filter(|k| !a.contains_key(*k)) {
+                differences.push(format!("{}/{}: only in {}", path, key, ms_b));
+            }
+        }
+        _ if a != b => differences.push(format!(
+            "{}: {} vs {}",
+            path,
+            shorten(a.to_string()),
+            shorten(b.to_string())
+        )),
+        _ => {}
+    }
+}
+
+/// Cut long values, e.g. keyword arrays, short enough to fit on a line.
+fn shorten(text: String) -> String {
+    if text.chars().count() > MAX_VALUE_CHARS {
+        let mut short: String = text.chars().take(MAX_VALUE_CHARS).collect();
+        short.push('…');
+        short
+    } else {
+        text
+    }
+}
+
+/// How a row of a column differs between the two Measurement Sets.
+pub struct RowDiff {
+    pub row: u64,
+    /// Number of elements that differ, all of them if the cells have different shapes.
+    pub n_differing: u64,
+    pub n_elements: u64,
+    /// Largest absolute difference of numeric elements, NaN if there is none.
+    pub max_abs_diff: f64,
+    pub shape_differs: bool,
+}
+
+/// Row-by-row comparison of a column of the same table in two Measurement Sets.
+pub struct ColumnDiff {
+    pub column: String,
+    pub n_rows: (u64, u64),
+    /// Rows that differ, in row order.
+    pub rows: Vec<RowDiff>,
+    pub n_elements: u64,
+    pub n_differing: u64,
+    pub max_abs_diff: f64,
+}
+
+impl ColumnDiff {
+    fn new(column: &str, n_rows: (u64, u64)) -> Self {
+        ColumnDiff {
+            column: column.to_string(),
+            n_rows,
+            rows: Vec::new(),
+            n_elements: 0,
+            n_differing: 0,
+            max_abs_diff: f64::NAN,
+        }
+    }
+
+    /// Number of rows present in both tables, which are the rows that are compared.
+    pub fn n_compared(&self) -> u64 {
+        self.n_rows.0.min(self.n_rows.1)
+    }
+
+    /// Differences of a row, or None if it is the same in both tables.
+    pub fn row(&self, row: u64) -> Option<&RowDiff> {
+        self.rows
+            .binary_search_by_key(&row, |r| r.row)
+            .ok()
+            .map(|i| &self.rows[i])
+    }
+
+    /// First differing row after `row`, or before it when going backwards.
+    pub fn next_row(&self, row: u64, forwards: bool) -> Option<u64> {
+        let i = self.rows.partition_point(|r| r.row <= row);
+        if forwards {
+            self.rows.get(i).map(|r| r.row)
+        } else {
+            let i = self.rows.partition_point(|r| r.row < row);
+            i.checked_sub(1).map(|i| self.rows[i].row)
+        }
+    }
+
+    fn add(&mut self, diff: RowDiff) {
+        self.n_elements += diff.n_elements;
+        self.n_differing += diff.n_differing;
+        if diff.max_abs_diff > self.max_abs_diff || self.max_abs_diff.is_nan() {
+            self.max_abs_diff = diff.max_abs_diff;
+        }
+        if diff.n_differing > 0 {
+            self.rows.push(diff);
+        }
+    }
+
+    /// One-line summary of the comparison.
+    pub fn summary(&self) -> String {
+        let mut buf = format!(
+            "Column {}: {} of {} rows differ, {} of {} elements, max abs diff {}",
+            self.column,
+            self.rows.len(),
+            self.n_compared(),
+            self.n_differing,
+            self.n_elements,
+            format_diff(self.max_abs_diff)
+        );
+        if self.n_rows.0 != self.n_rows.1 {
+            buf.push_str(&format!(
+                " (row counts {} and {}; only the common rows are compared)",
+                self.n_rows.0, self.n_rows.1
+            ));
+        }
+        buf
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut buf = self.summary();
+        buf.push('\n');
+        if !self.rows.is_empty() {
+            buf.push_str(&format!(
+                "\n{:>10} {:>12} {:>12} {:>14}\n",
+                "Row", "Differing", "Elements", "Max abs diff"
+            ));
+        }
+        for diff in self.rows.iter() {
+            buf.push_str(&format!(
+                "{:>10} {:>12} {:>12} {:>14}{}\n",
+                diff.row,
+                diff.n_differing,
+                diff.n_elements,
+                format_diff(diff.max_abs_diff),
+                if diff.shape_differs {
+                    "  (shapes differ)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        buf
+    }
+
+    pub fn to_json(&self) -> Value {
+        // NaN cannot be written as JSON; no numeric difference is written as null.
+        let number = |v: f64| if v.is_nan() { Value::Null } else { json!(v) };
+        json!({
+            "column": self.column,
+            "n_rows": [self.n_rows.0, self.n_rows.1],
+            "n_differing_rows": self.rows.len(),
+            "n_elements": self.n_elements,
+            "n_differing_elements": self.n_differing,
+            "max_abs_diff": number(self.max_abs_diff),
+            "rows": self
+                .rows
+                .iter()
+                .map(|d| json!({
+                    "row": d.row,
+                    "n_differing": d.n_differing,
+                    "n_elements": d.n_elements,
+                    "max_abs_diff": number(d.max_abs_diff),
+                    "shape_differs": d.shape_differs,
+                }))
+                .collect::<Vec<Value>>(),
+        })
+    }
+}
+
+/// Format an absolute difference, or `-` if there is none.
+pub fn format_diff(value: f64) -> String {
+    if value.is_nan() {
+        "-".to_string()
+    } else {
+        format!("{:.6e}", value)
+    }
+}
+
+/// Whether two values differ; NaN equals NaN, as flagged data is often NaN in both.
+fn differs(a: Complex<f64>, b: Complex<f64>) -> bool {
+    let same = |x: f64, y: f64| x == y || (x.is_nan() && y.is_nan());
+    !same(a.re, b.re) || !same(a.im, b.im)
+}
+
+/// Comparison of a column row by row in steps, so progress can be shown in between.
+pub struct DiffJob {
+    pub column: String,
+    pub next_row: u64,
+    reader_a: ColumnReader,
+    reader_b: ColumnReader,
+    diff: ColumnDiff,
+}
+
+impl DiffJob {
+    pub fn new(a: &mut Table, b: &mut Table, column_name: &str) -> Result<Self> {
+        for (table, which) in [(&mut *a, "first"), (&mut *b, "second")] {
+            if !table.column_names()?.iter().any(|c| c == column_name) {
+                bail!("The {} table has no column {}", which, column_name);
+            }
+        }
+        Ok(DiffJob {
+            column: column_name.to_string(),
+            next_row: 0,
+            reader_a: ColumnReader::new(JOB_CACHE_BYTES),
+            reader_b: ColumnReader::new(JOB_CACHE_BYTES),
+            diff: ColumnDiff::new(column_name, (a.n_rows(), b.n_rows())),
+        })
+    }
+
+    pub fn n_rows(&self) -> u64 {
+        self.diff.n_compared()
+    }
+
+    /// Differences found so far.
+    pub fn diff(&self) -> &ColumnDiff {
+        &self.diff
+    }
+
+    /// Fraction of the rows compared so far.
+    pub fn progress(&self) -> f64 {
+        if self.n_rows() == 0 {
+            1.0
+        } else {
+            self.next_row as f64 / self.n_rows() as f64
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_row >= self.n_rows()
+    }
+
+    /// Compare the next batch of rows.
+    pub fn step(&mut self, a: &mut Table, b: &mut Table) -> Result<()> {
+        let end = (self.next_row + JOB_ROWS_PER_STEP).min(self.n_rows());
+        for row in self.next_row..end {
+            let diff = self.compare_row(a, b, row)?;
+            self.diff.add(diff);
+        }
+        self.next_row = end;
+        Ok(())
+    }
+
+    fn compare_row(&mut self, a: &mut Table, b: &mut Table, row: u64) -> Result<RowDiff> {
+        let mut diff = RowDiff {
+            row,
+            n_differing: 0,
+            n_elements: 0,
+            max_abs_diff: f64::NAN,
+            shape_differs: false,
+        };
+        let shape_a = self.reader_a.cell_shape(a, &self.column, row)?;
+        let shape_b = self.reader_b.cell_shape(b, &self.column, row)?;
+        if shape_a != shape_b {
+            let n_elements = |s: &Option<Vec<usize>>| s.as_ref().map_or(0, |s| s.iter().product());
+            diff.n_elements = n_elements(&shape_a).max(n_elements(&shape_b)) as u64;
+            // A cell that could not be read still counts as one differing cell.
+            diff.n_differing = diff.n_elements.max(1);
+            diff.shape_differs = true;
+            return Ok(diff);
+        }
+        let values_a = self.reader_a.cell_values(a, &self.column, row)?;
+        let values_b = self.reader_b.cell_values(b, &self.column, row)?;
+        match (values_a, values_b) {
+            (Some(values_a), Some(values_b)) => {
+                for (x, y) in values_a
+                    .into_complex()
+                    .into_iter()
+                    .zip(values_b.into_complex())
+                {
+                    diff.n_elements += 1;
+                    if differs(x, y) {
+                        diff.n_differing += 1;
+                        let abs_diff = (x - y).norm();
+                        if diff.max_abs_diff.is_nan() || abs_diff > diff.max_abs_diff {
+                            diff.max_abs_diff = abs_diff;
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Strings, or columns whose types cannot be compared numerically.
+                let elements_a =
+                    self.reader_a
+                        .cell_elements(a, &self.column, row, &ColumnUnit::None)?;
+                let elements_b =
+                    self.reader_b
+                        .cell_elements(b, &self.column, row, &ColumnUnit::None)?;
+                if let (Some(elements_a), Some(elements_b)) = (elements_a, elements_b) {
+                    if let (ElementValues::Text(x), ElementValues::Text(y)) =
+                        (elements_a.values, elements_b.values)
+                    {
+                        diff.n_elements = x.len() as u64;
+                        diff.n_differing =
+                            x.iter().zip(y.iter()).filter(|(x, y)| x != y).count() as u64;
+                    }
+                }
+            }
+        }
+        Ok(diff)
+    }
+
+    pub fn finish(self) -> ColumnDiff {
+        self.diff
+    }
+}
+
+/// Comparison of two Measurement Sets: their structure and one column row by row.
+pub struct MsDiff {
+    pub structure: Vec<String>,
+    pub column: ColumnDiff,
+}
+
+impl MsDiff {
+    /// Compare `ms_a` with `ms_b`, and `column_name` of the given table, MAIN by default, row by
+    /// row.
+    pub fn compute(
+        ms_a: &str,
+        ms_b: &str,
+        table_name: Option<&str>,
+        column_name: &str,
+    ) -> Result<Self> {
+        let structure = compare_structure(ms_a, ms_b)?;
+        let table_name = table_name.unwrap_or("MAIN");
+        let mut a = Table::open(table_path(ms_a, table_name), TableOpenMode::Read)?;
+        let mut b = Table::open(table_path(ms_b, table_name), TableOpenMode::Read)?;
+        let mut job = DiffJob::new(&mut a, &mut b, column_name)?;
+        while !job.is_done() {
+            job.step(&mut a, &mut b)?;
+        }
+        Ok(MsDiff {
+            structure,
+            column: job.finish(),
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut buf = String::new();
+        if self.structure.is_empty() {
+            buf.push_str("No differences in tables, columns, keywords or row counts\n");
+        } else {
+            buf.push_str(&format!(
+                "{} differences in tables, columns, keywords or row counts:\n",
+                self.structure.len()
+            ));
+            for difference in self.structure.iter() {
+                buf.push_str(&format!("  {}\n", difference));
+            }
+        }
+        buf.push('\n');
+        buf.push_str(&self.column.to_text());
+        buf
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "structure": self.structure,
+            "column": self.column.to_json(),
+        })
+    }
+}
+
+/// Side-by-side view of a column of the same table in two Measurement Sets.
+///
+/// The table of the first MS is the one open in the interface; the view holds the second.
+pub struct DiffView {
+    pub other_path: String,
+    pub other_table: Table,
+    pub column: String,
+    pub job: Option<DiffJob>,
+    pub result: Option<ColumnDiff>,
+    /// First row shown.
+    pub scroll: u64,
+    /// Scroll position of the list of structural differences.
+    pub structure_scroll: u16,
+    /// Formatted rows `first_row..` of both tables, one line per row.
+    pub first_row: u64,
+    pub lines: (Vec<String>, Vec<String>),
+    reader_a: ColumnReader,
+    reader_b: ColumnReader,
+}
+
+impl DiffView {
+    pub fn new(table: &mut Table, other_path: &str, column_name: &str) -> Result<Self> {
+        let mut other_table = Table::open(other_path, TableOpenMode::Read)
+            .with_context(|| format!("failed to open {}", other_path))?;
+        let job = DiffJob::new(table, &mut other_table, column_name)?;
+        Ok(DiffView {
+            other_path: other_path.to_string(),
+            other_table,
+            column: column_name.to_string(),
+            job: Some(job),
+            result: None,
+            scroll: 0,
+            structure_scroll: 0,
+            first_row: 0,
+            lines: (Vec::new(), Vec::new()),
+            reader_a: ColumnReader::new(JOB_CACHE_BYTES),
+            reader_b: ColumnReader::new(JOB_CACHE_BYTES),
+        })
+    }
+
+    /// Compare the next batch of rows of the running comparison, if there is one.
+    pub fn step(&mut self, table: &mut Table) -> Result<()> {
+        let Some(job) = self.job.as_mut() else {
+            return Ok(());
+        };
+        job.step(table, &mut self.other_table)?;
+        if job.is_done() {
+            self.result = self.job.take().map(|j| j.finish());
+        }
+        Ok(())
+    }
+
+    /// Differences found so far, or all of them once the comparison has finished.
+    pub fn column_diff(&self) -> Option<&ColumnDiff> {
+        self.result.as_ref().or(self.job.as_ref().map(|j| j.diff()))
+    }
+
+    /// Number of rows that can be scrolled through: those of the longer table.
+    pub fn n_rows(&self, table: &Table) -> u64 {
+        table.n_rows().max(self.other_table.n_rows())
+    }
+
+    pub fn scroll_by(&mut self, table: &Table, amount: i64) {
+        let last = self.n_rows(table).saturating_sub(1) as i64;
+        self.scroll = (self.scroll as i64 + amount).clamp(0, last.max(0)) as u64;
+    }
+
+    /// Scroll to the next or previous differing row found so far.
+    pub fn jump(&mut self, forwards: bool) {
+        if let Some(row) = self
+            .column_diff()
+            .and_then(|d| d.next_row(self.scroll, forwards))
+        {
+            self.scroll = row;
+        }
+    }
+
+    /// Format the rows in view, unless they already are.
+    pub fn update_lines(
+        &mut self,
+        table: &mut Table,
+        display: &DisplayOptions,
+        height: u64,
+    ) -> Result<()> {
+        let end = self.scroll + height;
+        if self.scroll >= self.first_row && end <= self.first_row + self.lines.0.len() as u64 {
+            return Ok(());
+        }
+        let rows: Vec<u64> = (self.scroll..self.scroll + height.max(VIEW_ROWS)).collect();
+        self.lines.0 = format_lines(&mut self.reader_a, table, &self.column, &rows, display)?;
+        self.lines.1 = format_lines(
+            &mut self.reader_b,
+            &mut self.other_table,
+            &self.column,
+            &rows,
+            display,
+        )?;
+        self.first_row = self.scroll;
+        Ok(())
+    }
+}
+
+/// Format rows as in the Column Values panel, one line per row; rows past the end are empty.
+fn format_lines(
+    reader: &mut ColumnReader,
+    table: &mut Table,
+    column_name: &str,
+    rows: &[u64],
+    display: &DisplayOptions,
+) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for &row in rows {
+        if row >= table.n_rows() {
+            lines.push(String::new());
+            continue;
+        }
+        let text = reader.format_rows(table, column_name, &[row], display, &|| false)?;
+        // Array cells span several lines; join them, skipping the header line.
+        let line: Vec<&str> = text.lines().skip(1).map(|l| l.trim()).collect();
+        lines.push(line.join(" "));
+    }
+    Ok(lines)
+}
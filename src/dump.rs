@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use rubbl_casatables::{GlueDataType, Table, TableOpenMode, TableRecord};
+use rubbl_core::Complex;
+use serde_json::{json, Map, Value};
+
+fn complex_to_json<T: Into<f64>>(c: Complex<T>) -> Value {
+    json!({"re": c.re.into(), "im": c.im.into()})
+}
+
+/// Convert a single keyword of a table or column keyword record to JSON.
+pub fn keyword_to_json(record: &mut TableRecord, name: &str, dtype: GlueDataType) -> Result<Value> {
+    let value = match dtype {
+        GlueDataType::TpBool => json!(record.get_field::<bool>(name)?),
+        GlueDataType::TpChar => json!(record.get_field::<i8>(name)?),
+        GlueDataType::TpUChar => json!(record.get_field::<u8>(name)?),
+        GlueDataType::TpShort => json!(record.get_field::<i16>(name)?),
+        GlueDataType::TpUShort => json!(record.get_field::<u16>(name)?),
+        GlueDataType::TpInt => json!(record.get_field::<i32>(name)?),
+        GlueDataType::TpUInt => json!(record.get_field::<u32>(name)?),
+        GlueDataType::TpInt64 => json!(record.get_field::<i64>(name)?),
+        GlueDataType::TpFloat => json!(record.get_field::<f32>(name)?),
+        GlueDataType::TpDouble => json!(record.get_field::<f64>(name)?),
+        GlueDataType::TpComplex => complex_to_json(record.get_field::<Complex<f32>>(name)?),
+        GlueDataType::TpDComplex => complex_to_json(record.get_field::<Complex<f64>>(name)?),
+        GlueDataType::TpString => json!(record.get_field::<String>(name)?),
+        GlueDataType::TpArrayBool => json!(record.get_field::<Vec<bool>>(name)?),
+        GlueDataType::TpArrayChar => json!(record.get_field::<Vec<i8>>(name)?),
+        GlueDataType::TpArrayUChar => json!(record.get_field::<Vec<u8>>(name)?),
+        GlueDataType::TpArrayShort => json!(record.get_field::<Vec<i16>>(name)?),
+        GlueDataType::TpArrayUShort => json!(record.get_field::<Vec<u16>>(name)?),
+        GlueDataType::TpArrayInt => json!(record.get_field::<Vec<i32>>(name)?),
+        GlueDataType::TpArrayUInt => json!(record.get_field::<Vec<u32>>(name)?),
+        GlueDataType::TpArrayInt64 => json!(record.get_field::<Vec<i64>>(name)?),
+        GlueDataType::TpArrayFloat => json!(record.get_field::<Vec<f32>>(name)?),
+        GlueDataType::TpArrayDouble => json!(record.get_field::<Vec<f64>>(name)?),
+        GlueDataType::TpArrayComplex => Value::Array(
+            record
+                .get_field::<Vec<Complex<f32>>>(name)?
+                .into_iter()
+                .map(complex_to_json)
+                .collect(),
+        ),
+        GlueDataType::TpArrayDComplex => Value::Array(
+            record
+                .get_field::<Vec<Complex<f64>>>(name)?
+                .into_iter()
+                .map(complex_to_json)
+                .collect(),
+        ),
+        GlueDataType::TpArrayString => json!(record.get_field::<Vec<String>>(name)?),
+        GlueDataType::TpRecord => {
+            let mut sub = record.get_field::<TableRecord>(name)?;
+            record_to_json(&mut sub)?
+        }
+        // Subtables are described separately in the table tree.
+        GlueDataType::TpTable => json!("Table"),
+        _ => json!(format!("Not implemented for {}", dtype)),
+    };
+    Ok(value)
+}
+
+/// Convert all keywords in a record to a JSON object of {name: {type, value}}.
+pub fn record_to_json(record: &mut TableRecord) -> Result<Value> {
+    let mut map = Map::new();
+    for info in record.get_keyword_info()? {
+        let value = keyword_to_json(record, &info.name, info.dtype)?;
+        map.insert(
+            info.name.clone(),
+            json!({"type": info.dtype.to_string(), "value": value}),
+        );
+    }
+    Ok(Value::Object(map))
+}
+
+/// Describe the columns, keywords and row count of a single table.
+pub fn describe_table(table: &mut Table) -> Result<Value> {
+    let mut columns = Map::new();
+    for column_name in table.column_names()? {
+        let col_desc = table.get_col_desc(&column_name)?;
+        let mut keywords = table.get_column_keyword_record(&column_name)?;
+        columns.insert(
+            column_name.clone(),
+            json!({
+                "data_type": col_desc.data_type().to_string(),
+                "scalar": col_desc.is_scalar(),
+                "fixed_shape": col_desc.is_fixed_shape(),
+                "shape": col_desc.shape(),
+                "keywords": record_to_json(&mut keywords)?,
+            }),
+        );
+    }
+    let mut keywords = table.get_keyword_record()?;
+    Ok(json!({
+        "n_rows": table.n_rows(),
+        "keywords": record_to_json(&mut keywords)?,
+        "columns": Value::Object(columns),
+    }))
+}
+
+/// Tables of a Measurement Set: MAIN followed by its subtables.
+pub fn table_names(ms_name: &str) -> Result<Vec<String>> {
+    let mut t = Table::open(ms_name, TableOpenMode::Read)
+        .with_context(|| format!("failed to open {}", ms_name))?;
+    let mut tables = vec!["MAIN".to_string()];
+    tables.extend(t.table_keyword_names()?);
+    Ok(tables)
+}
+
+/// Describe the MAIN table and the given subtables of a Measurement Set.
+pub fn describe_ms(ms_name: &str, tables: &[String]) -> Result<Value> {
+    let mut description = Map::new();
+    for table_name in tables {
+        let path = if table_name == "MAIN" {
+            ms_name.to_string()
+        } else {
+            format!("{}/{}", ms_name, table_name)
+        };
+        let mut t = Table::open(&path, TableOpenMode::Read)
+            .with_context(|| format!("failed to open {}", path))?;
+        let table_description =
+            describe_table(&mut t).with_context(|| format!("failed to describe {}", path))?;
+        description.insert(table_name.clone(), table_description);
+    }
+    Ok(json!({"name": ms_name, "tables": Value::Object(description)}))
+}
@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use rubbl_casatables::{Table, TableOpenMode};
+use rubbl_core::Complex;
+use serde_json::{json, Map, Value};
+
+use crate::diff::format_diff;
+use crate::flagstats::station_name;
+use crate::inspector::spectral_setup;
+use crate::reader::{CellValues, ColumnReader, JOB_CACHE_BYTES};
+
+/// Size of the fixed part of the header at the start of a Dysco file.
+const HEADER_SIZE: usize = 49;
+
+/// Compression settings of a Dysco storage manager, read from the header of its file.
+pub struct DyscoParameters {
+    pub data_bits: u8,
+    pub weight_bits: u8,
+    pub distribution: u8,
+    pub normalization: u8,
+    pub fit_to_maximum: bool,
+    pub student_t_nu: f64,
+    pub truncation: f64,
+    pub rows_per_block: u32,
+    pub version: (u16, u16),
+}
+
+impl DyscoParameters {
+    /// Read the header of a Dysco file, e.g. table.f3 of a compressed MS.
+    pub fn read(path: &Path) -> Result<Self> {
+        // Only the header is needed; the rest of the file holds the compressed data.
+        let mut file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut data = [0u8; HEADER_SIZE];
+        if let Err(err) = file.read_exact(&mut data) {
+            if err.kind() == ErrorKind::UnexpectedEof {
+                bail!("{} is too short for a Dysco header", path.display());
+            }
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+        // The header is written field by field in little-endian order, without padding.
+        let u32_at = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        let u16_at = |i: usize| u16::from_le_bytes(data[i..i + 2].try_into().unwrap());
+        let f64_at = |i: usize| f64::from_le_bytes(data[i..i + 8].try_into().unwrap());
+        Ok(DyscoParameters {
+            rows_per_block: u32_at(12),
+            version: (u16_at(24), u16_at(26)),
+            data_bits: data[28],
+            weight_bits: data[29],
+            fit_to_maximum: data[30] != 0,
+            distribution: data[31],
+            normalization: data[32],
+            student_t_nu: f64_at(33),
+            truncation: f64_at(41),
+        })
+    }
+
+    pub fn distribution_name(&self) -> &'static str {
+        match self.distribution {
+            0 => "Gaussian",
+            1 => "Uniform",
+            2 => "StudentsT",
+            3 => "TruncatedGaussian",
+            _ => "unknown",
+        }
+    }
+
+    pub fn normalization_name(&self) -> &'static str {
+        match self.normalization {
+            0 => "AF",
+            1 => "RF",
+            2 => "Row",
+            _ => "unknown",
+        }
+    }
+
+    /// Describe the settings that apply to a data column, or to a weight column.
+    pub fn describe(&self, is_weight: bool) -> String {
+        if is_weight {
+            return format!("Bit rate: {} bits per weight", self.weight_bits);
+        }
+        let mut buf = format!(
+            "Bit rate: {} bits per value\nDistribution: {}",
+            self.data_bits,
+            self.distribution_name()
+        );
+        match self.distribution {
+            2 => buf.push_str(&format!(" (nu = {})", self.student_t_nu)),
+            3 => buf.push_str(&format!(" (truncation = {} sigma)", self.truncation)),
+            _ => {}
+        }
+        buf.push_str(&format!(
+            "\nNormalisation: {}\nFit to maximum: {}\nRows per block: {}\nDysco version: {}.{}",
+            self.normalization_name(),
+            self.fit_to_maximum,
+            self.rows_per_block,
+            self.version.0,
+            self.version.1
+        ));
+        buf
+    }
+}
+
+/// Squared differences between decompressed and reference values.
+#[derive(Clone, Copy, Default)]
+pub struct ErrorSum {
+    pub sum_sq_error: f64,
+    pub sum_sq_reference: f64,
+    pub count: u64,
+}
+
+impl ErrorSum {
+    fn add(&mut self, value: Complex<f64>, reference: Complex<f64>) {
+        self.sum_sq_error += (value - reference).norm_sqr();
+        self.sum_sq_reference += reference.norm_sqr();
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &ErrorSum) {
+        self.sum_sq_error += other.sum_sq_error;
+        self.sum_sq_reference += other.sum_sq_reference;
+        self.count += other.count;
+    }
+
+    pub fn rms(&self) -> f64 {
+        (self.sum_sq_error / self.count as f64).sqrt()
+    }
+
+    /// RMS error relative to the RMS of the reference values, or NaN if those are all zero.
+    pub fn relative_rms(&self) -> f64 {
+        if self.sum_sq_reference == 0.0 {
+            return f64::NAN;
+        }
+        (self.sum_sq_error / self.sum_sq_reference).sqrt()
+    }
+}
+
+/// Format a relative error as a percentage, or "-" if it is not known.
+fn format_percentage(value: f64) -> String {
+    if value.is_nan() {
+        "-".to_string()
+    } else {
+        format!("{:.4}%", 100.0 * value)
+    }
+}
+
+/// Quantisation error of a Dysco-compressed column compared with an uncompressed copy.
+pub struct DyscoComparison {
+    pub column: String,
+    pub total: ErrorSum,
+    /// Samples that were skipped because either value is NaN or infinite.
+    pub n_skipped: u64,
+    pub tables: Vec<(&'static str, Vec<(String, ErrorSum)>)>,
+}
+
+impl DyscoComparison {
+    /// Compare a column of `ms_name` row by row with the same column of `reference_name`.
+    ///
+    /// Both Measurement Sets must have the same rows in the same order, as is the case when one is
+    /// a compressed copy of the other.
+    pub fn compute(
+        ms_name: &str,
+        reference_name: &str,
+        column_name: &str,
+        station_names: &[String],
+    ) -> Result<Self> {
+        let mut table = Table::open(ms_name, TableOpenMode::Read)?;
+        let mut reference = Table::open(reference_name, TableOpenMode::Read)?;
+        if table.n_rows() != reference.n_rows() {
+            bail!(
+                "{} has {} rows, but the reference {} has {}",
+                ms_name,
+                table.n_rows(),
+                reference_name,
+                reference.n_rows()
+            );
+        }
+        let (chan_freq, _) = if table.n_rows() > 0 {
+            spectral_setup(ms_name, &mut table, 0).unwrap_or_default()
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let mut total = ErrorSum::default();
+        let mut n_skipped = 0;
+        let mut baselines: BTreeMap<(i32, i32), ErrorSum> = BTreeMap::new();
+        let mut channels: Vec<ErrorSum> = Vec::new();
+
+        let mut reader = ColumnReader::new(JOB_CACHE_BYTES);
+        let mut reference_reader = ColumnReader::new(JOB_CACHE_BYTES);
+        for row in 0..table.n_rows() {
+            let antenna = |reader: &mut ColumnReader, table: &mut Table, name: &str| {
+                reader.cell_values(table, name, row).map(|v| match v {
+                    Some(CellValues::Real(v)) => v.first().map_or(-1, |a| *a as i32),
+                    _ => -1,
+                })
+            };
+            let antenna1 = antenna(&mut reader, &mut table, "ANTENNA1")?;
+            let antenna2 = antenna(&mut reader, &mut table, "ANTENNA2")?;
+            if antenna(&mut reference_reader, &mut reference, "ANTENNA1")? != antenna1
+                || antenna(&mut reference_reader, &mut reference, "ANTENNA2")? != antenna2
+            {
+                bail!("Row {} has a different baseline in the reference", row);
+            }
+            let shape = reader.cell_shape(&mut table, column_name, row)?;
+            if reference_reader.cell_shape(&mut reference, column_name, row)? != shape {
+                bail!("Row {} has a different shape in the reference", row);
+            }
+            // Cells of (channel, correlation); other shapes are not broken down per channel.
+            let n_corr = match shape.as_deref() {
+                Some([_, n_corr]) => *n_corr,
+                _ => 0,
+            };
+            let values = |reader: &mut ColumnReader, table: &mut Table| {
+                reader
+                    .cell_values(table, column_name, row)
+                    .map(|v| v.map(CellValues::into_complex).unwrap_or_default())
+            };
+            let values_ms = values(&mut reader, &mut table)?;
+            let values_reference = values(&mut reference_reader, &mut reference)?;
+
+            let mut row_sum = ErrorSum::default();
+            for (i, (value, reference_value)) in
+                values_ms.iter().zip(values_reference.iter()).enumerate()
+            {
+                let finite = [value.re, value.im, reference_value.re, reference_value.im]
+                    .iter()
+                    .all(|v| v.is_finite());
+                if !finite {
+                    n_skipped += 1;
+                    continue;
+                }
+                row_sum.add(*value, *reference_value);
+                if let Some(chan) = i.checked_div(n_corr) {
+                    if channels.len() <= chan {
+                        channels.resize(chan + 1, ErrorSum::default());
+                    }
+                    channels[chan].add(*value, *reference_value);
+                }
+            }
+            total.merge(&row_sum);
+            baselines
+                .entry((antenna1.min(antenna2), antenna1.max(antenna2)))
+                .or_default()
+                .merge(&row_sum);
+        }
+
+        let tables = vec![
+            (
+                "Baseline",
+                baselines
+                    .into_iter()
+                    .map(|((a1, a2), e)| {
+                        (
+                            format!(
+                                "{}-{}",
+                                station_name(station_names, a1),
+                                station_name(station_names, a2)
+                            ),
+                            e,
+                        )
+                    })
+                    .collect(),
+            ),
+            (
+                "Channel",
+                channels
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, e)| match chan_freq.get(i) {
+                        Some(f) => (format!("{} ({:.6} MHz)", i, f / 1e6), e),
+                        None => (i.to_string(), e),
+                    })
+                    .collect(),
+            ),
+        ];
+        Ok(DyscoComparison {
+            column: column_name.to_string(),
+            total,
+            n_skipped,
+            tables,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&format!(
+            "Column {}: RMS error {} ({} of the RMS of the reference) over {} samples\n",
+            self.column,
+            format_diff(self.total.rms()),
+            format_percentage(self.total.relative_rms()),
+            self.total.count
+        ));
+        if self.n_skipped > 0 {
+            buf.push_str(&format!("Skipped {} non-finite samples\n", self.n_skipped));
+        }
+        for (title, rows) in self.tables.iter() {
+            buf.push_str(&format!("\nRMS error per {}:\n", title.to_lowercase()));
+            for (name, error) in rows.iter() {
+                buf.push_str(&format!(
+                    "  {:<40} {:>14} {:>10}\n",
+                    name,
+                    format_diff(error.rms()),
+                    format_percentage(error.relative_rms())
+                ));
+            }
+        }
+        buf
+    }
+
+    pub fn to_json(&self) -> Value {
+        let error_json = |e: &ErrorSum| {
+            json!({
+                "rms_error": e.rms(),
+                "relative_rms_error": e.relative_rms(),
+                "samples": e.count,
+            })
+        };
+        let mut map = Map::new();
+        map.insert("column".to_string(), json!(self.column));
+        map.insert("total".to_string(), error_json(&self.total));
+        map.insert("skipped".to_string(), json!(self.n_skipped));
+        for (title, rows) in self.tables.iter() {
+            let rows: Vec<Value> = rows
+                .iter()
+                .map(|(name, e)| {
+                    let mut row = error_json(e);
+                    row["name"] = json!(name);
+                    row
+                })
+                .collect();
+            map.insert(title.to_lowercase(), Value::Array(rows));
+        }
+        Value::Object(map)
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// Errors that can occur while exploring a Measurement Set.
+#[derive(Debug)]
+pub enum AppError {
+    /// A table or subtable could not be opened, e.g. because it is missing or locked.
+    OpenTable { path: String, source: anyhow::Error },
+    /// The column names or keywords of a table could not be read.
+    ReadTable { path: String, source: anyhow::Error },
+    /// The description or keywords of a column could not be read.
+    ReadColumn {
+        column: String,
+        source: anyhow::Error,
+    },
+    /// A view could not be opened or updated, e.g. a plot of a column that is not complex.
+    View {
+        action: String,
+        source: anyhow::Error,
+    },
+}
+
+impl AppError {
+    pub fn open_table(path: &str, source: impl Into<anyhow::Error>) -> Self {
+        AppError::OpenTable {
+            path: path.to_string(),
+            source: source.into(),
+        }
+    }
+
+    pub fn read_table(path: &str, source: impl Into<anyhow::Error>) -> Self {
+        AppError::ReadTable {
+            path: path.to_string(),
+            source: source.into(),
+        }
+    }
+
+    pub fn read_column(column: &str, source: impl Into<anyhow::Error>) -> Self {
+        AppError::ReadColumn {
+            column: column.to_string(),
+            source: source.into(),
+        }
+    }
+
+    /// Failure of a view; `action` completes the sentence "Failed to ...".
+    pub fn view(action: impl Into<String>, source: impl Into<anyhow::Error>) -> Self {
+        AppError::View {
+            action: action.into(),
+            source: source.into(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The alternate format of anyhow errors includes their causes.
+        match self {
+            AppError::OpenTable { path, source } => {
+                write!(f, "Failed to open table {}: {:#}", path, source)
+            }
+            AppError::ReadTable { path, source } => {
+                write!(f, "Failed to read table {}: {:#}", path, source)
+            }
+            AppError::ReadColumn { column, source } => {
+                write!(f, "Failed to read column {}: {:#}", column, source)
+            }
+            AppError::View { action, source } => {
+                write!(f, "Failed to {}: {:#}", action, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        let source = match self {
+            AppError::OpenTable { source, .. }
+            | AppError::ReadTable { source, .. }
+            | AppError::ReadColumn { source, .. }
+            | AppError::View { source, .. } => source,
+        };
+        Some(source.as_ref())
+    }
+}
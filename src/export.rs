@@ -0,0 +1,747 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use rubbl_casatables::Table;
+use rubbl_core::Complex;
+
+use crate::columnar::ColumnarWriter;
+use crate::measures::{column_unit, ColumnUnit};
+use crate::npy::{NpyElement, NpyWriter};
+use crate::plot::is_complex;
+use crate::query::RowFilter;
+use crate::reader::{
+    CellElements, ColumnReader, ElementValues, JOB_CACHE_BYTES, JOB_ROWS_PER_STEP,
+};
+
+/// Rows exported per step; fewer than for other jobs, as text output has a line per element.
+const ROWS_PER_STEP: u64 = JOB_ROWS_PER_STEP / 10;
+
+/// File format of an export.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    /// A single column as a NumPy array.
+    Npy,
+    /// Several columns as NumPy arrays in one archive, with the row numbers as `row`.
+    Npz,
+    /// Apache Arrow IPC file, also known as Feather version 2.
+    Arrow,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Format matching the extension of a file name, CSV if it is not known.
+    pub fn from_path(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.ends_with(".tsv") {
+            ExportFormat::Tsv
+        } else if path.ends_with(".npy") {
+            ExportFormat::Npy
+        } else if path.ends_with(".npz") {
+            ExportFormat::Npz
+        } else if path.ends_with(".arrow") || path.ends_with(".feather") {
+            ExportFormat::Arrow
+        } else if path.ends_with(".parquet") || path.ends_with(".pq") {
+            ExportFormat::Parquet
+        } else {
+            ExportFormat::Csv
+        }
+    }
+}
+
+/// How complex values are split into two fields.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ComplexFormat {
+    /// Real and imaginary part.
+    ReIm,
+    /// Amplitude and phase in degrees.
+    AmpPhase,
+}
+
+impl ComplexFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComplexFormat::ReIm => "real/imaginary",
+            ComplexFormat::AmpPhase => "amplitude/phase",
+        }
+    }
+
+    fn suffixes(&self) -> [&'static str; 2] {
+        match self {
+            ComplexFormat::ReIm => ["real", "imag"],
+            ComplexFormat::AmpPhase => ["amp", "phase_deg"],
+        }
+    }
+
+    fn split(&self, value: Complex<f64>) -> (f64, f64) {
+        match self {
+            ComplexFormat::ReIm => (value.re, value.im),
+            ComplexFormat::AmpPhase => (value.norm(), value.arg().to_degrees()),
+        }
+    }
+}
+
+/// How values are written to CSV and TSV files.
+#[derive(Clone, Copy, Debug)]
+pub struct TextOptions {
+    pub complex: ComplexFormat,
+    /// Write epochs such as TIME as raw values instead of UTC timestamps.
+    pub raw_values: bool,
+}
+
+/// Input of the export prompt in the interface.
+pub struct ExportForm {
+    /// Comma-separated column names.
+    pub columns: String,
+    /// Range of the rows shown as start:end, or empty for all of them.
+    pub rows: String,
+    pub path: String,
+    pub complex: ComplexFormat,
+    /// Field being edited: 0 for the columns, 1 for the rows, 2 for the file name, 3 for the
+    /// complex format.
+    pub field: usize,
+}
+
+impl ExportForm {
+    pub fn new(column_name: &str, path: String) -> Self {
+        ExportForm {
+            columns: column_name.to_string(),
+            rows: String::new(),
+            path,
+            complex: ComplexFormat::ReIm,
+            field: 2,
+        }
+    }
+
+    /// Text being edited, or None when the complex format is selected.
+    pub fn input(&mut self) -> Option<&mut String> {
+        match self.field {
+            0 => Some(&mut self.columns),
+            1 => Some(&mut self.rows),
+            2 => Some(&mut self.path),
+            _ => None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % 4;
+    }
+
+    pub fn toggle_complex(&mut self) {
+        self.complex = match self.complex {
+            ComplexFormat::ReIm => ComplexFormat::AmpPhase,
+            ComplexFormat::AmpPhase => ComplexFormat::ReIm,
+        };
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        split_column_names(&self.columns)
+    }
+
+    /// Positions of the rows to export among the rows shown.
+    pub fn row_range(&self) -> Result<Range<u64>> {
+        if self.rows.trim().is_empty() {
+            Ok(0..u64::MAX)
+        } else {
+            parse_row_range(&self.rows)
+        }
+    }
+}
+
+/// Split a comma-separated list of column names.
+pub fn split_column_names(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Parse a range of rows written as `start:end`, with the end excluded; either may be left out.
+pub fn parse_row_range(text: &str) -> Result<Range<u64>> {
+    let Some((start, end)) = text.split_once(':') else {
+        bail!("Expected a row range as start:end, not {}", text);
+    };
+    let parse = |s: &str, default: u64| -> Result<u64> {
+        let s = s.trim();
+        if s.is_empty() {
+            Ok(default)
+        } else {
+            s.parse()
+                .with_context(|| format!("Invalid row number {} in {}", s, text))
+        }
+    };
+    Ok(parse(start, 0)?..parse(end, u64::MAX)?)
+}
+
+/// Names of the index columns written for cells with the given number of axes.
+fn index_names(n_axes: usize) -> Vec<String> {
+    match n_axes {
+        0 => Vec::new(),
+        1 => vec!["index".to_string()],
+        2 => vec!["channel".to_string(), "correlation".to_string()],
+        _ => (0..n_axes).map(|i| format!("axis{}", i)).collect(),
+    }
+}
+
+/// Shape that cells of the given shapes broadcast to, aligning their last axes.
+///
+/// Axes of length one are repeated to match the other cells, as for NumPy arrays.
+fn broadcast_shape<'a>(shapes: impl Iterator<Item = &'a [usize]>) -> Option<Vec<usize>> {
+    let mut result: Vec<usize> = Vec::new();
+    for shape in shapes {
+        if shape.len() > result.len() {
+            let mut padded = vec![1; shape.len() - result.len()];
+            padded.extend_from_slice(&result);
+            result = padded;
+        }
+        let offset = result.len() - shape.len();
+        for (i, n) in shape.iter().enumerate() {
+            let current = &mut result[offset + i];
+            if *current == 1 {
+                *current = *n;
+            } else if *n != 1 && *n != *current {
+                return None;
+            }
+        }
+    }
+    Some(result)
+}
+
+/// Quote a text field if it contains the delimiter, quotes or line breaks.
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Format a complex component with the precision of the column.
+fn format_component(value: f64, single_precision: bool) -> String {
+    if single_precision {
+        (value as f32).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV or TSV output, with one line per element of the cells of all columns in a row.
+///
+/// Array cells are flattened with the row number and the position of the element in the cell
+/// as index columns. Cells of different columns in the same row are combined by aligning their
+/// last axes, so that e.g. WEIGHT_SPECTRUM and FLAG line up with DATA, while a scalar such as
+/// TIME is repeated for every element. Epochs are written as UTC timestamps unless raw values
+/// are asked for.
+struct TextOutput {
+    writer: Box<dyn Write>,
+    /// Unit of each column, used to decode epochs.
+    units: Vec<ColumnUnit>,
+    delimiter: char,
+    complex: ComplexFormat,
+    n_axes: usize,
+    n_lines: u64,
+}
+
+impl TextOutput {
+    /// Write the header line; the first row decides the index columns and checks the columns.
+    fn new(
+        table: &mut Table,
+        reader: &mut ColumnReader,
+        columns: &[String],
+        first_row: Option<u64>,
+        mut writer: Box<dyn Write>,
+        delimiter: char,
+        options: TextOptions,
+    ) -> Result<Self> {
+        let units: Vec<ColumnUnit> = columns
+            .iter()
+            .map(|column| match column_unit(table, column) {
+                unit @ ColumnUnit::Epoch(..) if !options.raw_values => unit,
+                _ => ColumnUnit::None,
+            })
+            .collect();
+        let mut n_axes = 0;
+        if let Some(first_row) = first_row {
+            let mut cells = Vec::new();
+            for (column, unit) in columns.iter().zip(units.iter()) {
+                match reader.cell_elements(table, column, first_row, unit)? {
+                    Some(cell) => cells.push(cell),
+                    None => bail!(
+                        "Export is not available for column {} of type {}",
+                        column,
+                        table.get_col_desc(column)?.data_type()
+                    ),
+                }
+            }
+            n_axes = row_shape(columns, &cells, first_row)?.len();
+        }
+
+        let mut header = vec!["row".to_string()];
+        header.extend(index_names(n_axes));
+        for column in columns.iter() {
+            if is_complex(table.get_col_desc(column)?.data_type()) {
+                for suffix in options.complex.suffixes() {
+                    header.push(format!("{}_{}", column, suffix));
+                }
+            } else {
+                header.push(column.clone());
+            }
+        }
+        let header: Vec<String> = header.iter().map(|h| quote(h, delimiter)).collect();
+        writeln!(writer, "{}", header.join(&delimiter.to_string()))?;
+
+        Ok(TextOutput {
+            writer,
+            units,
+            delimiter,
+            complex: options.complex,
+            n_axes,
+            n_lines: 0,
+        })
+    }
+
+    fn write_row(&mut self, columns: &[String], row: u64, cells: &[CellElements]) -> Result<()> {
+        let mut shape = row_shape(columns, cells, row)?;
+        if shape.len() > self.n_axes {
+            bail!(
+                "Row {} has cells with {} axes, but the first row exported has {}",
+                row,
+                shape.len(),
+                self.n_axes
+            );
+        }
+        let mut padded = vec![1; self.n_axes - shape.len()];
+        padded.append(&mut shape);
+        let shape = padded;
+
+        let delimiter = self.delimiter;
+        let n_elements: usize = shape.iter().product();
+        let mut index = vec![0; shape.len()];
+        let mut line = String::new();
+        for _ in 0..n_elements {
+            line.clear();
+            line.push_str(&row.to_string());
+            for i in index.iter() {
+                line.push(delimiter);
+                line.push_str(&i.to_string());
+            }
+            for cell in cells.iter() {
+                let element = element_index(&cell.shape, &index);
+                match &cell.values {
+                    ElementValues::Text(values) => {
+                        line.push(delimiter);
+                        if let Some(value) = values.get(element) {
+                            line.push_str(&quote(value, delimiter));
+                        }
+                    }
+                    ElementValues::Complex {
+                        values,
+                        single_precision,
+                    } => {
+                        let parts = values.get(element).map(|v| self.complex.split(*v));
+                        for part in [parts.map(|p| p.0), parts.map(|p| p.1)] {
+                            line.push(delimiter);
+                            if let Some(part) = part {
+                                line.push_str(&format_component(part, *single_precision));
+                            }
+                        }
+                    }
+                }
+            }
+            writeln!(self.writer, "{}", line)?;
+            self.n_lines += 1;
+
+            // Advance the index in row-major order.
+            for axis in (0..index.len()).rev() {
+                index[axis] += 1;
+                if index[axis] < shape[axis] {
+                    break;
+                }
+                index[axis] = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// NumPy output, with one array of shape (rows, cell shape) per column.
+///
+/// The arrays are written one after the other, so the rows are read once for every column.
+struct NumPyOutput {
+    writer: NpyWriter,
+    /// Data type and cell shape of each column.
+    arrays: Vec<(&'static str, Vec<usize>)>,
+    buf: Vec<u8>,
+}
+
+enum Output {
+    Text(TextOutput),
+    NumPy(NumPyOutput),
+    Columnar(ColumnarWriter),
+}
+
+/// Export of columns to a file in steps, so progress can be shown in between.
+pub struct ExportJob {
+    pub columns: Vec<String>,
+    pub start_row: u64,
+    pub end_row: u64,
+    pub next_row: u64,
+    /// Column being written to a NumPy file; text output covers all columns at once.
+    pub column_index: usize,
+    /// Rows matching the active filter; the row numbers above then index into these.
+    rows: Option<Arc<Vec<u64>>>,
+    reader: ColumnReader,
+    /// Output file, or None when writing to standard output.
+    pub path: Option<PathBuf>,
+    output: Output,
+}
+
+impl ExportJob {
+    /// Start exporting `columns` over a range of rows to `path`, or to standard output.
+    ///
+    /// With a filter, the range refers to the matching rows rather than to all rows. Without
+    /// columns, all columns of the table are exported.
+    pub fn new(
+        table: &mut Table,
+        columns: &[String],
+        positions: Range<u64>,
+        filter: Option<&RowFilter>,
+        format: ExportFormat,
+        text_options: TextOptions,
+        path: Option<&Path>,
+    ) -> Result<Self> {
+        let column_names = table.column_names()?;
+        if column_names.is_empty() {
+            bail!("The table has no columns to export");
+        }
+        let columns: Vec<String> = if columns.is_empty() {
+            column_names.clone()
+        } else {
+            columns
+                .iter()
+                .map(|name| {
+                    column_names
+                        .iter()
+                        .find(|c| c.eq_ignore_ascii_case(name))
+                        .cloned()
+                        .with_context(|| format!("Column {} not found", name))
+                })
+                .collect::<Result<_>>()?
+        };
+        // Checked before the output file is created, so a bad request leaves no empty file.
+        if format == ExportFormat::Npy && columns.len() != 1 {
+            bail!(
+                "A .npy file holds a single array, but {} columns were selected; use npz for \
+                 several columns",
+                columns.len()
+            );
+        }
+
+        let rows = filter.map(|f| Arc::clone(&f.rows));
+        let n_rows = rows.as_ref().map_or(table.n_rows(), |r| r.len() as u64);
+        let end_row = positions.end.min(n_rows);
+        let start_row = positions.start.min(end_row);
+        let first_row = if start_row < end_row {
+            Some(rows.as_ref().map_or(start_row, |r| r[start_row as usize]))
+        } else {
+            None
+        };
+        let mut reader = ColumnReader::new(JOB_CACHE_BYTES);
+
+        let writer: Box<dyn Write + Send> = match path {
+            Some(path) => {
+                Box::new(BufWriter::new(File::create(path).with_context(|| {
+                    format!("failed to create {}", path.display())
+                })?))
+            }
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        let output = match format {
+            ExportFormat::Csv | ExportFormat::Tsv => {
+                let delimiter = if format == ExportFormat::Tsv {
+                    '\t'
+                } else {
+                    ','
+                };
+                Output::Text(TextOutput::new(
+                    table,
+                    &mut reader,
+                    &columns,
+                    first_row,
+                    writer,
+                    delimiter,
+                    text_options,
+                )?)
+            }
+            ExportFormat::Npy | ExportFormat::Npz => {
+                let Some(first_row) = first_row else {
+                    bail!("No rows to export");
+                };
+                let archive = format == ExportFormat::Npz;
+                let mut writer = NpyWriter::new(writer, archive);
+                let mut arrays = Vec::new();
+                let mut buf = Vec::new();
+                for column in columns.iter() {
+                    match reader.npy_cell(table, column, first_row, &mut buf)? {
+                        Some(array) => arrays.push(array),
+                        None => bail!(
+                            "NumPy export is not available for column {} of type {}",
+                            column,
+                            table.get_col_desc(column)?.data_type()
+                        ),
+                    }
+                    buf.clear();
+                }
+                let n = (end_row - start_row) as usize;
+                if archive {
+                    // Row numbers, so rows selected by a filter can be related to the table.
+                    writer.start_array("row", <i64 as NpyElement>::DESCR, &[n])?;
+                    for i in start_row..end_row {
+                        let row = rows.as_ref().map_or(i, |r| r[i as usize]);
+                        (row as i64).write_le(&mut buf);
+                    }
+                    writer.write(&buf)?;
+                    writer.end_array()?;
+                    buf.clear();
+                }
+                let (descr, cell_shape) = &arrays[0];
+                writer.start_array(&columns[0], descr, &[&[n], cell_shape.as_slice()].concat())?;
+                Output::NumPy(NumPyOutput {
+                    writer,
+                    arrays,
+                    buf,
+                })
+            }
+            ExportFormat::Arrow | ExportFormat::Parquet => Output::Columnar(ColumnarWriter::new(
+                table,
+                &mut reader,
+                &columns,
+                first_row,
+                writer,
+                format == ExportFormat::Parquet,
+            )?),
+        };
+
+        Ok(ExportJob {
+            columns,
+            start_row,
+            end_row,
+            next_row: start_row,
+            column_index: 0,
+            rows,
+            reader,
+            path: path.map(Path::to_path_buf),
+            output,
+        })
+    }
+
+    /// Number of passes over the rows: one per column for NumPy files, one otherwise.
+    fn n_passes(&self) -> usize {
+        match self.output {
+            Output::Text(_) | Output::Columnar(_) => 1,
+            Output::NumPy(_) => self.columns.len(),
+        }
+    }
+
+    /// Fraction of the rows exported so far.
+    pub fn progress(&self) -> f64 {
+        if self.end_row <= self.start_row {
+            1.0
+        } else {
+            let n = (self.end_row - self.start_row) as f64;
+            let done = self.column_index as f64 * n + (self.next_row - self.start_row) as f64;
+            done / (n * self.n_passes() as f64)
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.column_index + 1 >= self.n_passes() && self.next_row >= self.end_row
+    }
+
+    /// Export the next batch of rows.
+    pub fn step(&mut self, table: &mut Table) -> Result<()> {
+        let end = (self.next_row + ROWS_PER_STEP).min(self.end_row);
+        match &mut self.output {
+            Output::Text(output) => {
+                for i in self.next_row..end {
+                    let row = self.rows.as_ref().map_or(i, |r| r[i as usize]);
+                    let mut cells = Vec::with_capacity(self.columns.len());
+                    for (column, unit) in self.columns.iter().zip(output.units.iter()) {
+                        let cell = self.reader.cell_elements(table, column, row, unit)?;
+                        cells.push(cell.unwrap_or(CellElements {
+                            shape: Vec::new(),
+                            values: ElementValues::Text(Vec::new()),
+                        }));
+                    }
+                    output.write_row(&self.columns, row, &cells)?;
+                }
+                self.next_row = end;
+            }
+            Output::Columnar(output) => {
+                for i in self.next_row..end {
+                    let row = self.rows.as_ref().map_or(i, |r| r[i as usize]);
+                    output.write_row(table, &mut self.reader, row)?;
+                }
+                self.next_row = end;
+            }
+            Output::NumPy(output) => {
+                let column = &self.columns[self.column_index];
+                let cell_shape = &output.arrays[self.column_index].1;
+                for i in self.next_row..end {
+                    let row = self.rows.as_ref().map_or(i, |r| r[i as usize]);
+                    output.buf.clear();
+                    match self.reader.npy_cell(table, column, row, &mut output.buf)? {
+                        Some((_, shape)) if shape == *cell_shape => {}
+                        Some((_, shape)) => bail!(
+                            "Row {} of {} has shape {:?} instead of {:?}; a NumPy array needs \
+                             cells of the same shape",
+                            row,
+                            column,
+                            shape,
+                            cell_shape
+                        ),
+                        None => bail!("Row {} of {} could not be read", row, column),
+                    }
+                    output.writer.write(&output.buf)?;
+                }
+                self.next_row = end;
+                if self.next_row >= self.end_row {
+                    output.writer.end_array()?;
+                    if self.column_index + 1 < self.columns.len() {
+                        self.column_index += 1;
+                        self.next_row = self.start_row;
+                        let n = (self.end_row - self.start_row) as usize;
+                        let (descr, cell_shape) = &output.arrays[self.column_index];
+                        let shape = [&[n], cell_shape.as_slice()].concat();
+                        output.writer.start_array(
+                            &self.columns[self.column_index],
+                            descr,
+                            &shape,
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the output and describe what was written, e.g. "1024 lines".
+    pub fn finish(self) -> Result<String> {
+        match self.output {
+            Output::Text(mut output) => {
+                output.writer.flush()?;
+                Ok(format!("{} lines", output.n_lines))
+            }
+            Output::NumPy(output) => {
+                let n_arrays = output.writer.n_arrays();
+                output.writer.finish()?;
+                Ok(format!("{} arrays", n_arrays))
+            }
+            Output::Columnar(output) => {
+                let n_rows = output.n_rows;
+                output.finish()?;
+                Ok(format!("{} rows", n_rows))
+            }
+        }
+    }
+
+    /// Stop exporting and remove the partly written file.
+    pub fn cancel(self) {
+        let path = self.path.clone();
+        drop(self);
+        if let Some(path) = path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Shape that the cells of one row broadcast to; cells that could not be read are left out.
+fn row_shape(columns: &[String], cells: &[CellElements], row: u64) -> Result<Vec<usize>> {
+    let defined = || {
+        cells
+            .iter()
+            .filter(|c| !c.is_empty())
+            .map(|c| c.shape.as_slice())
+    };
+    match broadcast_shape(defined()) {
+        Some(shape) => Ok(shape),
+        None => {
+            let shapes: Vec<String> = columns
+                .iter()
+                .zip(cells.iter())
+                .map(|(name, cell)| format!("{} {:?}", name, cell.shape))
+                .collect();
+            bail!(
+                "Row {}: cells of shapes {} cannot be combined",
+                row,
+                shapes.join(", ")
+            )
+        }
+    }
+}
+
+/// Row-major position in a cell of the element at `index` of the broadcast shape.
+fn element_index(shape: &[usize], index: &[usize]) -> usize {
+    let offset = index.len() - shape.len().min(index.len());
+    let mut position = 0;
+    for (n, i) in shape.iter().zip(index[offset..].iter()) {
+        position = position * n + if *n == 1 { 0 } else { *i };
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_ranges() {
+        assert_eq!(parse_row_range("10:20").unwrap(), 10..20);
+        assert_eq!(parse_row_range(" 10 : ").unwrap(), 10..u64::MAX);
+        assert_eq!(parse_row_range(":5").unwrap(), 0..5);
+        assert_eq!(parse_row_range(":").unwrap(), 0..u64::MAX);
+        assert_eq!(
+            parse_row_range("10").unwrap_err().to_string(),
+            "Expected a row range as start:end, not 10"
+        );
+        assert_eq!(
+            parse_row_range("-1:5").unwrap_err().to_string(),
+            "Invalid row number -1 in -1:5"
+        );
+    }
+
+    #[test]
+    fn form_row_range_defaults_to_all_rows() {
+        let mut form = ExportForm::new("DATA", "DATA.csv".to_string());
+        assert_eq!(form.row_range().unwrap(), 0..u64::MAX);
+        form.rows = "100:200".to_string();
+        assert_eq!(form.row_range().unwrap(), 100..200);
+    }
+
+    #[test]
+    fn shapes_broadcast_on_their_last_axes() {
+        let shapes = |list: &[&[usize]]| broadcast_shape(list.iter().copied());
+        // DATA, FLAG and a scalar such as TIME.
+        assert_eq!(shapes(&[&[64, 4], &[64, 4], &[]]), Some(vec![64, 4]));
+        // WEIGHT with one value per correlation lines up with DATA.
+        assert_eq!(shapes(&[&[4], &[64, 4]]), Some(vec![64, 4]));
+        assert_eq!(shapes(&[&[64, 1], &[1, 4]]), Some(vec![64, 4]));
+        assert_eq!(shapes(&[&[64, 4], &[64, 2]]), None);
+        assert_eq!(shapes(&[&[3], &[64, 4]]), None);
+        assert_eq!(shapes(&[]), Some(Vec::new()));
+    }
+
+    #[test]
+    fn formats_from_file_names() {
+        assert_eq!(ExportFormat::from_path("out.TSV"), ExportFormat::Tsv);
+        assert_eq!(ExportFormat::from_path("out.feather"), ExportFormat::Arrow);
+        assert_eq!(ExportFormat::from_path("out.pq"), ExportFormat::Parquet);
+        assert_eq!(ExportFormat::from_path("out"), ExportFormat::Csv);
+    }
+}
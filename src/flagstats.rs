@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use rubbl_casatables::Table;
+use rubbl_core::ndarray::Array2;
+use serde_json::{json, Map, Value};
+
+use crate::inspector::spectral_setup;
+use crate::measures::format_epoch;
+use crate::reader::JOB_ROWS_PER_STEP;
+use crate::summary::stokes_name;
+
+/// Number of flagged samples out of a total.
+#[derive(Clone, Copy, Default)]
+pub struct FlagCount {
+    pub flagged: u64,
+    pub total: u64,
+}
+
+impl FlagCount {
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            100.0 * self.flagged as f64 / self.total as f64
+        }
+    }
+
+    fn add(&mut self, flagged: u64, total: u64) {
+        self.flagged += flagged;
+        self.total += total;
+    }
+}
+
+/// Flag occupancy along one axis of the data, e.g. per station.
+pub struct FlagTable {
+    pub title: &'static str,
+    pub rows: Vec<(String, FlagCount)>,
+    /// Rows in their original order, used to undo sorting.
+    natural_rows: Vec<(String, FlagCount)>,
+}
+
+impl FlagTable {
+    fn new(title: &'static str, rows: Vec<(String, FlagCount)>) -> Self {
+        FlagTable {
+            title,
+            natural_rows: rows.clone(),
+            rows,
+        }
+    }
+
+    /// Sort rows by flagged percentage, highest first, or back in their natural order.
+    pub fn sort(&mut self, by_percentage: bool) {
+        self.rows = self.natural_rows.clone();
+        if by_percentage {
+            self.rows
+                .sort_by(|a, b| b.1.percentage().total_cmp(&a.1.percentage()));
+        }
+    }
+}
+
+/// Flag occupancy of a Measurement Set per station, baseline, channel, correlation and time slot.
+pub struct FlagStats {
+    pub total: FlagCount,
+    pub tables: Vec<FlagTable>,
+}
+
+pub fn station_name(station_names: &[String], antenna: i32) -> String {
+    usize::try_from(antenna)
+        .ok()
+        .and_then(|a| station_names.get(a))
+        .cloned()
+        .unwrap_or_else(|| antenna.to_string())
+}
+
+impl FlagStats {
+    /// Stream the FLAG and FLAG_ROW columns of MAIN and count flagged samples.
+    ///
+    /// `progress` is told the fraction of rows done now and then; an error from it stops reading.
+    pub fn compute(
+        ms_name: &str,
+        table: &mut Table,
+        station_names: &[String],
+        progress: &dyn Fn(f64) -> Result<()>,
+    ) -> Result<Self> {
+        let columns = table.column_names()?;
+        if !columns.iter().any(|c| c == "FLAG") {
+            bail!("Table has no FLAG column");
+        }
+        let has_flag_row = columns.iter().any(|c| c == "FLAG_ROW");
+        let (chan_freq, corr_type) = if table.n_rows() > 0 {
+            spectral_setup(ms_name, table, 0).unwrap_or_default()
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let mut total = FlagCount::default();
+        let mut stations: BTreeMap<i32, FlagCount> = BTreeMap::new();
+        let mut baselines: BTreeMap<(i32, i32), FlagCount> = BTreeMap::new();
+        // TIME is positive, so the bit patterns sort in the same order as the values.
+        let mut times: BTreeMap<u64, FlagCount> = BTreeMap::new();
+        let mut channels: Vec<FlagCount> = Vec::new();
+        let mut correlations: Vec<FlagCount> = Vec::new();
+
+        let mut main_row = table.get_row_reader()?;
+        for row in 0..table.n_rows() {
+            if row % JOB_ROWS_PER_STEP == 0 {
+                progress(row as f64 / table.n_rows() as f64)?;
+            }
+            table.read_row(&mut main_row, row)?;
+            let antenna1 = main_row.get_cell::<i32>("ANTENNA1")?;
+            let antenna2 = main_row.get_cell::<i32>("ANTENNA2")?;
+            let time = main_row.get_cell::<f64>("TIME")?;
+            let mut flags = main_row.get_cell::<Array2<bool>>("FLAG")?;
+            if has_flag_row && main_row.get_cell::<bool>("FLAG_ROW")? {
+                flags.fill(true);
+            }
+            let (n_chan, n_corr) = flags.dim();
+            if channels.len() < n_chan {
+                channels.resize(n_chan, FlagCount::default());
+            }
+            if correlations.len() < n_corr {
+                correlations.resize(n_corr, FlagCount::default());
+            }
+
+            let mut n_flagged = 0;
+            for ((chan, corr), flagged) in flags.indexed_iter() {
+                let f = *flagged as u64;
+                n_flagged += f;
+                channels[chan].add(f, 1);
+                correlations[corr].add(f, 1);
+            }
+            let n_samples = flags.len() as u64;
+            total.add(n_flagged, n_samples);
+            stations
+                .entry(antenna1)
+                .or_default()
+                .add(n_flagged, n_samples);
+            if antenna2 != antenna1 {
+                stations
+                    .entry(antenna2)
+                    .or_default()
+                    .add(n_flagged, n_samples);
+            }
+            baselines
+                .entry((antenna1.min(antenna2), antenna1.max(antenna2)))
+                .or_default()
+                .add(n_flagged, n_samples);
+            times
+                .entry(time.to_bits())
+                .or_default()
+                .add(n_flagged, n_samples);
+        }
+
+        let tables = vec![
+            FlagTable::new(
+                "Station",
+                stations
+                    .into_iter()
+                    .map(|(a, c)| (station_name(station_names, a), c))
+                    .collect(),
+            ),
+            FlagTable::new(
+                "Baseline",
+                baselines
+                    .into_iter()
+                    .map(|((a1, a2), c)| {
+                        (
+                            format!(
+                                "{}-{}",
+                                station_name(station_names, a1),
+                                station_name(station_names, a2)
+                            ),
+                            c,
+                        )
+                    })
+                    .collect(),
+            ),
+            FlagTable::new(
+                "Channel",
+                channels
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, c)| match chan_freq.get(i) {
+                        Some(f) => (format!("{} ({:.6} MHz)", i, f / 1e6), c),
+                        None => (i.to_string(), c),
+                    })
+                    .collect(),
+            ),
+            FlagTable::new(
+                "Correlation",
+                correlations
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, c)| match corr_type.get(i) {
+                        Some(t) => (stokes_name(*t).to_string(), c),
+                        None => (i.to_string(), c),
+                    })
+                    .collect(),
+            ),
+            FlagTable::new(
+                "Time slot",
+                times
+                    .into_iter()
+                    .map(|(t, c)| (format_epoch(f64::from_bits(t)), c))
+                    .collect(),
+            ),
+        ];
+        Ok(FlagStats { total, tables })
+    }
+
+    /// Sort all tables by flagged percentage or in their natural order.
+    pub fn sort(&mut self, by_percentage: bool) {
+        for table in self.tables.iter_mut() {
+            table.sort(by_percentage);
+        }
+    }
+
+    /// Render a plain-text report, similar to the flagging summary printed by DP3.
+    pub fn to_text(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&format!(
+            "Total: {:.2}% of {} samples flagged\n",
+            self.total.percentage(),
+            self.total.total
+        ));
+        for table in self.tables.iter() {
+            buf.push_str(&format!(
+                "\nFlagged percentage per {}:\n",
+                table.title.to_lowercase()
+            ));
+            for (name, count) in table.rows.iter() {
+                buf.push_str(&format!(
+                    "  {:<40} {:>7.2}% ({} of {})\n",
+                    name,
+                    count.percentage(),
+                    count.flagged,
+                    count.total
+                ));
+            }
+        }
+        buf
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        map.insert(
+            "total".to_string(),
+            json!({
+                "flagged": self.total.flagged,
+                "samples": self.total.total,
+                "percentage": self.total.percentage(),
+            }),
+        );
+        for table in self.tables.iter() {
+            let rows: Vec<Value> = table
+                .rows
+                .iter()
+                .map(|(name, count)| {
+                    json!({
+                        "name": name,
+                        "flagged": count.flagged,
+                        "samples": count.total,
+                        "percentage": count.percentage(),
+                    })
+                })
+                .collect();
+            map.insert(
+                table.title.to_lowercase().replace(' ', "_"),
+                Value::Array(rows),
+            );
+        }
+        Value::Object(map)
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use rubbl_casatables::Table;
+
+use crate::summary::{open_subtable, stokes_name};
+
+/// Read a string column of a subtable, e.g. the NAME column of ANTENNA.
+fn read_names(table: &mut Table, column_name: &str) -> Result<Vec<String>> {
+    let mut names = Vec::with_capacity(table.n_rows() as usize);
+    for row in 0..table.n_rows() {
+        names.push(table.get_cell::<String>(column_name, row)?);
+    }
+    Ok(names)
+}
+
+fn antenna_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "ANTENNA")?;
+    read_names(&mut t, "NAME")
+}
+
+fn field_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "FIELD")?;
+    read_names(&mut t, "NAME")
+}
+
+fn observation_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "OBSERVATION")?;
+    read_names(&mut t, "PROJECT")
+}
+
+fn processor_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "PROCESSOR")?;
+    read_names(&mut t, "TYPE")
+}
+
+fn state_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "STATE")?;
+    read_names(&mut t, "OBS_MODE")
+}
+
+/// Labels of the feeds, by FEED_ID, from their polarization types, e.g. `X Y`.
+///
+/// FEED has a row per antenna, spectral window and time for each feed, so FEED1/FEED2 are not
+/// row numbers; a feed takes the label of its first row.
+fn feed_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "FEED")?;
+    let mut labels: Vec<Option<String>> = Vec::new();
+    for row in 0..t.n_rows() {
+        let Ok(feed) = usize::try_from(t.get_cell::<i32>("FEED_ID", row)?) else {
+            continue;
+        };
+        if labels.len() <= feed {
+            labels.resize(feed + 1, None);
+        }
+        if labels[feed].is_none() {
+            let types = t.get_cell::<Vec<String>>("POLARIZATION_TYPE", row)?;
+            labels[feed] = Some(types.join(" "));
+        }
+    }
+    Ok(labels.into_iter().map(Option::unwrap_or_default).collect())
+}
+
+fn spectral_window_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "SPECTRAL_WINDOW")?;
+    let mut labels = Vec::with_capacity(t.n_rows() as usize);
+    for row in 0..t.n_rows() {
+        let name = t.get_cell::<String>("NAME", row)?;
+        let ref_freq = t.get_cell::<f64>("REF_FREQUENCY", row)?;
+        if name.is_empty() {
+            labels.push(format!("{:.3} MHz", ref_freq / 1e6));
+        } else {
+            labels.push(format!("{} {:.3} MHz", name, ref_freq / 1e6));
+        }
+    }
+    Ok(labels)
+}
+
+fn polarization_labels(ms: &str) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "POLARIZATION")?;
+    let mut labels = Vec::with_capacity(t.n_rows() as usize);
+    for row in 0..t.n_rows() {
+        let corr_types = t.get_cell_as_vec::<i32>("CORR_TYPE", row)?;
+        let names: Vec<&str> = corr_types.iter().map(|c| stokes_name(*c)).collect();
+        labels.push(names.join(" "));
+    }
+    Ok(labels)
+}
+
+fn data_description_labels(
+    ms: &str,
+    spectral_windows: &[String],
+    polarizations: &[String],
+) -> Result<Vec<String>> {
+    let mut t = open_subtable(ms, "DATA_DESCRIPTION")?;
+    let mut labels = Vec::with_capacity(t.n_rows() as usize);
+    for row in 0..t.n_rows() {
+        let spw = t.get_cell::<i32>("SPECTRAL_WINDOW_ID", row)?;
+        let pol = t.get_cell::<i32>("POLARIZATION_ID", row)?;
+        let spw_label = spectral_windows
+            .get(spw as usize)
+            .map(|l| format!(" {}", l))
+            .unwrap_or_default();
+        let pol_label = polarizations
+            .get(pol as usize)
+            .map(|l| format!(" {}", l))
+            .unwrap_or_default();
+        labels.push(format!(
+            "spw {}{}, pol {}{}",
+            spw, spw_label, pol, pol_label
+        ));
+    }
+    Ok(labels)
+}
+
+/// Load human-readable labels for the `*_ID`, ANTENNA1/ANTENNA2 and FEED1/FEED2 columns of a
+/// Measurement Set.
+///
+/// The returned map goes from column name to a label per row of the subtable the column refers to.
+/// Subtables that are missing or cannot be read are skipped.
+pub fn load_foreign_keys(ms: &str) -> HashMap<String, Vec<String>> {
+    let mut keys = HashMap::new();
+    if let Ok(antennas) = antenna_labels(ms) {
+        keys.insert("ANTENNA1".to_string(), antennas.clone());
+        keys.insert("ANTENNA2".to_string(), antennas.clone());
+        keys.insert("ANTENNA_ID".to_string(), antennas);
+    }
+    if let Ok(fields) = field_labels(ms) {
+        keys.insert("FIELD_ID".to_string(), fields);
+    }
+    if let Ok(observations) = observation_labels(ms) {
+        keys.insert("OBSERVATION_ID".to_string(), observations);
+    }
+    if let Ok(processors) = processor_labels(ms) {
+        keys.insert("PROCESSOR_ID".to_string(), processors);
+    }
+    if let Ok(states) = state_labels(ms) {
+        keys.insert("STATE_ID".to_string(), states);
+    }
+    if let Ok(feeds) = feed_labels(ms) {
+        keys.insert("FEED1".to_string(), feeds.clone());
+        keys.insert("FEED2".to_string(), feeds.clone());
+        keys.insert("FEED_ID".to_string(), feeds);
+    }
+    let spectral_windows = spectral_window_labels(ms).unwrap_or_default();
+    let polarizations = polarization_labels(ms).unwrap_or_default();
+    if let Ok(data_descriptions) = data_description_labels(ms, &spectral_windows, &polarizations) {
+        keys.insert("DATA_DESC_ID".to_string(), data_descriptions);
+    }
+    if !spectral_windows.is_empty() {
+        keys.insert("SPECTRAL_WINDOW_ID".to_string(), spectral_windows);
+    }
+    if !polarizations.is_empty() {
+        keys.insert("POLARIZATION_ID".to_string(), polarizations);
+    }
+    keys
+}
+
+/// Format an index into a subtable, appending its label if it has one, e.g. `3 (CS004HBA0)`.
+pub fn format_foreign_key(index: i32, labels: Option<&Vec<String>>) -> String {
+    let label = labels.and_then(|l| usize::try_from(index).ok().and_then(|i| l.get(i)));
+    match label.filter(|l| !l.is_empty()) {
+        Some(label) => format!("{} ({})", index, label),
+        None => format!("{}", index),
+    }
+}
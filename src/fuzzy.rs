@@ -0,0 +1,76 @@
+/// Match `pattern` as a case-insensitive subsequence of `candidate`.
+///
+/// Returns a score, higher for better matches, and the positions of the matched characters of
+/// `candidate`. Matches at the start of words and runs of consecutive characters score higher,
+/// so that e.g. `wsp` ranks WEIGHT_SPECTRUM above other columns containing those letters.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().flat_map(|c| c.to_lowercase()).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    // Try every occurrence of the first character as a start and keep the best match.
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..lower.len()).filter(|i| lower[*i] == pattern[0]) {
+        let mut positions = vec![start];
+        for p in pattern[1..].iter() {
+            let from = positions[positions.len() - 1] + 1;
+            match (from..lower.len()).find(|i| lower[*i] == *p) {
+                Some(i) => positions.push(i),
+                None => break,
+            }
+        }
+        if positions.len() < pattern.len() {
+            break;
+        }
+        let score = score(&chars, &positions);
+        if best.as_ref().is_none_or(|(b, _)| score > *b) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+fn score(chars: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    for (i, pos) in positions.iter().enumerate() {
+        let word_start = *pos == 0 || matches!(chars[pos - 1], '_' | ' ' | '-' | '.');
+        if word_start {
+            score += 10;
+        }
+        if i > 0 && positions[i - 1] + 1 == *pos {
+            score += 5;
+        }
+    }
+    // Prefer matches near the start and shorter names.
+    score - positions[0] as i64 - chars.len() as i64 / 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_a_case_insensitive_subsequence() {
+        assert_eq!(fuzzy_match("", "DATA"), Some((0, Vec::new())));
+        let (_, positions) = fuzzy_match("wsp", "WEIGHT_SPECTRUM").unwrap();
+        assert_eq!(positions, vec![0, 7, 8]);
+        assert!(fuzzy_match("spw", "WEIGHT_SPECTRUM").is_none());
+        assert!(fuzzy_match("dataa", "DATA").is_none());
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        let score = |pattern, candidate| fuzzy_match(pattern, candidate).unwrap().0;
+        assert!(score("wsp", "WEIGHT_SPECTRUM") > score("wsp", "FLAG_WEIGHTS_SPW"));
+        assert!(score("data", "DATA") > score("data", "CORRECTED_DATA"));
+        assert!(score("ant", "ANTENNA") > score("ant", "ANTENNA1_DATA"));
+        // The best of several starts is kept: "cor" at CORRECTED rather than the earlier "c".
+        let (_, positions) = fuzzy_match("cor", "ACORR_CORRECTED").unwrap();
+        assert_eq!(positions, vec![6, 7, 8]);
+    }
+}
@@ -0,0 +1,336 @@
+use anyhow::{bail, Result};
+use rubbl_casatables::{CasaScalarData, GlueDataType, Table};
+use rubbl_core::ndarray::{ArrayD, IxDyn};
+use rubbl_core::Complex;
+
+use crate::summary::{open_subtable, stokes_name};
+
+/// Maximum number of elements rendered at once, to keep huge cells responsive.
+const MAX_ELEMENTS: usize = 20000;
+
+/// A single axis of an array cell, with optional labels per element.
+pub struct Axis {
+    pub name: String,
+    pub labels: Option<Vec<String>>,
+    pub len: usize,
+    /// Selected range of this axis; start is inclusive, end is exclusive.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Axis {
+    fn describe(&self, index: usize) -> String {
+        match self.labels.as_ref().and_then(|l| l.get(index)) {
+            Some(label) => format!("{} {} ({})", self.name, index, label),
+            None => format!("{} {}", self.name, index),
+        }
+    }
+}
+
+/// Viewer for a single cell of an array column of arbitrary rank.
+pub struct CellInspector {
+    pub column: String,
+    pub row: u64,
+    pub axes: Vec<Axis>,
+    pub current_axis: usize,
+    pub scroll: u16,
+    values: ArrayD<String>,
+}
+
+fn read_cell<T: CasaScalarData + Copy + std::fmt::Display>(
+    table: &mut Table,
+    column_name: &str,
+    row: u64,
+    scalar: bool,
+) -> Result<ArrayD<String>> {
+    if scalar {
+        let data = table.get_cell::<T>(column_name, row)?;
+        return Ok(ArrayD::from_elem(IxDyn(&[]), format!("{}", data)));
+    }
+    let data = table.get_cell::<ArrayD<T>>(column_name, row)?;
+    Ok(data.mapv(|v| format!("{}", v)))
+}
+
+/// Read a cell of any data type and rank, formatting every element as a string.
+fn read_cell_as_strings(table: &mut Table, column_name: &str, row: u64) -> Result<ArrayD<String>> {
+    let col_desc = table.get_col_desc(column_name)?;
+    let scalar = col_desc.is_scalar();
+    match col_desc.data_type() {
+        GlueDataType::TpBool | GlueDataType::TpArrayBool => {
+            read_cell::<bool>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpChar | GlueDataType::TpArrayChar => {
+            read_cell::<i8>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpUChar | GlueDataType::TpArrayUChar => {
+            read_cell::<u8>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpShort | GlueDataType::TpArrayShort => {
+            read_cell::<i16>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpUShort | GlueDataType::TpArrayUShort => {
+            read_cell::<u16>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpInt | GlueDataType::TpArrayInt => {
+            read_cell::<i32>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpUInt | GlueDataType::TpArrayUInt => {
+            read_cell::<u32>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpInt64 | GlueDataType::TpArrayInt64 => {
+            read_cell::<i64>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpFloat | GlueDataType::TpArrayFloat => {
+            read_cell::<f32>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpDouble | GlueDataType::TpArrayDouble => {
+            read_cell::<f64>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpComplex | GlueDataType::TpArrayComplex => {
+            read_cell::<Complex<f32>>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpDComplex | GlueDataType::TpArrayDComplex => {
+            read_cell::<Complex<f64>>(table, column_name, row, scalar)
+        }
+        GlueDataType::TpString if scalar => {
+            let data = table.get_cell::<String>(column_name, row)?;
+            Ok(ArrayD::from_elem(IxDyn(&[]), data))
+        }
+        GlueDataType::TpString | GlueDataType::TpArrayString => {
+            let data = table.get_cell::<Vec<String>>(column_name, row)?;
+            Ok(ArrayD::from_shape_vec(IxDyn(&[data.len()]), data)?)
+        }
+        other => bail!("Not implemented for {}", other),
+    }
+}
+
+/// Look up the channel frequencies and correlation types that apply to a row of MAIN.
+pub fn spectral_setup(ms_name: &str, table: &mut Table, row: u64) -> Result<(Vec<f64>, Vec<i32>)> {
+    let ddid = table.get_cell::<i32>("DATA_DESC_ID", row)?;
+    let mut dd = open_subtable(ms_name, "DATA_DESCRIPTION")?;
+    let spw = dd.get_cell::<i32>("SPECTRAL_WINDOW_ID", ddid as u64)?;
+    let pol = dd.get_cell::<i32>("POLARIZATION_ID", ddid as u64)?;
+    let chan_freq = open_subtable(ms_name, "SPECTRAL_WINDOW")?
+        .get_cell_as_vec::<f64>("CHAN_FREQ", spw as u64)?;
+    let corr_type =
+        open_subtable(ms_name, "POLARIZATION")?.get_cell_as_vec::<i32>("CORR_TYPE", pol as u64)?;
+    Ok((chan_freq, corr_type))
+}
+
+/// Name the axes of a cell, labelling channel and correlation axes of visibility-like columns.
+fn name_axes(ms_name: &str, table: &mut Table, row: u64, shape: &[usize]) -> Vec<Axis> {
+    let has_ddid = table
+        .column_names()
+        .map(|c| c.iter().any(|n| n == "DATA_DESC_ID"))
+        .unwrap_or(false);
+    let (chan_freq, corr_type) = if has_ddid {
+        spectral_setup(ms_name, table, row).unwrap_or_default()
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let rank = shape.len();
+    shape
+        .iter()
+        .enumerate()
+        .map(|(i, &len)| {
+            // Cells of visibility-like columns are (.., nchan, ncorr).
+            let is_corr = !corr_type.is_empty() && len == corr_type.len() && i + 1 == rank;
+            let is_chan = !chan_freq.is_empty()
+                && len == chan_freq.len()
+                && (i + 2 == rank || (rank == 1 && !is_corr));
+            let (name, labels) = if is_corr {
+                (
+                    "corr".to_string(),
+                    Some(
+                        corr_type
+                            .iter()
+                            .map(|c| stokes_name(*c).to_string())
+                            .collect(),
+                    ),
+                )
+            } else if is_chan {
+                (
+                    "chan".to_string(),
+                    Some(
+                        chan_freq
+                            .iter()
+                            .map(|f| format!("{:.6} MHz", f / 1e6))
+                            .collect(),
+                    ),
+                )
+            } else {
+                (format!("axis{}", i), None)
+            };
+            Axis {
+                name,
+                labels,
+                len,
+                start: 0,
+                end: len,
+            }
+        })
+        .collect()
+}
+
+impl CellInspector {
+    pub fn new(ms_name: &str, table: &mut Table, column_name: &str, row: u64) -> Result<Self> {
+        if row >= table.n_rows() {
+            bail!(
+                "Row {} is out of range, table has {} rows",
+                row,
+                table.n_rows()
+            );
+        }
+        let values = read_cell_as_strings(table, column_name, row)?;
+        let axes = name_axes(ms_name, table, row, values.shape());
+        Ok(CellInspector {
+            column: column_name.to_string(),
+            row,
+            axes,
+            current_axis: 0,
+            scroll: 0,
+            values,
+        })
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        self.values.shape()
+    }
+
+    pub fn next_axis(&mut self) {
+        if !self.axes.is_empty() {
+            self.current_axis = (self.current_axis + 1) % self.axes.len();
+        }
+    }
+
+    pub fn previous_axis(&mut self) {
+        if !self.axes.is_empty() {
+            self.current_axis = (self.current_axis + self.axes.len() - 1) % self.axes.len();
+        }
+    }
+
+    /// Slide the selected range of the current axis, keeping its width.
+    pub fn shift_range(&mut self, forwards: bool) {
+        if let Some(axis) = self.axes.get_mut(self.current_axis) {
+            if forwards && axis.end < axis.len {
+                axis.start += 1;
+                axis.end += 1;
+            } else if !forwards && axis.start > 0 {
+                axis.start -= 1;
+                axis.end -= 1;
+            }
+            self.scroll = 0;
+        }
+    }
+
+    /// Grow or shrink the selected range of the current axis at its end.
+    pub fn resize_range(&mut self, grow: bool) {
+        if let Some(axis) = self.axes.get_mut(self.current_axis) {
+            if grow && axis.end < axis.len {
+                axis.end += 1;
+            } else if !grow && axis.end > axis.start + 1 {
+                axis.end -= 1;
+            }
+            self.scroll = 0;
+        }
+    }
+
+    /// Restrict the current axis to a single element, e.g. one correlation.
+    pub fn select_single(&mut self) {
+        if let Some(axis) = self.axes.get_mut(self.current_axis) {
+            axis.end = axis.start + 1;
+            self.scroll = 0;
+        }
+    }
+
+    /// Select the full extent of the current axis again.
+    pub fn select_all(&mut self) {
+        if let Some(axis) = self.axes.get_mut(self.current_axis) {
+            axis.start = 0;
+            axis.end = axis.len;
+            self.scroll = 0;
+        }
+    }
+
+    /// Render the header describing the cell and the selection on every axis.
+    pub fn render_header(&self) -> String {
+        let mut buf = String::new();
+        buf.push_str(&format!(
+            "Column: {}   Row: {}   Shape: {:?}\n",
+            self.column,
+            self.row,
+            self.shape()
+        ));
+        for (i, axis) in self.axes.iter().enumerate() {
+            let marker = if i == self.current_axis { ">>" } else { "  " };
+            buf.push_str(&format!(
+                "{} {:<6} [{}..{}) of {}",
+                marker, axis.name, axis.start, axis.end, axis.len
+            ));
+            if axis.labels.is_some() {
+                buf.push_str(&format!(
+                    "   {} .. {}",
+                    axis.describe(axis.start),
+                    axis.describe(axis.end - 1)
+                ));
+            }
+            buf.push('\n');
+        }
+        buf
+    }
+
+    /// Render the selected elements, one per line, labelled with their index along every axis.
+    pub fn render_values(&self) -> String {
+        let mut buf = String::new();
+        if self.axes.is_empty() {
+            if let Some(value) = self.values.first() {
+                buf.push_str(value);
+                buf.push('\n');
+            }
+            return buf;
+        }
+        if self.axes.iter().any(|a| a.start >= a.end) {
+            return buf;
+        }
+
+        let mut index: Vec<usize> = self.axes.iter().map(|a| a.start).collect();
+        let mut n_elements = 0;
+        loop {
+            let labels: Vec<String> = self
+                .axes
+                .iter()
+                .zip(index.iter())
+                .map(|(axis, &i)| axis.describe(i))
+                .collect();
+            buf.push_str(&format!(
+                "[{}] {}\n",
+                labels.join(", "),
+                self.values[IxDyn(&index)]
+            ));
+            n_elements += 1;
+            if n_elements >= MAX_ELEMENTS {
+                buf.push_str(&format!(
+                    "... output truncated after {} elements, narrow the selection to see more\n",
+                    MAX_ELEMENTS
+                ));
+                break;
+            }
+
+            // Advance the index with the last axis varying fastest.
+            let mut axis = self.axes.len();
+            loop {
+                if axis == 0 {
+                    return buf;
+                }
+                axis -= 1;
+                index[axis] += 1;
+                if index[axis] < self.axes[axis].end {
+                    break;
+                }
+                index[axis] = self.axes[axis].start;
+            }
+        }
+        buf
+    }
+}
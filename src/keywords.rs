@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use rubbl_casatables::{GlueDataType, Table, TableRecord};
+use serde_json::Value;
+
+use crate::dump::keyword_to_json;
+
+/// A keyword, or a group of keywords such as a record, in the keyword browser.
+pub struct KeywordNode {
+    pub name: String,
+    pub dtype: String,
+    pub value: String,
+    pub children: Vec<KeywordNode>,
+    pub expanded: bool,
+}
+
+impl KeywordNode {
+    fn group(name: &str, children: Vec<KeywordNode>) -> Self {
+        KeywordNode {
+            name: name.to_string(),
+            dtype: String::new(),
+            value: String::new(),
+            children,
+            expanded: true,
+        }
+    }
+
+    fn leaf(name: &str, dtype: &str, value: String) -> Self {
+        KeywordNode {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+            value,
+            children: Vec::new(),
+            expanded: false,
+        }
+    }
+}
+
+/// Convert the keywords of a record to nodes; nested records become collapsed subtrees.
+fn record_nodes(record: &mut TableRecord) -> Result<Vec<KeywordNode>> {
+    let mut nodes = Vec::new();
+    for info in record.get_keyword_info()? {
+        let dtype = info.dtype.to_string();
+        let node = match info.dtype {
+            GlueDataType::TpRecord => {
+                let mut sub = record.get_field::<TableRecord>(&info.name)?;
+                let children = record_nodes(&mut sub)?;
+                KeywordNode {
+                    name: info.name.clone(),
+                    value: format!("{} fields", children.len()),
+                    dtype,
+                    children,
+                    expanded: false,
+                }
+            }
+            GlueDataType::TpTable => KeywordNode::leaf(&info.name, &dtype, "subtable".to_string()),
+            _ => {
+                let value = match keyword_to_json(record, &info.name, info.dtype) {
+                    Ok(Value::String(s)) => s,
+                    Ok(v) => v.to_string(),
+                    Err(e) => format!("Failed to read keyword: {}", e),
+                };
+                KeywordNode::leaf(&info.name, &dtype, value)
+            }
+        };
+        nodes.push(node);
+    }
+    Ok(nodes)
+}
+
+/// Read the type, subtype and readme from the table.info file of a table.
+fn table_info(table_path: &str) -> Vec<KeywordNode> {
+    let Ok(contents) = fs::read_to_string(Path::new(table_path).join("table.info")) else {
+        return vec![KeywordNode::leaf("table.info", "", "not found".to_string())];
+    };
+    let mut nodes = Vec::new();
+    let mut readme = Vec::new();
+    for line in contents.lines() {
+        match line.split_once('=') {
+            Some((key, value)) if readme.is_empty() && !key.trim().contains(' ') => {
+                nodes.push(KeywordNode::leaf(key.trim(), "", value.trim().to_string()));
+            }
+            _ => {
+                if !line.trim().is_empty() || !readme.is_empty() {
+                    readme.push(line);
+                }
+            }
+        }
+    }
+    if !readme.is_empty() {
+        nodes.push(KeywordNode::leaf("Readme", "", readme.join("\n")));
+    }
+    nodes
+}
+
+/// Browser for the TableInfo, table keywords and column keywords of a table.
+pub struct KeywordTree {
+    pub roots: Vec<KeywordNode>,
+    pub selected: usize,
+}
+
+impl KeywordTree {
+    pub fn load(table_path: &str, table: &mut Table, column_name: Option<&str>) -> Result<Self> {
+        let mut roots = vec![
+            KeywordNode::group("TableInfo", table_info(table_path)),
+            KeywordNode::group(
+                "Table keywords",
+                record_nodes(&mut table.get_keyword_record()?)?,
+            ),
+        ];
+        if let Some(column_name) = column_name {
+            roots.push(KeywordNode::group(
+                &format!("Column keywords of {}", column_name),
+                record_nodes(&mut table.get_column_keyword_record(column_name)?)?,
+            ));
+        }
+        Ok(KeywordTree { roots, selected: 0 })
+    }
+
+    /// Index paths and depths of the nodes that are shown, in display order.
+    pub fn visible(&self) -> Vec<(Vec<usize>, usize)> {
+        fn walk(
+            nodes: &[KeywordNode],
+            prefix: &mut Vec<usize>,
+            out: &mut Vec<(Vec<usize>, usize)>,
+        ) {
+            for (i, node) in nodes.iter().enumerate() {
+                prefix.push(i);
+                out.push((prefix.clone(), prefix.len() - 1));
+                if node.expanded {
+                    walk(&node.children, prefix, out);
+                }
+                prefix.pop();
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.roots, &mut Vec::new(), &mut out);
+        out
+    }
+
+    pub fn node(&self, path: &[usize]) -> &KeywordNode {
+        let mut node = &self.roots[path[0]];
+        for i in &path[1..] {
+            node = &node.children[*i];
+        }
+        node
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut KeywordNode {
+        let mut node = &mut self.roots[path[0]];
+        for i in &path[1..] {
+            node = &mut node.children[*i];
+        }
+        node
+    }
+
+    pub fn move_selection(&mut self, amount: usize, forwards: bool) {
+        let n_visible = self.visible().len();
+        if forwards {
+            self.selected = (self.selected + amount).min(n_visible.saturating_sub(1));
+        } else {
+            self.selected = self.selected.saturating_sub(amount);
+        }
+    }
+
+    /// Expand or collapse the selected node.
+    ///
+    /// Collapsing a node without children moves the selection to its parent instead.
+    pub fn expand(&mut self, expand: bool) {
+        let visible = self.visible();
+        let Some((path, _)) = visible.get(self.selected) else {
+            return;
+        };
+        let has_children = !self.node(path).children.is_empty();
+        if has_children && self.node(path).expanded != expand {
+            self.node_mut(path).expanded = expand;
+        } else if !expand && path.len() > 1 {
+            let parent = &path[..path.len() - 1];
+            if let Some(i) = visible.iter().position(|(p, _)| p == parent) {
+                self.selected = i;
+            }
+        }
+    }
+
+    /// Toggle the selected node between expanded and collapsed.
+    pub fn toggle(&mut self) {
+        let visible = self.visible();
+        if let Some((path, _)) = visible.get(self.selected) {
+            let node = self.node_mut(path);
+            node.expanded = !node.expanded;
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{bail, Result};
+use rubbl_casatables::{Table, TableOpenMode};
+
+use crate::flagstats::FlagStats;
+use crate::measures::{column_unit, ColumnUnit};
+use crate::plot::{Visibilities, VisibilityPlot, VisibilitySource};
+use crate::reader::{ColumnReader, DisplayOptions};
+use crate::uvplot::{UvPlot, UvPlotJob};
+use crate::waterfall::Waterfall;
+
+/// Memory used to cache recently viewed blocks of rows.
+const CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Rows of a column to read and format for the Column Values panel.
+pub struct LoadRequest {
+    pub table_path: String,
+    pub column: String,
+    /// Rows to show, in order; not necessarily contiguous when a filter is active.
+    pub rows: Vec<u64>,
+    pub raw_values: bool,
+    pub labels: Option<Vec<String>>,
+}
+
+/// Work on a table that may take a while, for the Tables panel and the views.
+pub enum TableRequest {
+    /// Open a table selected in the Tables panel.
+    Open(String),
+    /// Read the data of a view of a table.
+    View {
+        ms_name: String,
+        table_path: String,
+        view: ViewRequest,
+    },
+}
+
+/// A view and what it shows.
+pub enum ViewRequest {
+    Plot {
+        visibilities: Visibilities,
+        n_antennas: usize,
+    },
+    /// Read the data of a plot again after its selection changed.
+    ReloadPlot(Box<VisibilityPlot>),
+    Waterfall {
+        visibilities: Visibilities,
+        n_antennas: usize,
+    },
+    /// Read the data of a waterfall again after its selection changed.
+    ReloadWaterfall(Box<Waterfall>),
+    UvPlot {
+        station_names: Vec<String>,
+    },
+    FlagStats {
+        station_names: Vec<String>,
+    },
+}
+
+/// Result of a `TableRequest`.
+pub enum TableOutcome {
+    Opened(TableSummary),
+    Plot(VisibilityPlot),
+    Waterfall(Waterfall),
+    UvPlot(UvPlot),
+    FlagStats(FlagStats),
+}
+
+/// What the interface shows of a table before reading its values.
+pub struct TableSummary {
+    pub path: String,
+    pub n_rows: u64,
+    pub columns: Vec<ColumnSummary>,
+}
+
+/// Description of a column, shown above its values.
+pub struct ColumnSummary {
+    pub name: String,
+    pub data_type: String,
+    pub keywords: Vec<String>,
+    pub is_scalar: bool,
+    pub is_fixed_shape: bool,
+    /// Unit of the values, e.g. "m", or the frame of an epoch column such as UTC.
+    pub unit: Option<String>,
+}
+
+impl TableSummary {
+    /// Describe a table and its columns.
+    pub fn read(path: &str, table: &mut Table) -> Result<Self> {
+        let mut columns = Vec::new();
+        for name in table.column_names()? {
+            let col_desc = table.get_col_desc(&name)?;
+            columns.push(ColumnSummary {
+                data_type: col_desc.data_type().to_string(),
+                keywords: table.column_keyword_names(&name)?,
+                is_scalar: col_desc.is_scalar(),
+                is_fixed_shape: col_desc.is_fixed_shape(),
+                unit: column_unit(table, &name).header().map(str::to_string),
+                name,
+            });
+        }
+        Ok(TableSummary {
+            path: path.to_string(),
+            n_rows: table.n_rows(),
+            columns,
+        })
+    }
+}
+
+/// Outcome of a request handled by the loading thread.
+pub enum LoadOutcome<T> {
+    Loaded(T),
+    Failed(anyhow::Error),
+}
+
+/// The request a loading thread is working on.
+pub struct Task<'a> {
+    id: u64,
+    wanted: &'a AtomicU64,
+    progress: &'a AtomicU64,
+}
+
+impl Task<'_> {
+    /// Whether the request was cancelled or replaced by a newer one.
+    pub fn is_cancelled(&self) -> bool {
+        self.wanted.load(Ordering::SeqCst) != self.id
+    }
+
+    /// Report the fraction of the request that is done; fails if the request was cancelled.
+    pub fn report(&self, fraction: f64) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("Cancelled");
+        }
+        self.progress.store(fraction.to_bits(), Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Handle to a background thread that handles requests of type `R` with results of type `T`, so
+/// the interface stays responsive.
+///
+/// Tables cannot be moved between threads, so the thread opens its own copy of the tables it
+/// reads, kept in its state `S`.
+pub struct Loader<R, T> {
+    requests: Sender<(u64, R)>,
+    results: Receiver<(u64, LoadOutcome<T>)>,
+    /// Id of the request the thread should be working on, or 0 if nothing is wanted.
+    wanted: Arc<AtomicU64>,
+    /// Fraction of the current request that is done, as the bits of an f64.
+    progress: Arc<AtomicU64>,
+    next_id: u64,
+}
+
+impl<R: Send + 'static, T: Send + 'static> Loader<R, T> {
+    pub fn spawn<S: Default + 'static>(handle: fn(&mut S, R, &Task) -> Result<T>) -> Self {
+        let (requests, worker_requests) = channel();
+        let (worker_results, results) = channel();
+        let wanted = Arc::new(AtomicU64::new(0));
+        let progress = Arc::new(AtomicU64::new(0));
+        let worker_wanted = Arc::clone(&wanted);
+        let worker_progress = Arc::clone(&progress);
+        thread::spawn(move || {
+            worker(
+                worker_requests,
+                worker_results,
+                &worker_wanted,
+                &worker_progress,
+                &mut S::default(),
+                handle,
+            )
+        });
+        Loader {
+            requests,
+            results,
+            wanted,
+            progress,
+            next_id: 1,
+        }
+    }
+
+    /// Queue a request, abandoning whatever was being loaded before.
+    pub fn request(&mut self, request: R) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.progress.store(0.0f64.to_bits(), Ordering::SeqCst);
+        self.wanted.store(id, Ordering::SeqCst);
+        // If the thread is gone, the request stays pending and `poll` reports the failure.
+        let _ = self.requests.send((id, request));
+    }
+
+    /// Stop loading the current request.
+    pub fn cancel(&self) {
+        self.wanted.store(0, Ordering::SeqCst);
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.wanted.load(Ordering::SeqCst) != 0
+    }
+
+    /// Fraction of the current request that is done, if the request reports it.
+    pub fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::SeqCst))
+    }
+
+    /// Outcome of the current request, if it has finished.
+    pub fn poll(&mut self) -> Option<LoadOutcome<T>> {
+        loop {
+            match self.results.try_recv() {
+                Ok((id, outcome)) => {
+                    // Results of abandoned requests are dropped.
+                    if id == self.wanted.load(Ordering::SeqCst) {
+                        self.wanted.store(0, Ordering::SeqCst);
+                        return Some(outcome);
+                    }
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    if !self.is_busy() {
+                        return None;
+                    }
+                    self.wanted.store(0, Ordering::SeqCst);
+                    return Some(LoadOutcome::Failed(anyhow::anyhow!(
+                        "Loading thread stopped unexpectedly"
+                    )));
+                }
+            }
+        }
+    }
+}
+
+fn worker<R, T, S>(
+    requests: Receiver<(u64, R)>,
+    results: Sender<(u64, LoadOutcome<T>)>,
+    wanted: &AtomicU64,
+    progress: &AtomicU64,
+    state: &mut S,
+    handle: fn(&mut S, R, &Task) -> Result<T>,
+) {
+    while let Ok(mut next) = requests.recv() {
+        // Only the newest request matters when several queued up, e.g. while scrolling.
+        while let Ok(newer) = requests.try_recv() {
+            next = newer;
+        }
+        let (id, request) = next;
+        let task = Task {
+            id,
+            wanted,
+            progress,
+        };
+        if task.is_cancelled() {
+            continue;
+        }
+        let outcome = match handle(state, request, &task) {
+            Ok(result) => LoadOutcome::Loaded(result),
+            // Nobody is waiting for the result of a cancelled request.
+            Err(_) if task.is_cancelled() => continue,
+            Err(e) => LoadOutcome::Failed(e),
+        };
+        if results.send((id, outcome)).is_err() {
+            break;
+        }
+    }
+}
+
+/// The table the loading thread reads values from, with its cache.
+pub struct OpenTable {
+    path: String,
+    table: Table,
+    reader: ColumnReader,
+}
+
+/// Read and format the values of a `LoadRequest`.
+pub fn load_values(
+    open_table: &mut Option<OpenTable>,
+    request: LoadRequest,
+    task: &Task,
+) -> Result<String> {
+    // Keep the table and its cache between requests; only switching tables starts afresh.
+    let open = match open_table.take() {
+        Some(open) if open.path == request.table_path => open,
+        _ => OpenTable {
+            path: request.table_path.clone(),
+            table: Table::open(&request.table_path, TableOpenMode::Read)?,
+            reader: ColumnReader::new(CACHE_BYTES),
+        },
+    };
+    let OpenTable { table, reader, .. } = open_table.insert(open);
+    let display = DisplayOptions {
+        unit: if request.raw_values {
+            ColumnUnit::None
+        } else {
+            column_unit(table, &request.column)
+        },
+        labels: request.labels.as_ref(),
+    };
+    reader.format_rows(table, &request.column, &request.rows, &display, &|| {
+        task.is_cancelled()
+    })
+}
+
+/// Handle a `TableRequest`.
+///
+/// The last table stays open, so views of it need not open it again.
+pub fn handle_table_request(
+    open_table: &mut Option<(String, Table)>,
+    request: TableRequest,
+    task: &Task,
+) -> Result<TableOutcome> {
+    let path = match &request {
+        TableRequest::Open(path) => path,
+        TableRequest::View { table_path, .. } => table_path,
+    };
+    let (_, table) = match open_table.take() {
+        Some(open) if open.0 == *path => open_table.insert(open),
+        _ => open_table.insert((path.clone(), Table::open(path, TableOpenMode::Read)?)),
+    };
+    let (ms_name, view) = match request {
+        TableRequest::Open(path) => {
+            let summary = TableSummary::read(&path, table)?;
+            return Ok(TableOutcome::Opened(summary));
+        }
+        TableRequest::View { ms_name, view, .. } => (ms_name, view),
+    };
+    let progress = |fraction| task.report(fraction);
+    let outcome = match view {
+        ViewRequest::Plot {
+            visibilities,
+            n_antennas,
+        } => {
+            let source = VisibilitySource::open(table, &visibilities, n_antennas)?;
+            TableOutcome::Plot(VisibilityPlot::new(table, source, &progress)?)
+        }
+        ViewRequest::ReloadPlot(mut plot) => {
+            plot.reload(table, &progress)?;
+            TableOutcome::Plot(*plot)
+        }
+        ViewRequest::Waterfall {
+            visibilities,
+            n_antennas,
+        } => {
+            let source = VisibilitySource::open(table, &visibilities, n_antennas)?;
+            TableOutcome::Waterfall(Waterfall::new(&ms_name, table, source, &progress)?)
+        }
+        ViewRequest::ReloadWaterfall(mut waterfall) => {
+            waterfall.reload(table, &progress)?;
+            TableOutcome::Waterfall(*waterfall)
+        }
+        ViewRequest::UvPlot { station_names } => {
+            let mut job = UvPlotJob::new(&ms_name, table, &station_names)?;
+            while !job.is_done() {
+                job.step(table)?;
+                task.report(job.progress())?;
+            }
+            TableOutcome::UvPlot(job.finish())
+        }
+        ViewRequest::FlagStats { station_names } => {
+            let flag_stats = FlagStats::compute(&ms_name, table, &station_names, &progress)?;
+            TableOutcome::FlagStats(flag_stats)
+        }
+    };
+    Ok(outcome)
+}
@@ -1,11 +1,12 @@
-use std::{error::Error, io};
+use std::{collections::HashMap, error::Error, io, panic, path::Path, thread, time::Duration};
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand};
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+        cursor,
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
@@ -13,10 +14,49 @@ use ratatui::{
 };
 
 mod app;
+mod columnar;
+mod compare;
+mod diff;
+mod dump;
+mod dysco;
+mod error;
+mod export;
+mod flagstats;
+mod foreign_keys;
+mod fuzzy;
+mod inspector;
+mod keywords;
+mod loader;
+mod measures;
+mod npy;
+mod plot;
+mod query;
+mod reader;
+mod stats;
+mod storage;
+mod summary;
+mod tables;
 mod ui;
+mod uvplot;
+mod waterfall;
+use rubbl_casatables::{Table, TableOpenMode};
+
 use crate::{
     app::{App, CurrentScreen, CurrentlyEditing},
+    compare::{ColumnComparison, ColumnPair, CompareOp},
+    diff::MsDiff,
+    dump::{describe_ms, table_names},
+    dysco::DyscoComparison,
+    error::AppError,
+    export::{
+        parse_row_range, split_column_names, ComplexFormat, ExportFormat, ExportJob, TextOptions,
+    },
+    flagstats::FlagStats,
+    foreign_keys::load_foreign_keys,
+    plot::PlotQuantity,
+    query::RowFilter,
     ui::ui,
+    uvplot::StationType,
 };
 
 /// A Rust interface to summarise LOFAR H5parm calibration tables.
@@ -27,14 +67,308 @@ use crate::{
 #[command(
     help_template = "{name} \nVersion: {version} \nAuthor: {author}\n{about-section} \n {usage-heading} {usage} \n {all-args} {tab}"
 )]
+#[command(args_conflicts_with_subcommands = true)]
 // #[clap(author="Author Name", version, about="")]
 struct Args {
-    /// H5parm to summarise.
-    ms: String,
+    /// Measurement Set to explore.
+    ms: Option<String>,
+
+    /// Second Measurement Set to compare with side by side, e.g. the output of a DP3 step.
+    other: Option<String>,
+
+    /// Print the table tree of the MS as JSON when exiting the interface.
+    #[arg(long)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print an overview of the Measurement Set without starting the interface, similar to msoverview.
+    Summary {
+        /// Measurement Set to summarise.
+        ms: String,
+    },
+    /// Dump the table tree, column descriptions and keywords of the Measurement Set as JSON.
+    Dump {
+        /// Measurement Set to describe.
+        ms: String,
+    },
+    /// Report the flagged percentage per station, baseline, channel, correlation and time slot.
+    Flags {
+        /// Measurement Set to report on.
+        ms: String,
+        /// Print the report as JSON instead of plain text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report the RMS quantisation error of a Dysco-compressed MS per baseline and channel.
+    DyscoError {
+        /// Dysco-compressed Measurement Set.
+        ms: String,
+        /// Uncompressed Measurement Set with the same rows to compare with.
+        reference: String,
+        /// Column to compare.
+        #[arg(long, default_value = "DATA")]
+        column: String,
+        /// Print the report as JSON instead of plain text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report the difference or ratio of two columns of the MAIN table, e.g. DATA and
+    /// CORRECTED_DATA, per baseline, channel and row.
+    Compare {
+        /// Measurement Set to compare the columns of.
+        ms: String,
+        /// First column.
+        first: String,
+        /// Second column, with the same cell shape as the first.
+        second: String,
+        /// Show the first column minus the second, or the first divided by the second.
+        #[arg(long, value_enum, default_value = "difference")]
+        op: CompareOp,
+        /// Only compare rows matching a query, written as in the query bar of the interface.
+        #[arg(long)]
+        query: Option<String>,
+        /// Also report every row.
+        #[arg(long)]
+        per_row: bool,
+        /// Print the report as JSON instead of plain text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare the table trees, columns, keywords and row counts of two Measurement Sets, and the
+    /// values of a column row by row.
+    Diff {
+        /// First Measurement Set.
+        ms: String,
+        /// Second Measurement Set to compare with.
+        other: String,
+        /// Column to compare row by row.
+        #[arg(long, default_value = "DATA")]
+        column: String,
+        /// Subtable to compare the column of instead of the MAIN table.
+        #[arg(long)]
+        table: Option<String>,
+        /// Print the report as JSON instead of plain text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write columns of a table to CSV or TSV, with one line per element of array cells, to
+    /// NumPy .npy/.npz files with one array of shape (rows, cell shape) per column, or to Arrow
+    /// IPC or Parquet files.
+    Export {
+        /// Measurement Set to export from.
+        ms: String,
+        /// Subtable to export instead of the MAIN table, e.g. ANTENNA or SPECTRAL_WINDOW.
+        #[arg(long)]
+        table: Option<String>,
+        /// Comma-separated names of the columns to export [default: all columns for Arrow and
+        /// Parquet, DATA otherwise].
+        #[arg(long)]
+        columns: Option<String>,
+        /// Rows to export as start:end, end excluded; with --query these count matching rows.
+        #[arg(long)]
+        rows: Option<String>,
+        /// Only export rows matching a query, written as in the query bar of the interface.
+        #[arg(long)]
+        query: Option<String>,
+        /// Output format; by default taken from the extension of the output file, or CSV.
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Split complex values into real and imaginary part, or amplitude and phase.
+        #[arg(long, value_enum, default_value = "re-im")]
+        complex: ComplexFormat,
+        /// Write epochs such as TIME as raw MJD values instead of UTC timestamps in CSV and TSV.
+        #[arg(long)]
+        raw: bool,
+        /// File to write to instead of standard output.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
+    let ms = match args.command {
+        Some(Command::Summary { ms }) => {
+            print!("{}", summary::summarise(&ms)?);
+            return Ok(());
+        }
+        Some(Command::Dump { ms }) => {
+            let ms = ms.trim_end_matches('/');
+            let description = describe_ms(ms, &table_names(ms)?)?;
+            println!("{}", serde_json::to_string_pretty(&description)?);
+            return Ok(());
+        }
+        Some(Command::Flags { ms, json }) => {
+            let ms = ms.trim_end_matches('/');
+            let station_names = load_foreign_keys(ms).remove("ANTENNA1").unwrap_or_default();
+            let mut t = Table::open(ms, TableOpenMode::Read)?;
+            let flag_stats = FlagStats::compute(ms, &mut t, &station_names, &|_| Ok(()))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&flag_stats.to_json())?);
+            } else {
+                print!("{}", flag_stats.to_text());
+            }
+            return Ok(());
+        }
+        Some(Command::DyscoError {
+            ms,
+            reference,
+            column,
+            json,
+        }) => {
+            let ms = ms.trim_end_matches('/');
+            let station_names = load_foreign_keys(ms).remove("ANTENNA1").unwrap_or_default();
+            let comparison = DyscoComparison::compute(ms, &reference, &column, &station_names)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison.to_json())?);
+            } else {
+                print!("{}", comparison.to_text());
+            }
+            return Ok(());
+        }
+        Some(Command::Compare {
+            ms,
+            first,
+            second,
+            op,
+            query,
+            per_row,
+            json,
+        }) => {
+            let ms = ms.trim_end_matches('/');
+            let foreign_keys = load_foreign_keys(ms);
+            let station_names = foreign_keys.get("ANTENNA1").cloned().unwrap_or_default();
+            let mut t = Table::open(ms, TableOpenMode::Read)?;
+            let filter = match query {
+                Some(query) => Some(RowFilter::new(&query, &mut t, &foreign_keys)?),
+                None => None,
+            };
+            let pair = ColumnPair { first, second, op };
+            let comparison = ColumnComparison::compute(
+                ms,
+                &mut t,
+                &pair,
+                filter.as_ref(),
+                &station_names,
+                per_row,
+            )?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison.to_json())?);
+            } else {
+                print!("{}", comparison.to_text());
+            }
+            return Ok(());
+        }
+        Some(Command::Diff {
+            ms,
+            other,
+            column,
+            table,
+            json,
+        }) => {
+            let ms_diff = MsDiff::compute(
+                ms.trim_end_matches('/'),
+                other.trim_end_matches('/'),
+                table.as_deref(),
+                &column,
+            )?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&ms_diff.to_json())?);
+            } else {
+                print!("{}", ms_diff.to_text());
+            }
+            return Ok(());
+        }
+        Some(Command::Export {
+            ms,
+            table,
+            columns,
+            rows,
+            query,
+            format,
+            complex,
+            raw,
+            output,
+        }) => {
+            let ms = ms.trim_end_matches('/');
+            let path = match &table {
+                Some(table) => format!("{}/{}", ms, table),
+                None => ms.to_string(),
+            };
+            let mut t = Table::open(&path, TableOpenMode::Read)?;
+            let filter = match query {
+                // Names only apply to MAIN and the subtables of the MS, as in the interface.
+                Some(query) if table.as_deref().is_some_and(|t| t.contains('/')) => {
+                    Some(RowFilter::new(&query, &mut t, &HashMap::new())?)
+                }
+                Some(query) => Some(RowFilter::new(&query, &mut t, &load_foreign_keys(ms))?),
+                None => None,
+            };
+            let positions = match rows {
+                Some(rows) => parse_row_range(&rows)?,
+                None => 0..u64::MAX,
+            };
+            let format =
+                format.unwrap_or_else(|| ExportFormat::from_path(output.as_deref().unwrap_or("")));
+            let columns = match columns {
+                Some(columns) => split_column_names(&columns),
+                None if matches!(format, ExportFormat::Arrow | ExportFormat::Parquet) => Vec::new(),
+                None => vec!["DATA".to_string()],
+            };
+            let mut job = ExportJob::new(
+                &mut t,
+                &columns,
+                positions,
+                filter.as_ref(),
+                format,
+                TextOptions {
+                    complex,
+                    raw_values: raw,
+                },
+                output.as_deref().map(Path::new),
+            )?;
+            while !job.is_done() {
+                job.step(&mut t)?;
+            }
+            let written = job.finish()?;
+            if let Some(output) = output {
+                eprintln!("Wrote {} to {}", written, output);
+            }
+            return Ok(());
+        }
+        None => match args.ms {
+            Some(ms) => ms,
+            None => {
+                Args::command().print_help()?;
+                return Ok(());
+            }
+        },
+    };
+
+    // Open the MS before touching the terminal, so errors are printed normally.
+    let mut app = App::new(ms)?;
+    if let Some(other) = args.other {
+        app.compare_ms = Some(other.trim_end_matches('/').to_string());
+        if let Some(i) = app.columns.iter().position(|c| c == "DATA") {
+            app.current_column = i;
+        }
+        app.open_diff()?;
+    }
+
+    // Restore the terminal before a panic message is printed, so it is readable.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some("main") {
+            let _ = restore_terminal();
+        }
+        default_hook(info);
+    }));
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout(); // This is a special case. Normally using stdout is fine
@@ -42,22 +376,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // create app and run it
-    let mut app = App::new(args.ms);
+    // run the app
     let res = run_app(&mut terminal, &mut app);
 
     // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    restore_terminal()?;
 
     if let Ok(do_print) = res {
-        if do_print {
-            //app.print_json()?;
+        if do_print && args.json {
+            app.print_json()?;
         }
     } else if let Err(err) = res {
         println!("{err:?}");
@@ -66,123 +393,580 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Leave raw mode and the alternate screen.
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        cursor::Show
+    )
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<bool> {
     loop {
+        if let Err(e) = app.poll_loader() {
+            app.show_error(e);
+        }
+        if let Err(e) = app.poll_table_loader() {
+            app.show_error(e);
+        }
         terminal.draw(|f| ui(f, app))?;
 
+        // Keep computing statistics and exporting between key presses, so they can be cancelled.
+        let comparing = app.diff_view.as_ref().is_some_and(|v| v.job.is_some());
+        let timeout = if app.stats_job.is_some()
+            || app.export_job.is_some()
+            || app.comparison_job.is_some()
+            || comparing
+        {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(100)
+        };
+        if !event::poll(timeout)? {
+            if let Err(e) = app.step_column_stats() {
+                app.show_error(e);
+            }
+            if let Err(e) = app.step_export() {
+                app.show_error(e);
+            }
+            if let Err(e) = app.step_diff() {
+                app.show_error(e);
+            }
+            if let Err(e) = app.step_comparison() {
+                app.show_error(e);
+            }
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == event::KeyEventKind::Release {
                 // Skip events that are not KeyEventKind::Press
                 continue;
             }
-            match app.current_screen {
-                CurrentScreen::Main => match key.code {
-                    KeyCode::Char('?') => {
-                        app.current_screen = CurrentScreen::Help;
-                    }
-                    KeyCode::Char('q') => {
-                        app.current_screen = CurrentScreen::Exiting;
-                    }
-                    KeyCode::Tab => {
-                        app.toggle_editing(true);
-                    }
-                    KeyCode::BackTab => {
-                        app.toggle_editing(false);
-                    }
-                    KeyCode::Up => {
-                        app.decrease_soltab(1, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
+            match handle_key(app, key) {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(e) => app.show_error(e),
+            }
+        }
+    }
+}
+
+/// Whether a key moves through the Tables list, or opens the help or quits.
+fn moves_through_tables(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Backspace
+            | KeyCode::Enter
+            | KeyCode::Char('k' | 'K' | 'j' | 'J' | 'h' | 'l' | '?' | 'q')
+    )
+}
+
+/// Handle a key press on the current screen; returns whether the program should exit.
+fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool, AppError> {
+    match app.current_screen {
+        // Until a newly selected table is open, only moving through the Tables list works.
+        CurrentScreen::Main if app.opening_table.is_some() && !moves_through_tables(key.code) => {}
+        CurrentScreen::Main => match key.code {
+            KeyCode::Esc => {
+                app.cancel_loading();
+            }
+            KeyCode::Char('?') => {
+                app.current_screen = CurrentScreen::Help;
+            }
+            KeyCode::Char('q') => {
+                app.current_screen = CurrentScreen::Exiting;
+            }
+            KeyCode::Tab => {
+                app.toggle_editing(true);
+            }
+            KeyCode::BackTab => {
+                app.toggle_editing(false);
+            }
+            KeyCode::Up => {
+                app.decrease_soltab(1, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Down => {
+                app.increase_soltab(1, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Char('k') => {
+                app.decrease_soltab(1, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Char('K') => {
+                app.decrease_soltab(10, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Char('j') => {
+                app.increase_soltab(1, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::PageUp => {
+                app.decrease_soltab(1, "view");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::PageDown => {
+                app.increase_soltab(1, "view");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Char('J') => {
+                app.increase_soltab(10, "data");
+                app.update_soltabs();
+                match app.currently_editing {
+                    CurrentlyEditing::Table => app.select(true)?,
+                    CurrentlyEditing::Information => app.select(false)?,
+                    _ => {}
+                }
+            }
+            KeyCode::Enter => {
+                app.select(true)?;
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let CurrentlyEditing::Table = app.currently_editing {
+                    app.expand_table()?;
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace => {
+                if let CurrentlyEditing::Table = app.currently_editing {
+                    app.collapse_table()?;
+                }
+            }
+            KeyCode::Char('t') => {
+                app.toggle_raw_values()?;
+            }
+            KeyCode::Char('i') => {
+                app.inspect_cell(app.tab_scroll)?;
+            }
+            KeyCode::Char('v') => {
+                app.open_plot()?;
+            }
+            KeyCode::Char('w') => {
+                app.open_waterfall()?;
+            }
+            KeyCode::Char('u') => {
+                app.open_uv_plot()?;
+            }
+            KeyCode::Char('f') => {
+                app.open_flag_stats()?;
+            }
+            KeyCode::Char('m') => {
+                app.open_keywords()?;
+            }
+            KeyCode::Char('d') => {
+                app.open_storage()?;
+            }
+            KeyCode::Char('/') => {
+                app.open_query();
+            }
+            KeyCode::Char('g') => {
+                app.open_go_to();
+            }
+            KeyCode::Char('n') => {
+                app.open_search();
+            }
+            KeyCode::Char('e') => {
+                app.open_export();
+            }
+            KeyCode::Char('c') => {
+                app.open_diff()?;
+            }
+            KeyCode::Char('x') => {
+                app.open_compare_columns();
+            }
+            KeyCode::Home => {
+                app.scroll_to(0)?;
+            }
+            KeyCode::End => {
+                app.scroll_to(app.last_position())?;
+            }
+            KeyCode::Char('s') => {
+                app.start_column_stats(0, app.n_visible_rows())?;
+            }
+            KeyCode::Char('S') => {
+                app.open_stats_range();
+            }
+            _ => {}
+        },
+        CurrentScreen::Exiting => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('q') => {
+                return Ok(true);
+            }
+            KeyCode::Char('n') => {
+                //return Ok(false);
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Inspector => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Char('n') => {
+                app.inspect_next_cell(true)?;
+            }
+            KeyCode::Char('p') => {
+                app.inspect_next_cell(false)?;
+            }
+            _ => {
+                if let Some(inspector) = app.inspector.as_mut() {
+                    match key.code {
+                        KeyCode::Left | KeyCode::Char('h') => inspector.previous_axis(),
+                        KeyCode::Right | KeyCode::Char('l') => inspector.next_axis(),
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            inspector.scroll = inspector.scroll.saturating_sub(1);
                         }
-                    }
-                    KeyCode::Down => {
-                        app.increase_soltab(1, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            inspector.scroll = inspector.scroll.saturating_add(1);
                         }
-                    }
-                    KeyCode::Char('k') => {
-                        app.decrease_soltab(1, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
+                        KeyCode::PageUp | KeyCode::Char('K') => {
+                            inspector.scroll = inspector.scroll.saturating_sub(10);
                         }
-                    }
-                    KeyCode::Char('K') => {
-                        app.decrease_soltab(10, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
+                        KeyCode::PageDown | KeyCode::Char('J') => {
+                            inspector.scroll = inspector.scroll.saturating_add(10);
                         }
+                        KeyCode::Char('[') => inspector.shift_range(false),
+                        KeyCode::Char(']') => inspector.shift_range(true),
+                        KeyCode::Char('{') => inspector.resize_range(false),
+                        KeyCode::Char('}') => inspector.resize_range(true),
+                        KeyCode::Char('s') => inspector.select_single(),
+                        KeyCode::Char('a') => inspector.select_all(),
+                        _ => {}
                     }
-                    KeyCode::Char('j') => {
-                        app.increase_soltab(1, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
-                        }
+                }
+            }
+        },
+        CurrentScreen::Plot => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.cancel_view();
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Char('a') => app.set_plot_quantity(PlotQuantity::Amplitude),
+            KeyCode::Char('p') => app.set_plot_quantity(PlotQuantity::Phase),
+            KeyCode::Char('f') => {
+                if let Some(plot) = app.plot.as_mut() {
+                    plot.show_flagged = !plot.show_flagged;
+                }
+            }
+            _ => {
+                if let Some(plot) = app.plot.as_mut() {
+                    let selection = &mut plot.source.selection;
+                    match key.code {
+                        KeyCode::Char('[') => selection.next_antenna(true, false),
+                        KeyCode::Char(']') => selection.next_antenna(true, true),
+                        KeyCode::Char('{') => selection.next_antenna(false, false),
+                        KeyCode::Char('}') => selection.next_antenna(false, true),
+                        KeyCode::Char('c') => selection.next_correlation(),
+                        KeyCode::Char('<') => selection.shift_channels(false),
+                        KeyCode::Char('>') => selection.shift_channels(true),
+                        KeyCode::Char('-') => selection.resize_channels(false),
+                        KeyCode::Char('+') => selection.resize_channels(true),
+                        _ => return Ok(false),
                     }
-                    KeyCode::PageUp => {
-                        app.decrease_soltab(1, "view");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
-                        }
+                    app.reload_plot();
+                }
+            }
+        },
+        CurrentScreen::Waterfall => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.cancel_view();
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {
+                if let Some(waterfall) = app.waterfall.as_mut() {
+                    let selection = &mut waterfall.source.selection;
+                    match key.code {
+                        KeyCode::Char('[') => selection.next_antenna(true, false),
+                        KeyCode::Char(']') => selection.next_antenna(true, true),
+                        KeyCode::Char('{') => selection.next_antenna(false, false),
+                        KeyCode::Char('}') => selection.next_antenna(false, true),
+                        KeyCode::Char('c') => selection.next_correlation(),
+                        _ => return Ok(false),
                     }
-                    KeyCode::PageDown => {
-                        app.increase_soltab(1, "view");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
+                    app.reload_waterfall();
+                }
+            }
+        },
+        CurrentScreen::UvCoverage => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.cancel_view();
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {
+                if let Some(uv_plot) = app.uv_plot.as_mut() {
+                    match key.code {
+                        KeyCode::Char('c') => uv_plot.toggle_station_type(StationType::Core),
+                        KeyCode::Char('r') => uv_plot.toggle_station_type(StationType::Remote),
+                        KeyCode::Char('i') => {
+                            uv_plot.toggle_station_type(StationType::International)
                         }
+                        KeyCode::Char('f') => uv_plot.toggle_unflagged_only(),
+                        _ => {}
                     }
-                    KeyCode::Char('J') => {
-                        app.increase_soltab(10, "data");
-                        app.update_soltabs();
-                        match app.currently_editing {
-                            CurrentlyEditing::Table => app.select(true),
-                            CurrentlyEditing::Information => app.select(false),
-                            _ => {},
-                        }
+                }
+            }
+        },
+        CurrentScreen::FlagStats => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.cancel_view();
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Left | KeyCode::Char('h') => app.next_flag_table(false),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => app.next_flag_table(true),
+            KeyCode::Up | KeyCode::Char('k') => app.move_flag_row(1, false),
+            KeyCode::Down | KeyCode::Char('j') => app.move_flag_row(1, true),
+            KeyCode::PageUp | KeyCode::Char('K') => app.move_flag_row(10, false),
+            KeyCode::PageDown | KeyCode::Char('J') => app.move_flag_row(10, true),
+            KeyCode::Char('s') => app.toggle_flag_sort(),
+            _ => {}
+        },
+        CurrentScreen::ColumnStats => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.stats_job = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Keywords => {
+            if let KeyCode::Esc | KeyCode::Char('q') = key.code {
+                app.current_screen = CurrentScreen::Main;
+            } else if let Some(keywords) = app.keywords.as_mut() {
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => keywords.move_selection(1, false),
+                    KeyCode::Down | KeyCode::Char('j') => keywords.move_selection(1, true),
+                    KeyCode::PageUp | KeyCode::Char('K') => keywords.move_selection(10, false),
+                    KeyCode::PageDown | KeyCode::Char('J') => keywords.move_selection(10, true),
+                    KeyCode::Left | KeyCode::Char('h') => keywords.expand(false),
+                    KeyCode::Right | KeyCode::Char('l') => keywords.expand(true),
+                    KeyCode::Enter | KeyCode::Char(' ') => keywords.toggle(),
+                    _ => {}
+                }
+            }
+        }
+        CurrentScreen::Storage => {
+            if let KeyCode::Esc | KeyCode::Char('q') = key.code {
+                app.current_screen = CurrentScreen::Main;
+            } else if let Some(storage) = app.storage.as_mut() {
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => storage.move_selection(1, false),
+                    KeyCode::Down | KeyCode::Char('j') => storage.move_selection(1, true),
+                    KeyCode::PageUp | KeyCode::Char('K') => storage.move_selection(10, false),
+                    KeyCode::PageDown | KeyCode::Char('J') => storage.move_selection(10, true),
+                    _ => {}
+                }
+            }
+        }
+        CurrentScreen::Query => match key.code {
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Enter => {
+                app.apply_query()?;
+            }
+            KeyCode::Backspace => {
+                app.query_input.pop();
+            }
+            KeyCode::Char(c) => {
+                app.query_input.push(c);
+            }
+            _ => {}
+        },
+        CurrentScreen::GoTo => match key.code {
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Enter => {
+                app.go_to()?;
+            }
+            KeyCode::Backspace => {
+                app.goto_input.pop();
+            }
+            KeyCode::Char(c) => {
+                app.goto_input.push(c);
+            }
+            _ => {}
+        },
+        CurrentScreen::StatsRange => match key.code {
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Enter => {
+                app.start_column_stats_in_range()?;
+            }
+            KeyCode::Backspace => {
+                app.stats_range_input.pop();
+            }
+            KeyCode::Char(c) => {
+                app.stats_range_input.push(c);
+            }
+            _ => {}
+        },
+        CurrentScreen::Search => match key.code {
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Enter => {
+                app.accept_search()?;
+            }
+            KeyCode::Up => app.move_search_selection(false),
+            KeyCode::Down | KeyCode::Tab => app.move_search_selection(true),
+            KeyCode::Backspace => {
+                app.search_input.pop();
+                app.update_search();
+            }
+            KeyCode::Char(c) => {
+                app.search_input.push(c);
+                app.update_search();
+            }
+            _ => {}
+        },
+        CurrentScreen::Export => {
+            if app.export_job.is_some() {
+                if key.code == KeyCode::Esc {
+                    app.cancel_export();
+                    app.current_screen = CurrentScreen::Main;
+                }
+            } else if app.export_message.is_some() {
+                if let KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter = key.code {
+                    app.current_screen = CurrentScreen::Main;
+                }
+            } else if let Some(form) = app.export_form.as_mut() {
+                match key.code {
+                    KeyCode::Esc => {
+                        app.current_screen = CurrentScreen::Main;
                     }
                     KeyCode::Enter => {
-                        app.select(true);
+                        app.start_export()?;
                     }
-                    _ => {}
-                },
-                CurrentScreen::Exiting => match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('q') => {
-                        return Ok(true);
+                    KeyCode::Tab => form.next_field(),
+                    KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if form.field == 3 => {
+                        form.toggle_complex();
                     }
-                    KeyCode::Char('n') => {
-                        //return Ok(false);
-                        app.current_screen = CurrentScreen::Main;
+                    KeyCode::Backspace => {
+                        if let Some(input) = form.input() {
+                            input.pop();
+                        }
                     }
-                    _ => {}
-                },
-                CurrentScreen::Help => match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        app.current_screen = CurrentScreen::Main;
+                    KeyCode::Char(c) => {
+                        if let Some(input) = form.input() {
+                            input.push(c);
+                        }
                     }
                     _ => {}
-                },
+                }
             }
         }
+        CurrentScreen::Diff => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.diff_view = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_diff(-1)?,
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_diff(1)?,
+            KeyCode::PageUp | KeyCode::Char('K') => app.scroll_diff(-10)?,
+            KeyCode::PageDown | KeyCode::Char('J') => app.scroll_diff(10)?,
+            KeyCode::Char('n') => app.jump_diff(true)?,
+            KeyCode::Char('p') => app.jump_diff(false)?,
+            KeyCode::Char('[') | KeyCode::Char(']') => {
+                if let Some(view) = app.diff_view.as_mut() {
+                    view.structure_scroll = if key.code == KeyCode::Char('[') {
+                        view.structure_scroll.saturating_sub(1)
+                    } else {
+                        view.structure_scroll.saturating_add(1)
+                    };
+                }
+            }
+            _ => {}
+        },
+        CurrentScreen::CompareColumns => match key.code {
+            KeyCode::Esc => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Enter => {
+                app.start_comparison()?;
+            }
+            KeyCode::Tab => {
+                app.compare_per_row = !app.compare_per_row;
+            }
+            KeyCode::Backspace => {
+                app.compare_input.pop();
+            }
+            KeyCode::Char(c) => {
+                app.compare_input.push(c);
+            }
+            _ => {}
+        },
+        CurrentScreen::Comparison => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.comparison_job = None;
+                app.current_screen = CurrentScreen::Main;
+            }
+            KeyCode::Left | KeyCode::Char('h') => app.next_comparison_table(false),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => app.next_comparison_table(true),
+            KeyCode::Up | KeyCode::Char('k') => app.move_comparison_row(1, false),
+            KeyCode::Down | KeyCode::Char('j') => app.move_comparison_row(1, true),
+            KeyCode::PageUp | KeyCode::Char('K') => app.move_comparison_row(10, false),
+            KeyCode::PageDown | KeyCode::Char('J') => app.move_comparison_row(10, true),
+            KeyCode::Char('v') => app.open_plot()?,
+            KeyCode::Char('w') => app.open_waterfall()?,
+            _ => {}
+        },
+        CurrentScreen::Help => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
+        CurrentScreen::Error => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => {
+                app.current_screen = CurrentScreen::Main;
+            }
+            _ => {}
+        },
     }
+    Ok(false)
 }
@@ -0,0 +1,270 @@
+use rubbl_casatables::{Table, TableRecord};
+
+/// Convert a time in MJD seconds to a calendar date and time of day.
+///
+/// Returns (year, month, day, hour, minute, second).
+pub fn mjd_seconds_to_date(mjd_seconds: f64) -> (i64, u32, u32, u32, u32, f64) {
+    let days = (mjd_seconds / 86400.0).floor();
+    let seconds_of_day = mjd_seconds - days * 86400.0;
+    // MJD 0 is 1858-11-17, which is 678881 days after 0000-03-01 in the proleptic Gregorian calendar.
+    let z = days as i64 + 678881;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let hour = (seconds_of_day / 3600.0).floor();
+    let minute = ((seconds_of_day - hour * 3600.0) / 60.0).floor();
+    let second = seconds_of_day - hour * 3600.0 - minute * 60.0;
+    (year, month, day, hour as u32, minute as u32, second)
+}
+
+/// Convert a calendar date and time of day to a time in MJD seconds.
+pub fn date_to_mjd_seconds(year: i64, month: u32, day: u32, seconds_of_day: f64) -> f64 {
+    // The inverse of `mjd_seconds_to_date`, counting days from 0000-03-01.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 678881;
+    days as f64 * 86400.0 + seconds_of_day
+}
+
+/// Parse a UTC timestamp such as 2018-03-08T11:46:12, 2018/03/08/11:46:12 or 2018-03-08 11:46.
+///
+/// A time of day without a date, e.g. 11:46:12, is taken on the day of `reference_mjd_seconds`.
+pub fn parse_epoch(text: &str, reference_mjd_seconds: f64) -> Option<f64> {
+    let text = text.trim();
+    let (date, time) = match text.rfind(['T', ' ', '/']) {
+        Some(i) if text[i + 1..].contains(':') => (Some(&text[..i]), &text[i + 1..]),
+        _ if text.contains(':') => (None, text),
+        _ => (Some(text), ""),
+    };
+    let mut seconds_of_day = 0.0;
+    if !time.is_empty() {
+        let parts: Vec<&str> = time.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+        for (i, part) in parts.iter().enumerate() {
+            let value: f64 = part.parse().ok()?;
+            seconds_of_day += value * [3600.0, 60.0, 1.0][i];
+        }
+    }
+    let (year, month, day) = match date {
+        Some(date) => {
+            let parts: Vec<&str> = date.split(['-', '/']).collect();
+            let [year, month, day] = parts[..] else {
+                return None;
+            };
+            let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+            if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+                return None;
+            }
+            (year.parse().ok()?, month, day)
+        }
+        None => {
+            let (year, month, day, _, _, _) = mjd_seconds_to_date(reference_mjd_seconds);
+            (year, month, day)
+        }
+    };
+    Some(date_to_mjd_seconds(year, month, day, seconds_of_day))
+}
+
+/// Format a time in MJD seconds as an ISO-8601 UTC timestamp, e.g. 2018-03-08T11:46:12.000.
+pub fn format_epoch(mjd_seconds: f64) -> String {
+    // Round to milliseconds first, so that 59.9996 s does not end up printed as 60.000 s.
+    let mjd_seconds = (mjd_seconds * 1e3).round() / 1e3;
+    let (year, month, day, hour, minute, second) = mjd_seconds_to_date(mjd_seconds);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:06.3}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// Units of epoch columns, with the factor that converts them to seconds.
+const EPOCH_UNITS: [(&str, f64); 4] = [("s", 1.0), ("min", 60.0), ("h", 3600.0), ("d", 86400.0)];
+
+/// How values of a column should be presented to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnUnit {
+    /// An epoch measure; values are multiplied by the given factor to obtain MJD seconds in the
+    /// given reference frame, e.g. UTC or TAI.
+    Epoch(f64, String),
+    /// A quantity with the given unit, e.g. "s" or "m".
+    Quantity(String),
+    /// No unit information available.
+    None,
+}
+
+impl ColumnUnit {
+    /// Format a floating point value of a column with this unit.
+    ///
+    /// Epochs outside UTC carry the name of their frame; the unit of other quantities is left
+    /// to the header, see `header`.
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            ColumnUnit::Epoch(scale, frame) if frame == "UTC" => format_epoch(value * scale),
+            ColumnUnit::Epoch(scale, frame) => format!("{} {}", format_epoch(value * scale), frame),
+            ColumnUnit::Quantity(_) | ColumnUnit::None => format!("{}", value),
+        }
+    }
+
+    /// Unit of the raw values, e.g. "s" for TIME.
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            ColumnUnit::Epoch(scale, _) => EPOCH_UNITS
+                .iter()
+                .find(|(_, s)| s == scale)
+                .map(|(unit, _)| *unit),
+            ColumnUnit::Quantity(unit) => Some(unit),
+            ColumnUnit::None => None,
+        }
+    }
+
+    /// Unit to show once above the values, e.g. "m", or the frame of an epoch.
+    pub fn header(&self) -> Option<&str> {
+        match self {
+            ColumnUnit::Epoch(_, frame) => Some(frame),
+            ColumnUnit::Quantity(unit) => Some(unit),
+            ColumnUnit::None => None,
+        }
+    }
+}
+
+fn quantum_units(keywords: &mut TableRecord) -> Option<Vec<String>> {
+    keywords.get_field::<Vec<String>>("QuantumUnits").ok()
+}
+
+/// Reference frame of an epoch column, e.g. UTC, from its MEASINFO record.
+///
+/// Returns None for columns that are not epochs and for columns with a frame per row.
+fn epoch_frame(keywords: &mut TableRecord) -> Option<String> {
+    let mut measinfo = keywords.get_field::<TableRecord>("MEASINFO").ok()?;
+    let measure_type = measinfo.get_field::<String>("type").ok()?;
+    if !measure_type.eq_ignore_ascii_case("epoch") {
+        return None;
+    }
+    if measinfo.get_field::<String>("VarRefCol").is_ok() {
+        return None;
+    }
+    // Epochs without a Ref field are in UTC, as in casacore.
+    Some(
+        measinfo
+            .get_field::<String>("Ref")
+            .map_or("UTC".to_string(), |r| r.to_uppercase()),
+    )
+}
+
+/// Determine the unit of a column from its MEASINFO and QuantumUnits keywords.
+pub fn column_unit(table: &mut Table, column_name: &str) -> ColumnUnit {
+    let keyword_names = match table.column_keyword_names(column_name) {
+        Ok(names) => names,
+        Err(_) => return ColumnUnit::None,
+    };
+    if !keyword_names.iter().any(|k| k == "QuantumUnits") {
+        return ColumnUnit::None;
+    }
+    let mut keywords = match table.get_column_keyword_record(column_name) {
+        Ok(record) => record,
+        Err(_) => return ColumnUnit::None,
+    };
+    let units = quantum_units(&mut keywords).unwrap_or_default();
+    let unit = units.first().cloned().unwrap_or_default();
+    let frame = if keyword_names.iter().any(|k| k == "MEASINFO") {
+        epoch_frame(&mut keywords)
+    } else {
+        None
+    };
+    if let Some(frame) = frame {
+        if let Some((_, scale)) = EPOCH_UNITS.iter().find(|(u, _)| *u == unit) {
+            return ColumnUnit::Epoch(*scale, frame);
+        }
+    }
+    if unit.is_empty() {
+        ColumnUnit::None
+    } else {
+        ColumnUnit::Quantity(unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2018-03-08T11:46:12 UTC in MJD seconds; MJD 58185 is 2018-03-08.
+    const OBSERVATION: f64 = 58185.0 * 86400.0 + 11.0 * 3600.0 + 46.0 * 60.0 + 12.0;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(mjd_seconds_to_date(0.0), (1858, 11, 17, 0, 0, 0.0));
+        assert_eq!(mjd_seconds_to_date(OBSERVATION), (2018, 3, 8, 11, 46, 12.0));
+        // 2000-02-29 exists, 1900-02-29 does not.
+        assert_eq!(date_to_mjd_seconds(2000, 2, 29, 0.0), 51603.0 * 86400.0);
+        assert_eq!(
+            date_to_mjd_seconds(1900, 3, 1, 0.0) - date_to_mjd_seconds(1900, 2, 28, 0.0),
+            86400.0
+        );
+        // Dates before MJD 0 give negative times.
+        assert_eq!(mjd_seconds_to_date(-86400.0), (1858, 11, 16, 0, 0, 0.0));
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        let mut day = -100_000;
+        while day < 200_000 {
+            let mjd_seconds = day as f64 * 86400.0 + 43_210.5;
+            let (year, month, day_of_month, hour, minute, second) =
+                mjd_seconds_to_date(mjd_seconds);
+            let seconds_of_day = hour as f64 * 3600.0 + minute as f64 * 60.0 + second;
+            assert_eq!(
+                date_to_mjd_seconds(year, month, day_of_month, seconds_of_day),
+                mjd_seconds,
+                "day {}",
+                day
+            );
+            day += 97;
+        }
+    }
+
+    #[test]
+    fn format_and_parse_epochs() {
+        assert_eq!(format_epoch(OBSERVATION), "2018-03-08T11:46:12.000");
+        assert_eq!(
+            format_epoch(OBSERVATION - 0.0004),
+            "2018-03-08T11:46:12.000"
+        );
+        assert_eq!(
+            format_epoch(OBSERVATION + 47.9996),
+            "2018-03-08T11:47:00.000"
+        );
+        for text in [
+            "2018-03-08T11:46:12",
+            "2018/03/08/11:46:12",
+            "2018-03-08 11:46:12",
+        ] {
+            assert_eq!(parse_epoch(text, 0.0), Some(OBSERVATION), "{}", text);
+        }
+        assert_eq!(parse_epoch("11:46", OBSERVATION), Some(OBSERVATION - 12.0));
+        assert_eq!(parse_epoch("2018-03-08", 0.0), Some(58185.0 * 86400.0));
+        assert_eq!(parse_epoch("2018-13-08", 0.0), None);
+        assert_eq!(parse_epoch("11:46:12:00", 0.0), None);
+    }
+
+    #[test]
+    fn epochs_show_their_frame() {
+        let utc = ColumnUnit::Epoch(1.0, "UTC".to_string());
+        assert_eq!(utc.format(OBSERVATION), "2018-03-08T11:46:12.000");
+        let tai = ColumnUnit::Epoch(86400.0, "TAI".to_string());
+        assert_eq!(tai.format(58185.5), "2018-03-08T12:00:00.000 TAI");
+        let metres = ColumnUnit::Quantity("m".to_string());
+        assert_eq!(metres.format(1.5), "1.5");
+        assert_eq!(metres.header(), Some("m"));
+    }
+}
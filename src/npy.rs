@@ -0,0 +1,468 @@
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use rubbl_core::Complex;
+
+/// Magic string at the start of a .npy file.
+const MAGIC: &[u8] = b"\x93NUMPY";
+/// Largest size of a zip archive, or of an entry in it, without Zip64 extensions.
+const ZIP_LIMIT: u64 = u32::MAX as u64;
+/// Date of 1980-01-01 in MS-DOS format, written as the modification time of zip entries.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// An element type that can be stored in a NumPy array.
+pub trait NpyElement {
+    /// Data type as written in the header of a .npy file.
+    const DESCR: &'static str;
+
+    /// Append the element to `buf` in little-endian order.
+    fn write_le(&self, buf: &mut Vec<u8>);
+}
+
+macro_rules! npy_element {
+    ($t:ty, $descr:expr) => {
+        impl NpyElement for $t {
+            const DESCR: &'static str = $descr;
+
+            fn write_le(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    };
+}
+
+npy_element!(i8, "|i1");
+npy_element!(u8, "|u1");
+npy_element!(i16, "<i2");
+npy_element!(u16, "<u2");
+npy_element!(i32, "<i4");
+npy_element!(u32, "<u4");
+npy_element!(i64, "<i8");
+npy_element!(f32, "<f4");
+npy_element!(f64, "<f8");
+
+impl NpyElement for bool {
+    const DESCR: &'static str = "|b1";
+
+    fn write_le(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+}
+
+impl NpyElement for Complex<f32> {
+    const DESCR: &'static str = "<c8";
+
+    fn write_le(&self, buf: &mut Vec<u8>) {
+        self.re.write_le(buf);
+        self.im.write_le(buf);
+    }
+}
+
+impl NpyElement for Complex<f64> {
+    const DESCR: &'static str = "<c16";
+
+    fn write_le(&self, buf: &mut Vec<u8>) {
+        self.re.write_le(buf);
+        self.im.write_le(buf);
+    }
+}
+
+/// Size in bytes of an element of the given data type, e.g. 8 for `<c8`.
+fn item_size(descr: &str) -> usize {
+    descr[2..].parse().unwrap_or(1)
+}
+
+/// Header of a .npy file of format version 1.0, for an array in C order.
+pub fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let dims: Vec<String> = shape.iter().map(|n| n.to_string()).collect();
+    let shape_text = match dims.len() {
+        1 => format!("({},)", dims[0]),
+        _ => format!("({})", dims.join(", ")),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape_text
+    );
+    // Pad with spaces up to a newline, so the data starts at a multiple of 64 bytes.
+    let unpadded = MAGIC.len() + 4 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&[1, 0]);
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+/// Lookup table of the CRC-32 checksum used in zip archives.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            bit += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 checksum as used in zip archives.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Self {
+        Crc32(0xffff_ffff)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for b in data {
+            self.0 = CRC_TABLE[((self.0 ^ *b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.0 ^ 0xffff_ffff
+    }
+}
+
+/// An array written to an .npz archive.
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u64,
+    offset: u64,
+}
+
+/// The array being written.
+struct CurrentArray {
+    name: String,
+    size: u64,
+    written: u64,
+    offset: u64,
+    crc: Crc32,
+}
+
+/// Writes NumPy arrays, either a single one as a .npy file or several as an .npz archive.
+///
+/// Arrays are streamed: the header is written when an array is started and the data follows in
+/// C order. An .npz file is an uncompressed zip archive of .npy files, as written by
+/// `numpy.savez`. The CRC of each entry follows its data in a data descriptor, so the output does
+/// not have to be seekable. Zip64 extensions are not written, which limits an archive to 4 GiB.
+pub struct NpyWriter {
+    writer: Box<dyn Write>,
+    archive: bool,
+    offset: u64,
+    entries: Vec<ZipEntry>,
+    current: Option<CurrentArray>,
+}
+
+impl NpyWriter {
+    pub fn new(writer: Box<dyn Write>, archive: bool) -> Self {
+        NpyWriter {
+            writer,
+            archive,
+            offset: 0,
+            entries: Vec::new(),
+            current: None,
+        }
+    }
+
+    pub fn n_arrays(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.writer.write_all(data)?;
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    /// Start an array of the given data type and shape; `name` is the entry name in an archive.
+    pub fn start_array(&mut self, name: &str, descr: &str, shape: &[usize]) -> Result<()> {
+        if self.current.is_some() {
+            bail!("The previous array has not been finished");
+        }
+        if !self.archive && !self.entries.is_empty() {
+            bail!("A .npy file holds a single array; use an .npz file for several columns");
+        }
+        let header = npy_header(descr, shape);
+        let n_elements: usize = shape.iter().product();
+        let size = header.len() as u64 + (n_elements * item_size(descr)) as u64;
+        let name = format!("{}.npy", name);
+        let offset = self.offset;
+        if self.archive {
+            if self.offset + size + 2 * (30 + name.len() as u64) > ZIP_LIMIT {
+                bail!(
+                    "{} does not fit in an .npz file of at most 4 GiB; export fewer rows",
+                    name
+                );
+            }
+            let mut local = Vec::new();
+            local.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            local.extend_from_slice(&20u16.to_le_bytes());
+            // Bit 3: the CRC follows the data in a data descriptor.
+            local.extend_from_slice(&0x0008u16.to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes());
+            local.extend_from_slice(&DOS_DATE.to_le_bytes());
+            local.extend_from_slice(&0u32.to_le_bytes());
+            local.extend_from_slice(&(size as u32).to_le_bytes());
+            local.extend_from_slice(&(size as u32).to_le_bytes());
+            local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes());
+            local.extend_from_slice(name.as_bytes());
+            self.write_raw(&local)?;
+        }
+        self.current = Some(CurrentArray {
+            name,
+            size,
+            written: 0,
+            offset,
+            crc: Crc32::new(),
+        });
+        self.write(&header)
+    }
+
+    /// Write data of the current array.
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        let Some(current) = self.current.as_mut() else {
+            bail!("No array has been started");
+        };
+        current.written += data.len() as u64;
+        if current.written > current.size {
+            bail!("More data than fits the shape of {}", current.name);
+        }
+        if self.archive {
+            current.crc.update(data);
+        }
+        self.write_raw(data)
+    }
+
+    /// Finish the current array, which must have been written completely.
+    pub fn end_array(&mut self) -> Result<()> {
+        let Some(current) = self.current.take() else {
+            bail!("No array has been started");
+        };
+        if current.written != current.size {
+            bail!("{} is incomplete", current.name);
+        }
+        let entry = ZipEntry {
+            name: current.name,
+            crc: current.crc.finish(),
+            size: current.size,
+            offset: current.offset,
+        };
+        if self.archive {
+            let mut descriptor = Vec::new();
+            descriptor.extend_from_slice(&0x0807_4b50u32.to_le_bytes());
+            descriptor.extend_from_slice(&entry.crc.to_le_bytes());
+            descriptor.extend_from_slice(&(entry.size as u32).to_le_bytes());
+            descriptor.extend_from_slice(&(entry.size as u32).to_le_bytes());
+            self.write_raw(&descriptor)?;
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory of an archive and flush the output.
+    pub fn finish(mut self) -> Result<()> {
+        if self.current.is_some() {
+            bail!("The last array has not been finished");
+        }
+        if self.archive {
+            let start = self.offset;
+            let mut directory = Vec::new();
+            for entry in self.entries.iter() {
+                directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+                directory.extend_from_slice(&20u16.to_le_bytes());
+                directory.extend_from_slice(&20u16.to_le_bytes());
+                directory.extend_from_slice(&0x0008u16.to_le_bytes());
+                directory.extend_from_slice(&0u16.to_le_bytes());
+                directory.extend_from_slice(&0u16.to_le_bytes());
+                directory.extend_from_slice(&DOS_DATE.to_le_bytes());
+                directory.extend_from_slice(&entry.crc.to_le_bytes());
+                directory.extend_from_slice(&(entry.size as u32).to_le_bytes());
+                directory.extend_from_slice(&(entry.size as u32).to_le_bytes());
+                directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+                // Extra field, comment, disk number and internal and external attributes.
+                directory.extend_from_slice(&[0; 12]);
+                directory.extend_from_slice(&(entry.offset as u32).to_le_bytes());
+                directory.extend_from_slice(entry.name.as_bytes());
+            }
+            let n_entries = self.entries.len() as u16;
+            let mut end = Vec::new();
+            end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+            end.extend_from_slice(&[0; 4]);
+            end.extend_from_slice(&n_entries.to_le_bytes());
+            end.extend_from_slice(&n_entries.to_le_bytes());
+            end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+            end.extend_from_slice(&(start as u32).to_le_bytes());
+            end.extend_from_slice(&0u16.to_le_bytes());
+            self.write_raw(&directory)?;
+            self.write_raw(&end)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+
+    use super::*;
+
+    /// Output that stays readable after the writer that owns it is finished.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn u16_at(data: &[u8], i: usize) -> u16 {
+        u16::from_le_bytes(data[i..i + 2].try_into().unwrap())
+    }
+
+    fn u32_at(data: &[u8], i: usize) -> u32 {
+        u32::from_le_bytes(data[i..i + 4].try_into().unwrap())
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(data);
+        crc.finish()
+    }
+
+    fn f64_data(values: &[f64]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for v in values {
+            v.write_le(&mut buf);
+        }
+        buf
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        // Updating in parts gives the same checksum.
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn header_is_padded_to_64_bytes() {
+        let header = npy_header("<c8", &[3]);
+        assert_eq!(header.len() % 64, 0);
+        assert_eq!(&header[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(u16_at(&header, 8) as usize, header.len() - 10);
+        let dict = std::str::from_utf8(&header[10..]).unwrap();
+        assert!(dict.starts_with("{'descr': '<c8', 'fortran_order': False, 'shape': (3,), }"));
+        assert!(dict.ends_with(" \n"));
+        let header = npy_header("<f8", &[100_000, 64, 4]);
+        assert_eq!(header.len() % 64, 0);
+        assert!(String::from_utf8_lossy(&header).contains("'shape': (100000, 64, 4), "));
+    }
+
+    #[test]
+    fn single_array() {
+        let out = SharedBuf::default();
+        let mut writer = NpyWriter::new(Box::new(out.clone()), false);
+        writer.start_array("DATA", "<f8", &[2, 2]).unwrap();
+        writer.write(&f64_data(&[1.0, 2.0])).unwrap();
+        writer.write(&f64_data(&[3.0, 4.0])).unwrap();
+        writer.end_array().unwrap();
+        assert!(writer.start_array("FLAG", "|b1", &[2]).is_err());
+        writer.finish().unwrap();
+        let data = out.0.borrow();
+        let header = npy_header("<f8", &[2, 2]);
+        assert_eq!(data[..header.len()], header[..]);
+        assert_eq!(data[header.len()..], f64_data(&[1.0, 2.0, 3.0, 4.0])[..]);
+    }
+
+    #[test]
+    fn data_must_fill_the_shape() {
+        let mut writer = NpyWriter::new(Box::new(SharedBuf::default()), false);
+        writer.start_array("TIME", "<f8", &[1]).unwrap();
+        assert!(writer.end_array().is_err());
+        let mut writer = NpyWriter::new(Box::new(SharedBuf::default()), false);
+        writer.start_array("TIME", "<f8", &[1]).unwrap();
+        assert!(writer.write(&f64_data(&[1.0, 2.0])).is_err());
+    }
+
+    #[test]
+    fn archive_entries_and_central_directory() {
+        let out = SharedBuf::default();
+        let mut writer = NpyWriter::new(Box::new(out.clone()), true);
+        let arrays = [
+            ("row", "<i8", 7i64.to_le_bytes().to_vec()),
+            ("TIME", "<f8", f64_data(&[4.5e9])),
+        ];
+        for (name, descr, data) in arrays.iter() {
+            writer.start_array(name, descr, &[1]).unwrap();
+            writer.write(data).unwrap();
+            writer.end_array().unwrap();
+        }
+        assert_eq!(writer.n_arrays(), 2);
+        writer.finish().unwrap();
+        let zip = out.0.borrow();
+
+        // End of central directory record.
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+        assert_eq!(u16_at(&zip, end + 10), 2);
+        let directory_size = u32_at(&zip, end + 12) as usize;
+        let mut entry = u32_at(&zip, end + 16) as usize;
+        assert_eq!(entry + directory_size, end);
+
+        for (name, descr, data) in arrays.iter() {
+            let file_name = format!("{}.npy", name);
+            let content = [npy_header(descr, &[1]), data.clone()].concat();
+            assert_eq!(u32_at(&zip, entry), 0x0201_4b50);
+            let crc = u32_at(&zip, entry + 16);
+            assert_eq!(crc, crc32(&content));
+            assert_eq!(u32_at(&zip, entry + 20) as usize, content.len());
+            let name_len = u16_at(&zip, entry + 28) as usize;
+            assert_eq!(
+                &zip[entry + 46..entry + 46 + name_len],
+                file_name.as_bytes()
+            );
+
+            // The local header, the stored data and the data descriptor after it.
+            let local = u32_at(&zip, entry + 42) as usize;
+            assert_eq!(u32_at(&zip, local), 0x0403_4b50);
+            assert_eq!(u16_at(&zip, local + 6), 0x0008);
+            let start = local + 30 + name_len;
+            assert_eq!(&zip[local + 30..start], file_name.as_bytes());
+            assert_eq!(zip[start..start + content.len()], content[..]);
+            let descriptor = start + content.len();
+            assert_eq!(u32_at(&zip, descriptor), 0x0807_4b50);
+            assert_eq!(u32_at(&zip, descriptor + 4), crc);
+            entry += 46 + name_len;
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use anyhow::{bail, Result};
+use rubbl_casatables::{GlueDataType, Table};
+use rubbl_core::ndarray::Array2;
+use rubbl_core::Complex;
+
+use crate::compare::{ColumnPair, CompareOp};
+use crate::reader::{Block, ColumnReader};
+
+/// Memory for cached cells of the visibility and flag columns.
+const CACHE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Quantity of a complex visibility to plot.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlotQuantity {
+    Amplitude,
+    Phase,
+}
+
+impl PlotQuantity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlotQuantity::Amplitude => "Amplitude",
+            PlotQuantity::Phase => "Phase (deg)",
+        }
+    }
+
+    pub fn apply(&self, value: Complex<f64>) -> f64 {
+        match self {
+            PlotQuantity::Amplitude => value.norm(),
+            PlotQuantity::Phase => value.arg().to_degrees(),
+        }
+    }
+}
+
+/// Baseline, correlation and channel range to show data for.
+#[derive(Clone)]
+pub struct BaselineSelection {
+    pub antenna1: i32,
+    pub antenna2: i32,
+    pub n_antennas: i32,
+    pub correlation: usize,
+    pub n_corr: usize,
+    /// Channel range; start is inclusive, end is exclusive.
+    pub chan_start: usize,
+    pub chan_end: usize,
+    pub n_chan: usize,
+}
+
+impl BaselineSelection {
+    pub fn next_antenna(&mut self, first: bool, forwards: bool) {
+        let n = self.n_antennas.max(1);
+        let antenna = if first {
+            &mut self.antenna1
+        } else {
+            &mut self.antenna2
+        };
+        *antenna = if forwards {
+            (*antenna + 1).rem_euclid(n)
+        } else {
+            (*antenna - 1).rem_euclid(n)
+        };
+    }
+
+    pub fn next_correlation(&mut self) {
+        self.correlation = (self.correlation + 1) % self.n_corr.max(1);
+    }
+
+    /// Slide the channel range, keeping its width.
+    pub fn shift_channels(&mut self, forwards: bool) {
+        if forwards && self.chan_end < self.n_chan {
+            self.chan_start += 1;
+            self.chan_end += 1;
+        } else if !forwards && self.chan_start > 0 {
+            self.chan_start -= 1;
+            self.chan_end -= 1;
+        }
+    }
+
+    /// Grow or shrink the channel range at its end.
+    pub fn resize_channels(&mut self, grow: bool) {
+        if grow && self.chan_end < self.n_chan {
+            self.chan_end += 1;
+        } else if !grow && self.chan_end > self.chan_start + 1 {
+            self.chan_end -= 1;
+        }
+    }
+}
+
+/// The scalar columns of MAIN needed to find the rows belonging to a baseline.
+pub struct BaselineIndex {
+    pub antenna1: Vec<i32>,
+    pub antenna2: Vec<i32>,
+    pub time: Vec<f64>,
+}
+
+impl BaselineIndex {
+    pub fn load(table: &mut Table) -> Result<Self> {
+        Ok(BaselineIndex {
+            antenna1: table.get_col_as_vec::<i32>("ANTENNA1")?,
+            antenna2: table.get_col_as_vec::<i32>("ANTENNA2")?,
+            time: table.get_col_as_vec::<f64>("TIME")?,
+        })
+    }
+
+    /// Rows of the given baseline, in either antenna order.
+    pub fn rows(&self, antenna1: i32, antenna2: i32) -> Vec<u64> {
+        self.antenna1
+            .iter()
+            .zip(self.antenna2.iter())
+            .enumerate()
+            .filter(|(_, (&a1, &a2))| {
+                (a1 == antenna1 && a2 == antenna2) || (a1 == antenna2 && a2 == antenna1)
+            })
+            .map(|(row, _)| row as u64)
+            .collect()
+    }
+}
+
+/// Read a (nchan, ncorr) cell of a complex column as double precision.
+pub fn read_visibilities(
+    reader: &mut ColumnReader,
+    table: &mut Table,
+    column_name: &str,
+    row: u64,
+) -> Result<Array2<Complex<f64>>> {
+    let cell = match reader.cell(table, column_name, row)? {
+        (Block::Complex(cells), index) => cells
+            .view(index)
+            .map(|c| c.mapv(|v| Complex::new(v.re as f64, v.im as f64))),
+        (Block::DComplex(cells), index) => cells.view(index).map(|c| c.to_owned()),
+        _ => bail!("Column {} is not complex", column_name),
+    };
+    match cell {
+        Some(cell) => Ok(cell.into_dimensionality()?),
+        None => bail!("Failed to read row {} of column {}", row, column_name),
+    }
+}
+
+/// Read the FLAG cell of a row, or no flags if the table has no FLAG column or the cell does
+/// not match the shape of the data.
+pub fn read_flags(
+    reader: &mut ColumnReader,
+    table: &mut Table,
+    has_flag: bool,
+    row: u64,
+    shape: &[usize],
+) -> Result<Array2<bool>> {
+    if has_flag {
+        if let (Block::Bool(cells), index) = reader.cell(table, "FLAG", row)? {
+            if let Some(flags) = cells.view(index).filter(|f| f.shape() == shape) {
+                return Ok(flags.to_owned().into_dimensionality()?);
+            }
+        }
+    }
+    Ok(Array2::from_elem((shape[0], shape[1]), false))
+}
+
+/// Whether a column holds complex values that can be plotted.
+pub fn is_complex(data_type: GlueDataType) -> bool {
+    matches!(
+        data_type,
+        GlueDataType::TpComplex
+            | GlueDataType::TpDComplex
+            | GlueDataType::TpArrayComplex
+            | GlueDataType::TpArrayDComplex
+    )
+}
+
+/// Combine two cells element by element, e.g. DATA - MODEL_DATA.
+pub fn combine(
+    data: Array2<Complex<f64>>,
+    other: &Array2<Complex<f64>>,
+    op: CompareOp,
+) -> Result<Array2<Complex<f64>>> {
+    if data.dim() != other.dim() {
+        bail!("Cells of the compared columns have different shapes");
+    }
+    let mut data = data;
+    data.zip_mut_with(other, |a, b| *a = op.apply(*a, *b));
+    Ok(data)
+}
+
+/// Column whose visibilities are shown, or pair of columns whose combination is shown.
+#[derive(Clone)]
+pub enum Visibilities {
+    Column(String),
+    Pair(ColumnPair),
+}
+
+impl Visibilities {
+    /// Name of the column, or of the combination of columns, e.g. DATA - MODEL_DATA.
+    pub fn label(&self) -> String {
+        match self {
+            Visibilities::Column(column) => column.clone(),
+            Visibilities::Pair(pair) => pair.label(),
+        }
+    }
+}
+
+/// Shared setup for views of complex visibility columns of one baseline.
+pub struct VisibilitySource {
+    pub column: String,
+    /// Column the values are combined with and how, e.g. MODEL_DATA to show DATA - MODEL_DATA.
+    pub other: Option<(String, CompareOp)>,
+    pub has_flag: bool,
+    pub index: BaselineIndex,
+    pub selection: BaselineSelection,
+    reader: ColumnReader,
+}
+
+impl VisibilitySource {
+    pub fn new(table: &mut Table, column_name: &str, n_antennas: usize) -> Result<Self> {
+        let col_desc = table.get_col_desc(column_name)?;
+        let data_type = col_desc.data_type();
+        if !is_complex(data_type) || col_desc.is_scalar() {
+            bail!(
+                "Column {} does not contain complex visibilities",
+                column_name
+            );
+        }
+        if table.n_rows() == 0 {
+            bail!("Table has no rows");
+        }
+        let has_flag = table.column_names()?.iter().any(|c| c == "FLAG");
+        let index = BaselineIndex::load(table)?;
+        let mut reader = ColumnReader::sparse(CACHE_BYTES);
+        let shape = read_visibilities(&mut reader, table, column_name, 0)?
+            .shape()
+            .to_vec();
+        let n_antennas = if n_antennas > 0 {
+            n_antennas as i32
+        } else {
+            index
+                .antenna1
+                .iter()
+                .chain(index.antenna2.iter())
+                .max()
+                .map_or(1, |m| m + 1)
+        };
+        // Start with the first cross-correlation in the data.
+        let (antenna1, antenna2) = index
+            .antenna1
+            .iter()
+            .zip(index.antenna2.iter())
+            .find(|(a1, a2)| a1 != a2)
+            .map_or((0, 0), |(a1, a2)| (*a1, *a2));
+        Ok(VisibilitySource {
+            column: column_name.to_string(),
+            other: None,
+            has_flag,
+            index,
+            selection: BaselineSelection {
+                antenna1,
+                antenna2,
+                n_antennas,
+                correlation: 0,
+                n_corr: shape[1],
+                chan_start: 0,
+                chan_end: shape[0],
+                n_chan: shape[0],
+            },
+            reader,
+        })
+    }
+
+    /// Set up the views of a column or of a pair of columns.
+    pub fn open(table: &mut Table, visibilities: &Visibilities, n_antennas: usize) -> Result<Self> {
+        match visibilities {
+            Visibilities::Column(column) => VisibilitySource::new(table, column, n_antennas),
+            Visibilities::Pair(pair) => VisibilitySource::for_pair(table, pair, n_antennas),
+        }
+    }
+
+    /// Show the difference or ratio of the two columns of a pair instead of a single column.
+    pub fn for_pair(table: &mut Table, pair: &ColumnPair, n_antennas: usize) -> Result<Self> {
+        let mut source = VisibilitySource::new(table, &pair.first, n_antennas)?;
+        let data_type = table.get_col_desc(&pair.second)?.data_type();
+        if !is_complex(data_type) {
+            bail!(
+                "Column {} does not contain complex visibilities",
+                pair.second
+            );
+        }
+        source.other = Some((pair.second.clone(), pair.op));
+        Ok(source)
+    }
+
+    /// Name of the column shown, or of the combination of columns, e.g. DATA - MODEL_DATA.
+    pub fn label(&self) -> String {
+        match &self.other {
+            Some((other, op)) => format!("{} {} {}", self.column, op.symbol(), other),
+            None => self.column.clone(),
+        }
+    }
+
+    /// Read the (nchan, ncorr) cell of a row, combined with the other column if there is one.
+    pub fn read(&mut self, table: &mut Table, row: u64) -> Result<Array2<Complex<f64>>> {
+        let data = read_visibilities(&mut self.reader, table, &self.column, row)?;
+        match &self.other {
+            Some((other, op)) => combine(
+                data,
+                &read_visibilities(&mut self.reader, table, other, row)?,
+                *op,
+            ),
+            None => Ok(data),
+        }
+    }
+
+    /// Read the FLAG cell of a row, see `read_flags`.
+    pub fn read_flags(
+        &mut self,
+        table: &mut Table,
+        row: u64,
+        shape: &[usize],
+    ) -> Result<Array2<bool>> {
+        read_flags(&mut self.reader, table, self.has_flag, row, shape)
+    }
+}
+
+/// Amplitude or phase against time for a single baseline.
+pub struct VisibilityPlot {
+    pub source: VisibilitySource,
+    pub quantity: PlotQuantity,
+    pub show_flagged: bool,
+    /// Time of the first point, in MJD seconds; points are relative to it.
+    pub time_origin: f64,
+    pub points: Vec<(f64, f64)>,
+    pub flagged_points: Vec<(f64, f64)>,
+}
+
+impl VisibilityPlot {
+    /// Read the data of the selected baseline; see `reload` for `progress`.
+    pub fn new(
+        table: &mut Table,
+        source: VisibilitySource,
+        progress: &dyn Fn(f64) -> Result<()>,
+    ) -> Result<Self> {
+        let mut plot = VisibilityPlot {
+            source,
+            quantity: PlotQuantity::Amplitude,
+            show_flagged: true,
+            time_origin: 0.0,
+            points: Vec::new(),
+            flagged_points: Vec::new(),
+        };
+        plot.reload(table, progress)?;
+        Ok(plot)
+    }
+
+    /// Read the data of the selected baseline again, e.g. after changing the selection.
+    ///
+    /// `progress` is told the fraction of rows done before each row; an error from it stops
+    /// reading.
+    pub fn reload(
+        &mut self,
+        table: &mut Table,
+        progress: &dyn Fn(f64) -> Result<()>,
+    ) -> Result<()> {
+        self.points.clear();
+        self.flagged_points.clear();
+        let selection = self.source.selection.clone();
+        let rows = self
+            .source
+            .index
+            .rows(selection.antenna1, selection.antenna2);
+        self.time_origin = rows
+            .first()
+            .map_or(0.0, |&r| self.source.index.time[r as usize]);
+        for (i, &row) in rows.iter().enumerate() {
+            progress(i as f64 / rows.len() as f64)?;
+            let data = self.source.read(table, row)?;
+            // Cells may vary in shape; skip rows without the selected correlation.
+            if selection.correlation >= data.shape()[1] {
+                continue;
+            }
+            let flags = self.source.read_flags(table, row, data.shape())?;
+            let mut sum = Complex::new(0.0, 0.0);
+            let mut sum_flagged = Complex::new(0.0, 0.0);
+            let mut n = 0;
+            let mut n_flagged = 0;
+            let chan_end = selection.chan_end.min(data.shape()[0]);
+            for chan in selection.chan_start..chan_end {
+                let value = data[[chan, selection.correlation]];
+                if flags[[chan, selection.correlation]] {
+                    sum_flagged += value;
+                    n_flagged += 1;
+                } else {
+                    sum += value;
+                    n += 1;
+                }
+            }
+            let t = self.source.index.time[row as usize] - self.time_origin;
+            if n > 0 {
+                self.points.push((t, self.quantity.apply(sum / n as f64)));
+            } else if n_flagged > 0 {
+                self.flagged_points
+                    .push((t, self.quantity.apply(sum_flagged / n_flagged as f64)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bounds of the x and y axes covering all points that are shown.
+    pub fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let mut x = [f64::INFINITY, f64::NEG_INFINITY];
+        let mut y = [f64::INFINITY, f64::NEG_INFINITY];
+        let flagged: &[(f64, f64)] = if self.show_flagged {
+            &self.flagged_points
+        } else {
+            &[]
+        };
+        for (px, py) in self.points.iter().chain(flagged.iter()) {
+            if !py.is_finite() {
+                continue;
+            }
+            x = [x[0].min(*px), x[1].max(*px)];
+            y = [y[0].min(*py), y[1].max(*py)];
+        }
+        if x[0] > x[1] {
+            x = [0.0, 1.0];
+        }
+        if y[0] > y[1] {
+            y = [0.0, 1.0];
+        }
+        if x[0] == x[1] {
+            x[1] += 1.0;
+        }
+        if y[0] == y[1] {
+            y = [y[0] - 1.0, y[1] + 1.0];
+        }
+        (x, y)
+    }
+}
@@ -0,0 +1,669 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use rubbl_casatables::{GlueDataType, Table};
+
+/// A token of a query.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 17] = [
+    "==", "!=", "<>", "<=", ">=", "&&", "||", "=", "<", ">", "!", "(", ")", "[", "]", ",", ":",
+];
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|d| *d == c)
+                .map(|p| i + 1 + p);
+            let Some(end) = end else {
+                bail!("Unterminated string starting at position {}", i + 1);
+            };
+            tokens.push(Token::Text(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if c.is_ascii_digit()
+            || c == '.'
+            || (c == '-' && i + 1 < chars.len() && chars[i + 1].is_ascii_digit())
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || chars[i] == '.'
+                    || chars[i] == 'e'
+                    || chars[i] == 'E'
+                    || ((chars[i] == '-' || chars[i] == '+') && matches!(chars[i - 1], 'e' | 'E')))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse() {
+                Ok(number) => tokens.push(Token::Number(number)),
+                Err(_) => bail!("Invalid number {}", text),
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) else {
+                bail!("Unexpected character '{}' at position {}", c, i + 1);
+            };
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// A value to compare a column with.
+#[derive(Clone, Debug)]
+enum Literal {
+    Number(f64),
+    /// A name, such as a station name for ANTENNA1, looked up in the labels of the column.
+    Text(String),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Number(n) => write!(f, "{}", n),
+            Literal::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// An item of an IN list: a single value or an inclusive range.
+#[derive(Clone, Debug)]
+enum SetItem {
+    Value(Literal),
+    Range(Literal, Literal),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(String, CompareOp, Literal),
+    In(String, Vec<SetItem>),
+}
+
+/// Recursive descent parser for the query language.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if !self.is_symbol(symbol) {
+            bail!("Expected '{}' but found {}", symbol, self.describe_next());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(Token::Word(w)) => format!("'{}'", w),
+            Some(Token::Number(n)) => format!("'{}'", n),
+            Some(Token::Text(t)) => format!("'{}'", t),
+            Some(Token::Symbol(s)) => format!("'{}'", s),
+            None => "the end of the query".to_string(),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.is_keyword("OR") || self.is_symbol("||") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.is_keyword("AND") || self.is_symbol("&&") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.is_keyword("NOT") || self.is_symbol("!") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.is_symbol("(") {
+            self.pos += 1;
+            let expr = self.or()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Expr> {
+        let column = match self.next() {
+            Some(Token::Word(w)) => w,
+            _ => {
+                self.pos -= 1;
+                bail!("Expected a column name but found {}", self.describe_next());
+            }
+        };
+        let negate = self.is_keyword("NOT");
+        if negate {
+            self.pos += 1;
+        }
+        let expr = if self.is_keyword("IN") {
+            self.pos += 1;
+            Expr::In(column, self.set()?)
+        } else if self.is_keyword("BETWEEN") {
+            self.pos += 1;
+            let low = self.literal()?;
+            if !self.is_keyword("AND") {
+                bail!(
+                    "Expected AND after BETWEEN but found {}",
+                    self.describe_next()
+                );
+            }
+            self.pos += 1;
+            let high = self.literal()?;
+            Expr::In(column, vec![SetItem::Range(low, high)])
+        } else if negate {
+            bail!(
+                "Expected IN or BETWEEN after NOT but found {}",
+                self.describe_next()
+            );
+        } else {
+            let op = match self.next() {
+                Some(Token::Symbol("=")) | Some(Token::Symbol("==")) => CompareOp::Eq,
+                Some(Token::Symbol("!=")) | Some(Token::Symbol("<>")) => CompareOp::Ne,
+                Some(Token::Symbol("<")) => CompareOp::Lt,
+                Some(Token::Symbol("<=")) => CompareOp::Le,
+                Some(Token::Symbol(">")) => CompareOp::Gt,
+                Some(Token::Symbol(">=")) => CompareOp::Ge,
+                _ => {
+                    self.pos -= 1;
+                    bail!(
+                        "Expected a comparison after {} but found {}",
+                        column,
+                        self.describe_next()
+                    );
+                }
+            };
+            Expr::Compare(column, op, self.literal()?)
+        };
+        Ok(if negate {
+            Expr::Not(Box::new(expr))
+        } else {
+            expr
+        })
+    }
+
+    fn set(&mut self) -> Result<Vec<SetItem>> {
+        let close = if self.is_symbol("[") {
+            "]"
+        } else if self.is_symbol("(") {
+            ")"
+        } else {
+            bail!(
+                "Expected '[' or '(' after IN but found {}",
+                self.describe_next()
+            );
+        };
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            let value = self.literal()?;
+            if self.is_symbol(":") {
+                self.pos += 1;
+                items.push(SetItem::Range(value, self.literal()?));
+            } else {
+                items.push(SetItem::Value(value));
+            }
+            if self.is_symbol(",") {
+                self.pos += 1;
+            } else {
+                self.expect_symbol(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<Literal> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Literal::Number(n)),
+            Some(Token::Text(t)) => Ok(Literal::Text(t)),
+            Some(Token::Word(w)) => Ok(match w.to_uppercase().as_str() {
+                "T" | "TRUE" => Literal::Number(1.0),
+                "F" | "FALSE" => Literal::Number(0.0),
+                _ => Literal::Text(w),
+            }),
+            _ => {
+                self.pos -= 1;
+                bail!("Expected a value but found {}", self.describe_next());
+            }
+        }
+    }
+}
+
+fn parse(query: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(query)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    if parser.peek().is_some() {
+        bail!(
+            "Unexpected {} after the end of the query",
+            parser.describe_next()
+        );
+    }
+    Ok(expr)
+}
+
+/// Values of a scalar column as double precision; booleans become 0 and 1.
+fn read_scalar_column(table: &mut Table, column_name: &str) -> Result<Vec<f64>> {
+    let desc = table.get_col_desc(column_name)?;
+    if !desc.is_scalar() {
+        bail!(
+            "Only scalar columns can be queried, but {} holds arrays",
+            column_name
+        );
+    }
+    let values = match desc.data_type() {
+        GlueDataType::TpBool => table
+            .get_col_as_vec::<bool>(column_name)?
+            .into_iter()
+            .map(|v| v as u8 as f64)
+            .collect(),
+        GlueDataType::TpShort => to_f64(table.get_col_as_vec::<i16>(column_name)?),
+        GlueDataType::TpUShort => to_f64(table.get_col_as_vec::<u16>(column_name)?),
+        GlueDataType::TpInt => to_f64(table.get_col_as_vec::<i32>(column_name)?),
+        GlueDataType::TpUInt => to_f64(table.get_col_as_vec::<u32>(column_name)?),
+        GlueDataType::TpFloat => to_f64(table.get_col_as_vec::<f32>(column_name)?),
+        GlueDataType::TpDouble => table.get_col_as_vec::<f64>(column_name)?,
+        GlueDataType::TpInt64 => table
+            .get_col_as_vec::<i64>(column_name)?
+            .into_iter()
+            .map(|v| v as f64)
+            .collect(),
+        other => bail!("Columns of type {} cannot be queried", other),
+    };
+    Ok(values)
+}
+
+fn to_f64<T: Into<f64>>(values: Vec<T>) -> Vec<f64> {
+    values.into_iter().map(|v| v.into()).collect()
+}
+
+/// A condition with its column read and its names resolved to numbers.
+enum Condition {
+    Or(Box<Condition>, Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Compare(usize, CompareOp, f64),
+    /// Matches when the value is one of the listed values or lies in one of the ranges.
+    In(usize, Vec<(f64, f64)>),
+}
+
+impl Condition {
+    fn matches(&self, columns: &[Vec<f64>], row: usize) -> bool {
+        match self {
+            Condition::Or(a, b) => a.matches(columns, row) || b.matches(columns, row),
+            Condition::And(a, b) => a.matches(columns, row) && b.matches(columns, row),
+            Condition::Not(a) => !a.matches(columns, row),
+            Condition::Compare(column, op, value) => {
+                let v = columns[*column][row];
+                match op {
+                    CompareOp::Eq => v == *value,
+                    CompareOp::Ne => v != *value,
+                    CompareOp::Lt => v < *value,
+                    CompareOp::Le => v <= *value,
+                    CompareOp::Gt => v > *value,
+                    CompareOp::Ge => v >= *value,
+                }
+            }
+            Condition::In(column, ranges) => {
+                let v = columns[*column][row];
+                ranges.iter().any(|(low, high)| *low <= v && v <= *high)
+            }
+        }
+    }
+}
+
+/// Builds conditions, reading each column that is used once.
+struct Resolver<'a> {
+    read_column: &'a mut dyn FnMut(&str) -> Result<Vec<f64>>,
+    column_names: Vec<String>,
+    labels: &'a HashMap<String, Vec<String>>,
+    used: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl Resolver<'_> {
+    fn column(&mut self, name: &str) -> Result<usize> {
+        // Column names are matched without regard to case, as in TaQL.
+        let Some(name) = self
+            .column_names
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(name))
+            .cloned()
+        else {
+            bail!("Unknown column {}", name);
+        };
+        if let Some(i) = self.used.iter().position(|c| *c == name) {
+            return Ok(i);
+        }
+        self.columns.push((self.read_column)(&name)?);
+        self.used.push(name);
+        Ok(self.used.len() - 1)
+    }
+
+    /// Values a literal stands for: names match the labels that equal or start with them.
+    fn values(&self, column: usize, literal: &Literal) -> Result<Vec<f64>> {
+        match literal {
+            Literal::Number(n) => Ok(vec![*n]),
+            Literal::Text(text) => {
+                let column_name = &self.used[column];
+                let Some(labels) = self.labels.get(column_name) else {
+                    bail!(
+                        "{} cannot be compared with the name '{}'",
+                        column_name,
+                        text
+                    );
+                };
+                let exact: Vec<f64> = labels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| l.eq_ignore_ascii_case(text))
+                    .map(|(i, _)| i as f64)
+                    .collect();
+                if !exact.is_empty() {
+                    return Ok(exact);
+                }
+                let prefix = text.to_uppercase();
+                let matching: Vec<f64> = labels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| l.to_uppercase().starts_with(&prefix))
+                    .map(|(i, _)| i as f64)
+                    .collect();
+                if matching.is_empty() {
+                    bail!("No {} is called '{}'", column_name, text);
+                }
+                Ok(matching)
+            }
+        }
+    }
+
+    fn single_value(&self, column: usize, literal: &Literal) -> Result<f64> {
+        match self.values(column, literal)?[..] {
+            [value] => Ok(value),
+            _ => bail!("'{}' matches several names; use = or IN instead", literal),
+        }
+    }
+
+    fn resolve(&mut self, expr: &Expr) -> Result<Condition> {
+        Ok(match expr {
+            Expr::Or(a, b) => Condition::Or(Box::new(self.resolve(a)?), Box::new(self.resolve(b)?)),
+            Expr::And(a, b) => {
+                Condition::And(Box::new(self.resolve(a)?), Box::new(self.resolve(b)?))
+            }
+            Expr::Not(a) => Condition::Not(Box::new(self.resolve(a)?)),
+            Expr::Compare(name, op, literal) => {
+                let column = self.column(name)?;
+                match op {
+                    // A name can stand for several values, e.g. both HBA ears of a core station.
+                    CompareOp::Eq | CompareOp::Ne => {
+                        let values = self.values(column, literal)?;
+                        let condition =
+                            Condition::In(column, values.into_iter().map(|v| (v, v)).collect());
+                        if let CompareOp::Ne = op {
+                            Condition::Not(Box::new(condition))
+                        } else {
+                            condition
+                        }
+                    }
+                    _ => Condition::Compare(column, *op, self.single_value(column, literal)?),
+                }
+            }
+            Expr::In(name, items) => {
+                let column = self.column(name)?;
+                let mut ranges = Vec::new();
+                for item in items {
+                    match item {
+                        SetItem::Value(literal) => {
+                            ranges.extend(self.values(column, literal)?.into_iter().map(|v| (v, v)))
+                        }
+                        SetItem::Range(low, high) => ranges.push((
+                            self.single_value(column, low)?,
+                            self.single_value(column, high)?,
+                        )),
+                    }
+                }
+                Condition::In(column, ranges)
+            }
+        })
+    }
+}
+
+/// Rows among the first `n_rows` that match a query, with the columns it uses read by
+/// `read_column`.
+fn matching_rows(
+    query: &str,
+    column_names: Vec<String>,
+    labels: &HashMap<String, Vec<String>>,
+    n_rows: u64,
+    read_column: &mut dyn FnMut(&str) -> Result<Vec<f64>>,
+) -> Result<Vec<u64>> {
+    let expr = parse(query)?;
+    let mut resolver = Resolver {
+        read_column,
+        column_names,
+        labels,
+        used: Vec::new(),
+        columns: Vec::new(),
+    };
+    let condition = resolver.resolve(&expr)?;
+    Ok((0..n_rows)
+        .filter(|row| condition.matches(&resolver.columns, *row as usize))
+        .collect())
+}
+
+/// Rows of a table that match a query, e.g. `ANTENNA1 = CS002 AND ANTENNA2 = RS503 AND
+/// SCAN_NUMBER = 3`.
+///
+/// Queries compare scalar columns with numbers using `= != < <= > >=`, test membership with
+/// `IN [1, 3:5]` or `BETWEEN 1 AND 5`, and combine conditions with AND, OR, NOT and brackets.
+/// Columns with labels, such as ANTENNA1, can also be compared with names.
+pub struct RowFilter {
+    pub query: String,
+    /// Matching rows in ascending order, shared with the loading thread.
+    pub rows: Arc<Vec<u64>>,
+}
+
+impl RowFilter {
+    pub fn new(
+        query: &str,
+        table: &mut Table,
+        labels: &HashMap<String, Vec<String>>,
+    ) -> Result<Self> {
+        let column_names = table.column_names()?;
+        let n_rows = table.n_rows();
+        let rows = matching_rows(query, column_names, labels, n_rows, &mut |name| {
+            read_scalar_column(table, name)
+        })?;
+        Ok(RowFilter {
+            query: query.trim().to_string(),
+            rows: Arc::new(rows),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows matching a query on a small table of baselines with station names.
+    fn query(text: &str) -> Result<Vec<u64>> {
+        let columns: HashMap<&str, Vec<f64>> = HashMap::from([
+            ("ANTENNA1", vec![0.0, 0.0, 1.0, 1.0, 2.0, 3.0]),
+            ("ANTENNA2", vec![1.0, 2.0, 2.0, 3.0, 3.0, 3.0]),
+            ("SCAN_NUMBER", vec![1.0, 1.0, 2.0, 2.0, 3.0, 3.0]),
+        ]);
+        let labels = HashMap::from([(
+            "ANTENNA1".to_string(),
+            vec![
+                "CS001HBA0".to_string(),
+                "CS001HBA1".to_string(),
+                "CS002HBA0".to_string(),
+                "RS503HBA".to_string(),
+            ],
+        )]);
+        let column_names = columns.keys().map(|c| c.to_string()).collect();
+        matching_rows(text, column_names, &labels, 6, &mut |name| {
+            Ok(columns[name].clone())
+        })
+    }
+
+    fn error(text: &str) -> String {
+        query(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            query("SCAN_NUMBER = 1 OR SCAN_NUMBER = 3 AND ANTENNA1 = 3").unwrap(),
+            vec![0, 1, 5]
+        );
+        assert_eq!(
+            query("(SCAN_NUMBER = 1 OR SCAN_NUMBER = 3) AND ANTENNA1 = 3").unwrap(),
+            vec![5]
+        );
+        assert_eq!(
+            query("NOT SCAN_NUMBER = 1 && ANTENNA2 == 3").unwrap(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            query("!(SCAN_NUMBER = 1 || SCAN_NUMBER = 2)").unwrap(),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn in_and_between() {
+        assert_eq!(query("ANTENNA2 IN [1, 3:5]").unwrap(), vec![0, 3, 4, 5]);
+        assert_eq!(query("ANTENNA2 NOT IN (1, 2)").unwrap(), vec![3, 4, 5]);
+        assert_eq!(
+            query("scan_number BETWEEN 2 AND 3").unwrap(),
+            vec![2, 3, 4, 5]
+        );
+        assert_eq!(
+            query("SCAN_NUMBER NOT BETWEEN 2 AND 3").unwrap(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn names_match_labels_exactly_or_by_prefix() {
+        assert_eq!(query("ANTENNA1 = 'cs001hba1'").unwrap(), vec![2, 3]);
+        // A prefix stands for every station it starts, e.g. both ears of CS001.
+        assert_eq!(query("ANTENNA1 = CS001").unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(query("ANTENNA1 != CS001").unwrap(), vec![4, 5]);
+        assert_eq!(query("ANTENNA1 IN [CS002, RS]").unwrap(), vec![4, 5]);
+        assert_eq!(query("ANTENNA1 >= CS002HBA0").unwrap(), vec![4, 5]);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(
+            error("ANTENNA1 < CS001"),
+            "'CS001' matches several names; use = or IN instead"
+        );
+        assert_eq!(error("ANTENNA1 = DE601"), "No ANTENNA1 is called 'DE601'");
+        assert_eq!(
+            error("ANTENNA2 = CS001"),
+            "ANTENNA2 cannot be compared with the name 'CS001'"
+        );
+        assert_eq!(error("FLAG_ROW = 1"), "Unknown column FLAG_ROW");
+        assert_eq!(
+            error("ANTENNA1 = 'CS001"),
+            "Unterminated string starting at position 12"
+        );
+        assert_eq!(
+            error("ANTENNA1 NOT = 1"),
+            "Expected IN or BETWEEN after NOT but found '='"
+        );
+        assert_eq!(
+            error("SCAN_NUMBER BETWEEN 1 OR 2"),
+            "Expected AND after BETWEEN but found 'OR'"
+        );
+        assert_eq!(
+            error("SCAN_NUMBER = 1 2"),
+            "Unexpected '2' after the end of the query"
+        );
+        assert_eq!(
+            error("(SCAN_NUMBER = 1"),
+            "Expected ')' but found the end of the query"
+        );
+        assert_eq!(
+            error("SCAN_NUMBER ~ 1"),
+            "Unexpected character '~' at position 13"
+        );
+        assert_eq!(
+            error("SCAN_NUMBER 1"),
+            "Expected a comparison after SCAN_NUMBER but found '1'"
+        );
+    }
+}
@@ -0,0 +1,696 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Write};
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use rubbl_casatables::{CasaScalarData, GlueDataType, Table};
+use rubbl_core::ndarray::{Array1, ArrayD, ArrayViewD, Axis, IxDyn};
+use rubbl_core::Complex;
+
+use crate::foreign_keys::format_foreign_key;
+use crate::measures::ColumnUnit;
+use crate::npy::NpyElement;
+
+/// Target size in bytes of a block of rows that is read in one go.
+const BLOCK_BYTES: usize = 4 * 1024 * 1024;
+/// Bounds on the number of rows in a block.
+const MIN_BLOCK_ROWS: u64 = 16;
+const MAX_BLOCK_ROWS: u64 = 4096;
+/// Number of rows in a block of a variable shape column, whose cell size is unknown.
+const VARIABLE_BLOCK_ROWS: u64 = 64;
+
+/// How the values of a column are displayed.
+pub struct DisplayOptions<'a> {
+    pub unit: ColumnUnit,
+    /// Foreign key labels shown next to integer values.
+    pub labels: Option<&'a Vec<String>>,
+}
+
+/// Memory that a job over the rows of a table, such as statistics or an export, uses to cache
+/// blocks of rows.
+pub const JOB_CACHE_BYTES: usize = 64 * 1024 * 1024;
+/// Rows a job handles per step, between progress updates.
+pub const JOB_ROWS_PER_STEP: u64 = 1000;
+
+/// Values of a cell converted to double precision, e.g. for statistics.
+pub enum CellValues {
+    Real(Vec<f64>),
+    Complex(Vec<Complex<f64>>),
+}
+
+impl CellValues {
+    /// The values as complex numbers; real values get an imaginary part of zero.
+    pub fn into_complex(self) -> Vec<Complex<f64>> {
+        match self {
+            CellValues::Real(values) => values.into_iter().map(|v| Complex::new(v, 0.0)).collect(),
+            CellValues::Complex(values) => values,
+        }
+    }
+}
+
+/// Elements of a cell in row-major order with the shape of the cell, e.g. for exporting.
+///
+/// Scalars have an empty shape; cells that could not be read have no elements.
+pub struct CellElements {
+    pub shape: Vec<usize>,
+    pub values: ElementValues,
+}
+
+impl CellElements {
+    pub fn is_empty(&self) -> bool {
+        match &self.values {
+            ElementValues::Text(values) => values.is_empty(),
+            ElementValues::Complex { values, .. } => values.is_empty(),
+        }
+    }
+}
+
+/// Real values are kept as text, so they are written with the precision of the column.
+pub enum ElementValues {
+    Text(Vec<String>),
+    Complex {
+        values: Vec<Complex<f64>>,
+        single_precision: bool,
+    },
+}
+
+/// Cells of a range of consecutive rows of one column.
+pub enum Cells<T> {
+    Scalar(Vec<T>),
+    /// Cells of a fixed shape column in one array, with the rows along the first axis.
+    Fixed(ArrayD<T>),
+    /// Cells of a variable shape column; `None` for cells that could not be read, e.g. undefined
+    /// cells.
+    Array(Vec<Option<ArrayD<T>>>),
+}
+
+impl<T: Clone> Cells<T> {
+    fn n_elements(&self) -> usize {
+        match self {
+            Cells::Scalar(values) => values.len(),
+            Cells::Fixed(cells) => cells.len(),
+            Cells::Array(cells) => cells.iter().flatten().map(|c| c.len()).sum(),
+        }
+    }
+
+    /// One cell of an array column, or None for scalars and cells that could not be read.
+    pub fn view(&self, index: usize) -> Option<ArrayViewD<'_, T>> {
+        match self {
+            Cells::Scalar(_) => None,
+            Cells::Fixed(cells) => Some(cells.index_axis(Axis(0), index)),
+            Cells::Array(cells) => cells[index].as_ref().map(|c| c.view()),
+        }
+    }
+
+    /// Shape of one cell, or None if it could not be read.
+    fn shape(&self, index: usize) -> Option<Vec<usize>> {
+        match self {
+            Cells::Scalar(_) => Some(Vec::new()),
+            _ => self.view(index).map(|c| c.shape().to_vec()),
+        }
+    }
+
+    /// Elements of one cell in row-major order.
+    fn elements(&self, index: usize) -> Vec<T> {
+        match self {
+            Cells::Scalar(values) => vec![values[index].clone()],
+            _ => self
+                .view(index)
+                .map_or(Vec::new(), |c| c.iter().cloned().collect()),
+        }
+    }
+}
+
+impl<T: Clone + Display> Cells<T> {
+    fn format(&self, index: usize, buf: &mut String) {
+        match self {
+            Cells::Scalar(values) => {
+                let _ = write!(buf, "{}", values[index]);
+            }
+            _ => match self.view(index) {
+                Some(cell) => {
+                    let _ = write!(buf, "{}", cell);
+                }
+                None => buf.push_str("Failed to parse field."),
+            },
+        }
+    }
+}
+
+/// Cells of a block of rows, in the data type of the column.
+pub enum Block {
+    Bool(Cells<bool>),
+    Char(Cells<i8>),
+    UChar(Cells<u8>),
+    Short(Cells<i16>),
+    UShort(Cells<u16>),
+    Int(Cells<i32>),
+    UInt(Cells<u32>),
+    Int64(Cells<i64>),
+    Float(Cells<f32>),
+    Double(Cells<f64>),
+    Complex(Cells<Complex<f32>>),
+    DComplex(Cells<Complex<f64>>),
+    String(Cells<String>),
+    Unsupported(GlueDataType),
+}
+
+/// Apply an expression to the cells of a block regardless of their type.
+macro_rules! with_cells {
+    ($block:expr, $cells:ident => $body:expr, $unsupported:expr) => {
+        match $block {
+            Block::Bool($cells) => $body,
+            Block::Char($cells) => $body,
+            Block::UChar($cells) => $body,
+            Block::Short($cells) => $body,
+            Block::UShort($cells) => $body,
+            Block::Int($cells) => $body,
+            Block::UInt($cells) => $body,
+            Block::Int64($cells) => $body,
+            Block::Float($cells) => $body,
+            Block::Double($cells) => $body,
+            Block::Complex($cells) => $body,
+            Block::DComplex($cells) => $body,
+            Block::String($cells) => $body,
+            Block::Unsupported(_) => $unsupported,
+        }
+    };
+}
+
+impl Block {
+    /// Approximate memory used by the block.
+    fn size_bytes(&self) -> usize {
+        with_cells!(self, cells => cells.n_elements() * element_size(cells), 0)
+    }
+
+    /// Format the cell at the given index of the block, as shown in the Column Values panel.
+    fn format_cell(&self, index: usize, display: &DisplayOptions, buf: &mut String) {
+        match self {
+            Block::Int(Cells::Scalar(values)) => {
+                buf.push_str(&format_foreign_key(values[index], display.labels));
+            }
+            Block::Double(Cells::Scalar(values)) => {
+                buf.push_str(&display.unit.format(values[index]));
+            }
+            Block::Double(cells @ (Cells::Fixed(_) | Cells::Array(_)))
+                if matches!(display.unit, ColumnUnit::Epoch(..)) =>
+            {
+                match cells.view(index) {
+                    Some(cell) if cell.ndim() == 1 => {
+                        let values: Vec<String> =
+                            cell.iter().map(|v| display.unit.format(*v)).collect();
+                        let _ = write!(buf, "[{}]", values.join(", "));
+                    }
+                    Some(cell) => {
+                        let _ = write!(buf, "{}", cell);
+                    }
+                    None => buf.push_str("Failed to parse field."),
+                }
+            }
+            Block::String(cells @ (Cells::Fixed(_) | Cells::Array(_))) => match cells.view(index) {
+                Some(cell) => {
+                    let values: Vec<&str> = cell.iter().map(|s| s.as_str()).collect();
+                    let _ = write!(buf, "[{}]", values.join(", "));
+                }
+                None => buf.push_str("Failed to parse field."),
+            },
+            Block::Unsupported(data_type) => {
+                let _ = write!(buf, "Not implemented for {}", data_type);
+            }
+            _ => with_cells!(self, cells => cells.format(index, buf), ()),
+        }
+    }
+
+    /// Values of the cell at the given index as double precision, or None for strings.
+    fn values(&self, index: usize) -> Option<CellValues> {
+        match self {
+            Block::Bool(cells) => real_values(cells, index, |v| *v as u8 as f64),
+            Block::Char(cells) => real_values(cells, index, |v| *v as f64),
+            Block::UChar(cells) => real_values(cells, index, |v| *v as f64),
+            Block::Short(cells) => real_values(cells, index, |v| *v as f64),
+            Block::UShort(cells) => real_values(cells, index, |v| *v as f64),
+            Block::Int(cells) => real_values(cells, index, |v| *v as f64),
+            Block::UInt(cells) => real_values(cells, index, |v| *v as f64),
+            Block::Int64(cells) => real_values(cells, index, |v| *v as f64),
+            Block::Float(cells) => real_values(cells, index, |v| *v as f64),
+            Block::Double(cells) => real_values(cells, index, |v| *v),
+            Block::Complex(cells) => Some(CellValues::Complex(
+                cells
+                    .elements(index)
+                    .iter()
+                    .map(|v| Complex::new(v.re as f64, v.im as f64))
+                    .collect(),
+            )),
+            Block::DComplex(cells) => Some(CellValues::Complex(cells.elements(index))),
+            Block::String(_) | Block::Unsupported(_) => None,
+        }
+    }
+
+    /// Elements of the cell at the given index with its shape, or None for unsupported types.
+    ///
+    /// Epochs are decoded with `unit`, e.g. TIME as UTC timestamps.
+    fn elements(&self, index: usize, unit: &ColumnUnit) -> Option<CellElements> {
+        let values = match self {
+            Block::Double(cells) if matches!(unit, ColumnUnit::Epoch(..)) => ElementValues::Text(
+                cells
+                    .elements(index)
+                    .iter()
+                    .map(|v| unit.format(*v))
+                    .collect(),
+            ),
+            Block::Complex(cells) => ElementValues::Complex {
+                values: cells
+                    .elements(index)
+                    .iter()
+                    .map(|v| Complex::new(v.re as f64, v.im as f64))
+                    .collect(),
+                single_precision: true,
+            },
+            Block::DComplex(cells) => ElementValues::Complex {
+                values: cells.elements(index),
+                single_precision: false,
+            },
+            _ => with_cells!(
+                self,
+                cells => ElementValues::Text(
+                    cells.elements(index).iter().map(|v| v.to_string()).collect()
+                ),
+                return None
+            ),
+        };
+        let shape = with_cells!(self, cells => cells.shape(index), None).unwrap_or_default();
+        Some(CellElements { shape, values })
+    }
+
+    /// Append the elements of the cell at the given index to `buf` as in a NumPy array, and return
+    /// their data type and the shape of the cell; None for strings and cells that could not be read.
+    fn npy_cell(&self, index: usize, buf: &mut Vec<u8>) -> Option<(&'static str, Vec<usize>)> {
+        match self {
+            Block::Bool(cells) => npy_cell(cells, index, buf),
+            Block::Char(cells) => npy_cell(cells, index, buf),
+            Block::UChar(cells) => npy_cell(cells, index, buf),
+            Block::Short(cells) => npy_cell(cells, index, buf),
+            Block::UShort(cells) => npy_cell(cells, index, buf),
+            Block::Int(cells) => npy_cell(cells, index, buf),
+            Block::UInt(cells) => npy_cell(cells, index, buf),
+            Block::Int64(cells) => npy_cell(cells, index, buf),
+            Block::Float(cells) => npy_cell(cells, index, buf),
+            Block::Double(cells) => npy_cell(cells, index, buf),
+            Block::Complex(cells) => npy_cell(cells, index, buf),
+            Block::DComplex(cells) => npy_cell(cells, index, buf),
+            Block::String(_) | Block::Unsupported(_) => None,
+        }
+    }
+}
+
+/// Append the elements of a cell to `buf` as in a NumPy array and return their type and shape.
+fn npy_cell<T: NpyElement + Clone>(
+    cells: &Cells<T>,
+    index: usize,
+    buf: &mut Vec<u8>,
+) -> Option<(&'static str, Vec<usize>)> {
+    let shape = cells.shape(index)?;
+    for value in cells.elements(index).iter() {
+        value.write_le(buf);
+    }
+    Some((T::DESCR, shape))
+}
+
+fn real_values<T: Clone>(
+    cells: &Cells<T>,
+    index: usize,
+    convert: impl Fn(&T) -> f64,
+) -> Option<CellValues> {
+    Some(CellValues::Real(
+        cells.elements(index).iter().map(convert).collect(),
+    ))
+}
+
+fn element_size<T>(_cells: &Cells<T>) -> usize {
+    std::mem::size_of::<T>()
+}
+
+/// Read the cells of a range of rows of a column.
+///
+/// Scalar columns are read with a single column read. The bindings have no read of a range of
+/// rows or of a slice of an array column, so array cells are read one row at a time; the cells
+/// of a fixed shape column are then copied into one array, which keeps the cached block compact.
+fn read_cells<T: CasaScalarData + Clone>(
+    table: &mut Table,
+    column_name: &str,
+    info: &ColumnInfo,
+    rows: Range<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<Cells<T>> {
+    let n_rows = (rows.end - rows.start) as usize;
+    if info.scalar {
+        let mut values = table.get_col_as_vec::<T>(column_name)?;
+        values.truncate(rows.end as usize);
+        values.drain(..rows.start as usize);
+        return Ok(Cells::Scalar(values));
+    }
+    if info.fixed_shape {
+        let first = table.get_cell::<ArrayD<T>>(column_name, rows.start)?;
+        let mut shape = vec![n_rows];
+        shape.extend_from_slice(first.shape());
+        let mut values = Vec::with_capacity(n_rows * first.len());
+        values.extend(first.iter().cloned());
+        for row in rows.start + 1..rows.end {
+            if cancelled() {
+                bail!("Cancelled");
+            }
+            let cell = table.get_cell_as_vec::<T>(column_name, row)?;
+            if cell.len() != first.len() {
+                bail!(
+                    "Cell {} of fixed shape column {} has {} elements instead of {}",
+                    row,
+                    column_name,
+                    cell.len(),
+                    first.len()
+                );
+            }
+            values.extend(cell);
+        }
+        return Ok(Cells::Fixed(ArrayD::from_shape_vec(IxDyn(&shape), values)?));
+    }
+    let mut cells = Vec::with_capacity(n_rows);
+    for row in rows {
+        if cancelled() {
+            bail!("Cancelled");
+        }
+        cells.push(table.get_cell::<ArrayD<T>>(column_name, row).ok());
+    }
+    Ok(Cells::Array(cells))
+}
+
+/// Read the cells of a range of rows of a string column.
+///
+/// String arrays can only be read as a list per cell, so they are kept as separate cells.
+fn read_string_cells(
+    table: &mut Table,
+    column_name: &str,
+    info: &ColumnInfo,
+    rows: Range<u64>,
+) -> Result<Cells<String>> {
+    if info.scalar {
+        return read_cells(table, column_name, info, rows, &|| false);
+    }
+    let mut cells = Vec::with_capacity((rows.end - rows.start) as usize);
+    for row in rows {
+        let cell = table.get_cell::<Vec<String>>(column_name, row).ok();
+        cells.push(cell.map(|c| Array1::from(c).into_dyn()));
+    }
+    Ok(Cells::Array(cells))
+}
+
+fn read_block(
+    table: &mut Table,
+    column_name: &str,
+    info: &ColumnInfo,
+    rows: Range<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> Result<Block> {
+    let block = match info.data_type {
+        GlueDataType::TpBool | GlueDataType::TpArrayBool => {
+            Block::Bool(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpChar | GlueDataType::TpArrayChar => {
+            Block::Char(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpUChar | GlueDataType::TpArrayUChar => {
+            Block::UChar(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpShort | GlueDataType::TpArrayShort => {
+            Block::Short(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpUShort | GlueDataType::TpArrayUShort => {
+            Block::UShort(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpInt | GlueDataType::TpArrayInt => {
+            Block::Int(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpUInt | GlueDataType::TpArrayUInt => {
+            Block::UInt(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpInt64 | GlueDataType::TpArrayInt64 => {
+            Block::Int64(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpFloat | GlueDataType::TpArrayFloat => {
+            Block::Float(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpDouble | GlueDataType::TpArrayDouble => {
+            Block::Double(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpComplex | GlueDataType::TpArrayComplex => {
+            Block::Complex(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpDComplex | GlueDataType::TpArrayDComplex => {
+            Block::DComplex(read_cells(table, column_name, info, rows, cancelled)?)
+        }
+        GlueDataType::TpString | GlueDataType::TpArrayString => {
+            Block::String(read_string_cells(table, column_name, info, rows)?)
+        }
+        other => Block::Unsupported(other),
+    };
+    Ok(block)
+}
+
+/// Size in bytes of one element of a column of the given type.
+fn type_size(data_type: GlueDataType) -> usize {
+    match data_type {
+        GlueDataType::TpBool
+        | GlueDataType::TpArrayBool
+        | GlueDataType::TpChar
+        | GlueDataType::TpArrayChar
+        | GlueDataType::TpUChar
+        | GlueDataType::TpArrayUChar => 1,
+        GlueDataType::TpShort
+        | GlueDataType::TpArrayShort
+        | GlueDataType::TpUShort
+        | GlueDataType::TpArrayUShort => 2,
+        GlueDataType::TpInt
+        | GlueDataType::TpArrayInt
+        | GlueDataType::TpUInt
+        | GlueDataType::TpArrayUInt
+        | GlueDataType::TpFloat
+        | GlueDataType::TpArrayFloat => 4,
+        GlueDataType::TpComplex | GlueDataType::TpArrayComplex => 8,
+        GlueDataType::TpDComplex | GlueDataType::TpArrayDComplex => 16,
+        _ => 8,
+    }
+}
+
+/// Description of a column, cached so it is only fetched once.
+#[derive(Clone, Copy)]
+struct ColumnInfo {
+    data_type: GlueDataType,
+    scalar: bool,
+    fixed_shape: bool,
+    /// Number of rows per block; a block of a scalar column covers the whole table.
+    block_rows: u64,
+}
+
+impl ColumnInfo {
+    fn new(table: &mut Table, column_name: &str, sparse: bool) -> Result<Self> {
+        let col_desc = table.get_col_desc(column_name)?;
+        let data_type = col_desc.data_type();
+        let scalar = col_desc.is_scalar();
+        let fixed_shape = scalar || col_desc.is_fixed_shape();
+        let block_rows = if scalar {
+            // Reading a whole scalar column is a single, fast call.
+            table.n_rows().max(1)
+        } else if sparse {
+            1
+        } else if fixed_shape {
+            let n_elements: u64 = col_desc.shape().map_or(1, |s| s.iter().product());
+            let cell_bytes = n_elements.max(1) as usize * type_size(data_type);
+            ((BLOCK_BYTES / cell_bytes) as u64).clamp(MIN_BLOCK_ROWS, MAX_BLOCK_ROWS)
+        } else {
+            VARIABLE_BLOCK_ROWS
+        };
+        Ok(ColumnInfo {
+            data_type,
+            scalar,
+            fixed_shape,
+            block_rows,
+        })
+    }
+}
+
+/// Reads a table column by column in blocks of rows, keeping recently used blocks in an LRU cache.
+///
+/// A reader belongs to a single table; use a new reader when switching tables.
+pub struct ColumnReader {
+    columns: HashMap<String, ColumnInfo>,
+    /// Cached blocks keyed by column and first row, most recently used first.
+    blocks: VecDeque<((String, u64), Block)>,
+    capacity_bytes: usize,
+    /// Whether array cells are read one row at a time, see `ColumnReader::sparse`.
+    sparse: bool,
+}
+
+impl ColumnReader {
+    pub fn new(capacity_bytes: usize) -> Self {
+        ColumnReader {
+            columns: HashMap::new(),
+            blocks: VecDeque::new(),
+            capacity_bytes,
+            sparse: false,
+        }
+    }
+
+    /// A reader for rows spread over the table, e.g. the rows of one baseline.
+    ///
+    /// Array cells are read one row at a time, instead of in blocks that would mostly hold other
+    /// rows; scalar columns are still read as a whole.
+    pub fn sparse(capacity_bytes: usize) -> Self {
+        ColumnReader {
+            sparse: true,
+            ..ColumnReader::new(capacity_bytes)
+        }
+    }
+
+    fn info(&mut self, table: &mut Table, column_name: &str) -> Result<ColumnInfo> {
+        if let Some(info) = self.columns.get(column_name) {
+            return Ok(*info);
+        }
+        let info = ColumnInfo::new(table, column_name, self.sparse)?;
+        self.columns.insert(column_name.to_string(), info);
+        Ok(info)
+    }
+
+    /// Block holding the given row and the index of the row within it.
+    fn block(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+        cancelled: &dyn Fn() -> bool,
+    ) -> Result<(&Block, usize)> {
+        let info = self.info(table, column_name)?;
+        let start = row - row % info.block_rows;
+        let position = self
+            .blocks
+            .iter()
+            .position(|((c, s), _)| *s == start && c == column_name);
+        match position {
+            Some(i) => {
+                let entry = self.blocks.remove(i).expect("position is in range");
+                self.blocks.push_front(entry);
+            }
+            None => {
+                let end = (start + info.block_rows).min(table.n_rows());
+                let block = read_block(table, column_name, &info, start..end, cancelled)?;
+                self.blocks
+                    .push_front(((column_name.to_string(), start), block));
+                self.evict();
+            }
+        }
+        Ok((&self.blocks[0].1, (row - start) as usize))
+    }
+
+    /// Drop the least recently used blocks until the cache fits.
+    ///
+    /// The newest block of every column is kept, so that reading several columns row by row,
+    /// e.g. when exporting, does not evict the blocks of the other columns each time.
+    fn evict(&mut self) {
+        let mut used: usize = self.blocks.iter().map(|(_, b)| b.size_bytes()).sum();
+        let mut i = self.blocks.len();
+        while used > self.capacity_bytes && i > 1 {
+            i -= 1;
+            let column = &self.blocks[i].0 .0;
+            let newest = !self.blocks.range(..i).any(|((c, _), _)| c == column);
+            if !newest {
+                if let Some((_, block)) = self.blocks.remove(i) {
+                    used -= block.size_bytes();
+                }
+            }
+        }
+    }
+
+    /// Format the given rows of a column, one row per line, as shown in the Column Values panel.
+    ///
+    /// Reading stops early when `cancelled` returns true.
+    pub fn format_rows(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        rows: &[u64],
+        display: &DisplayOptions,
+        cancelled: &dyn Fn() -> bool,
+    ) -> Result<String> {
+        let mut buf = String::new();
+        buf.push_str(&format!(":{:^5}: ", "ROW"));
+        buf.push_str("VALUE");
+        buf.push('\n');
+        let n_rows = table.n_rows();
+        for &row in rows.iter().filter(|r| **r < n_rows) {
+            if cancelled() {
+                bail!("Cancelled");
+            }
+            let (block, index) = self.block(table, column_name, row, cancelled)?;
+            buf.push_str(&format!(":{:>5}: ", row));
+            block.format_cell(index, display, &mut buf);
+            buf.push('\n');
+        }
+        Ok(buf)
+    }
+
+    /// Values of a cell as double precision, or None if the column is not numeric.
+    pub fn cell_values(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+    ) -> Result<Option<CellValues>> {
+        let (block, index) = self.block(table, column_name, row, &|| false)?;
+        Ok(block.values(index))
+    }
+
+    /// Elements of a cell with its shape, or None if the column type is not supported.
+    pub fn cell_elements(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+        unit: &ColumnUnit,
+    ) -> Result<Option<CellElements>> {
+        let (block, index) = self.block(table, column_name, row, &|| false)?;
+        Ok(block.elements(index, unit))
+    }
+
+    /// Append a cell to `buf` as in a NumPy array and return its data type and shape.
+    ///
+    /// Returns None for string columns, unsupported types and cells that could not be read.
+    pub fn npy_cell(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+        buf: &mut Vec<u8>,
+    ) -> Result<Option<(&'static str, Vec<usize>)>> {
+        let (block, index) = self.block(table, column_name, row, &|| false)?;
+        Ok(block.npy_cell(index, buf))
+    }
+
+    /// Block holding a cell in the data type of the column, and the index of the cell within it.
+    pub fn cell(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+    ) -> Result<(&Block, usize)> {
+        self.block(table, column_name, row, &|| false)
+    }
+
+    /// Shape of a cell, empty for scalars, or None if it could not be read.
+    pub fn cell_shape(
+        &mut self,
+        table: &mut Table,
+        column_name: &str,
+        row: u64,
+    ) -> Result<Option<Vec<usize>>> {
+        let (block, index) = self.block(table, column_name, row, &|| false)?;
+        Ok(with_cells!(block, cells => cells.shape(index), None))
+    }
+}
@@ -0,0 +1,355 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use rubbl_casatables::Table;
+
+use crate::plot::is_complex;
+use crate::query::RowFilter;
+use crate::reader::{CellValues, ColumnReader, JOB_CACHE_BYTES, JOB_ROWS_PER_STEP};
+
+/// Maximum number of values kept to determine the median; beyond this it is estimated.
+const MEDIAN_SAMPLE_SIZE: usize = 1_000_000;
+
+/// Running statistics of a stream of values.
+struct Accumulator {
+    count: u64,
+    n_nan: u64,
+    n_inf: u64,
+    n_zero: u64,
+    min: f64,
+    max: f64,
+    /// Running mean and sum of squared deviations from it (Welford's method), which stay
+    /// accurate for values with a large offset such as TIME.
+    mean: f64,
+    m2: f64,
+    /// Reservoir sample of the finite values, used for the median.
+    sample: Vec<f64>,
+    n_finite: u64,
+    rng_state: u64,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Accumulator {
+            count: 0,
+            n_nan: 0,
+            n_inf: 0,
+            n_zero: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            sample: Vec::new(),
+            n_finite: 0,
+            rng_state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64, good enough to pick a reservoir sample.
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        self.rng_state
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        if value.is_nan() {
+            self.n_nan += 1;
+            return;
+        }
+        if value.is_infinite() {
+            self.n_inf += 1;
+            return;
+        }
+        if value == 0.0 {
+            self.n_zero += 1;
+        }
+        self.n_finite += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.n_finite as f64;
+        self.m2 += delta * (value - self.mean);
+        if self.sample.len() < MEDIAN_SAMPLE_SIZE {
+            self.sample.push(value);
+        } else {
+            let i = self.next_random() % self.n_finite;
+            if (i as usize) < MEDIAN_SAMPLE_SIZE {
+                self.sample[i as usize] = value;
+            }
+        }
+    }
+
+    fn finish(mut self) -> SummaryStats {
+        let n = self.n_finite as f64;
+        let (mean, rms, std) = if self.n_finite > 0 {
+            let variance = (self.m2 / n).max(0.0);
+            let rms = (self.mean * self.mean + variance).sqrt();
+            (self.mean, rms, variance.sqrt())
+        } else {
+            (f64::NAN, f64::NAN, f64::NAN)
+        };
+        let median = if self.sample.is_empty() {
+            f64::NAN
+        } else {
+            let mid = self.sample.len() / 2;
+            let (_, median, _) = self
+                .sample
+                .select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+            *median
+        };
+        SummaryStats {
+            count: self.count,
+            n_nan: self.n_nan,
+            n_inf: self.n_inf,
+            n_zero: self.n_zero,
+            min: if self.n_finite > 0 {
+                self.min
+            } else {
+                f64::NAN
+            },
+            max: if self.n_finite > 0 {
+                self.max
+            } else {
+                f64::NAN
+            },
+            mean,
+            median,
+            median_estimated: self.n_finite as usize > MEDIAN_SAMPLE_SIZE,
+            rms,
+            std,
+        }
+    }
+}
+
+/// Statistics of one quantity of a column, e.g. the amplitude of DATA.
+pub struct SummaryStats {
+    pub count: u64,
+    pub n_nan: u64,
+    pub n_inf: u64,
+    pub n_zero: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub median_estimated: bool,
+    pub rms: f64,
+    pub std: f64,
+}
+
+/// Formats one statistic for the text report.
+type StatFormatter = fn(&SummaryStats) -> String;
+
+/// Statistics of a numeric or complex column over a range of rows.
+///
+/// With a filter, the range refers to the matching rows rather than to all rows.
+pub struct ColumnStats {
+    pub column: String,
+    pub start_row: u64,
+    pub end_row: u64,
+    pub filter: Option<(String, u64)>,
+    pub sections: Vec<(&'static str, SummaryStats)>,
+}
+
+impl ColumnStats {
+    pub fn to_text(&self) -> String {
+        let mut buf = String::new();
+        match &self.filter {
+            Some((query, n_matching)) => buf.push_str(&format!(
+                "Column: {}   Matching rows: {} - {} of {}   Filter: {}\n\n",
+                self.column,
+                self.start_row,
+                self.end_row.saturating_sub(1),
+                n_matching,
+                query
+            )),
+            None => buf.push_str(&format!(
+                "Column: {}   Rows: {} - {}\n\n",
+                self.column,
+                self.start_row,
+                self.end_row.saturating_sub(1)
+            )),
+        }
+        buf.push_str(&format!("{:<12}", ""));
+        for (name, _) in self.sections.iter() {
+            buf.push_str(&format!("{:>16}", name));
+        }
+        buf.push('\n');
+        let rows: [(&str, StatFormatter); 11] = [
+            ("count", |s| s.count.to_string()),
+            ("min", |s| format!("{:.6e}", s.min)),
+            ("max", |s| format!("{:.6e}", s.max)),
+            ("mean", |s| format!("{:.6e}", s.mean)),
+            ("median", |s| {
+                if s.median_estimated {
+                    format!("~{:.6e}", s.median)
+                } else {
+                    format!("{:.6e}", s.median)
+                }
+            }),
+            ("rms", |s| format!("{:.6e}", s.rms)),
+            ("std", |s| format!("{:.6e}", s.std)),
+            ("NaN", |s| s.n_nan.to_string()),
+            ("Inf", |s| s.n_inf.to_string()),
+            ("zero", |s| s.n_zero.to_string()),
+            ("finite", |s| (s.count - s.n_nan - s.n_inf).to_string()),
+        ];
+        for (label, value) in rows.iter() {
+            buf.push_str(&format!("{:<12}", label));
+            for (_, stats) in self.sections.iter() {
+                buf.push_str(&format!("{:>16}", value(stats)));
+            }
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+/// Computation of column statistics in steps, so progress can be shown in between.
+pub struct StatsJob {
+    pub column: String,
+    pub start_row: u64,
+    pub end_row: u64,
+    pub next_row: u64,
+    /// Rows matching the active filter; the row numbers above then index into these.
+    rows: Option<Arc<Vec<u64>>>,
+    query: Option<String>,
+    is_complex: bool,
+    reader: ColumnReader,
+    accumulators: Vec<Accumulator>,
+}
+
+impl StatsJob {
+    pub fn new(
+        table: &mut Table,
+        column_name: &str,
+        start_row: u64,
+        end_row: u64,
+        filter: Option<&RowFilter>,
+    ) -> Result<Self> {
+        let data_type = table.get_col_desc(column_name)?.data_type();
+        let is_complex = is_complex(data_type);
+        let rows = filter.map(|f| Arc::clone(&f.rows));
+        let n_rows = rows.as_ref().map_or(table.n_rows(), |r| r.len() as u64);
+        let end_row = end_row.min(n_rows);
+        let mut reader = ColumnReader::new(JOB_CACHE_BYTES);
+        let first_row = rows.as_ref().map_or(start_row, |r| {
+            r.get(start_row as usize).copied().unwrap_or(0)
+        });
+        if start_row < end_row && reader.cell_values(table, column_name, first_row)?.is_none() {
+            bail!(
+                "Statistics are not available for columns of type {}",
+                data_type
+            );
+        }
+        let n_accumulators = if is_complex { 4 } else { 1 };
+        Ok(StatsJob {
+            column: column_name.to_string(),
+            start_row,
+            end_row,
+            next_row: start_row,
+            rows,
+            query: filter.map(|f| f.query.clone()),
+            is_complex,
+            reader,
+            accumulators: (0..n_accumulators).map(|_| Accumulator::new()).collect(),
+        })
+    }
+
+    /// Fraction of the rows processed so far.
+    pub fn progress(&self) -> f64 {
+        if self.end_row <= self.start_row {
+            1.0
+        } else {
+            (self.next_row - self.start_row) as f64 / (self.end_row - self.start_row) as f64
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_row >= self.end_row
+    }
+
+    /// Process the next batch of rows.
+    pub fn step(&mut self, table: &mut Table) -> Result<()> {
+        let end = (self.next_row + JOB_ROWS_PER_STEP).min(self.end_row);
+        for i in self.next_row..end {
+            let row = self.rows.as_ref().map_or(i, |r| r[i as usize]);
+            match self.reader.cell_values(table, &self.column, row)? {
+                Some(CellValues::Real(values)) => {
+                    for v in values {
+                        self.accumulators[0].add(v);
+                    }
+                }
+                Some(CellValues::Complex(values)) => {
+                    for v in values {
+                        self.accumulators[0].add(v.re);
+                        self.accumulators[1].add(v.im);
+                        self.accumulators[2].add(v.norm());
+                        self.accumulators[3].add(v.arg().to_degrees());
+                    }
+                }
+                None => {}
+            }
+        }
+        self.next_row = end;
+        Ok(())
+    }
+
+    pub fn finish(self) -> ColumnStats {
+        let names: &[&'static str] = if self.is_complex {
+            &["real", "imag", "amplitude", "phase (deg)"]
+        } else {
+            &["value"]
+        };
+        ColumnStats {
+            column: self.column,
+            start_row: self.start_row,
+            end_row: self.end_row,
+            filter: self
+                .query
+                .zip(self.rows)
+                .map(|(query, rows)| (query, rows.len() as u64)),
+            sections: names
+                .iter()
+                .copied()
+                .zip(self.accumulators.into_iter().map(|a| a.finish()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std_of_values_with_a_large_offset() {
+        // Times in MJD seconds, 1 s apart: the spread is tiny compared to the values.
+        let mut acc = Accumulator::new();
+        for i in 0..1000 {
+            acc.add(5.0e9 + i as f64);
+        }
+        let stats = acc.finish();
+        let expected = ((1000.0f64 * 1000.0 - 1.0) / 12.0).sqrt();
+        assert!((stats.std - expected).abs() < 1e-6, "std {}", stats.std);
+        assert_eq!(stats.mean, 5.0e9 + 499.5);
+    }
+
+    #[test]
+    fn non_finite_values_are_counted_separately() {
+        let mut acc = Accumulator::new();
+        for value in [1.0, f64::NAN, f64::INFINITY, 0.0, 3.0] {
+            acc.add(value);
+        }
+        let stats = acc.finish();
+        assert_eq!(
+            (stats.count, stats.n_nan, stats.n_inf, stats.n_zero),
+            (5, 1, 1, 1)
+        );
+        assert_eq!((stats.min, stats.max), (0.0, 3.0));
+        assert!((stats.mean - 4.0 / 3.0).abs() < 1e-12);
+        assert!((stats.rms - (10.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    }
+}
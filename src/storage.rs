@@ -0,0 +1,774 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use rubbl_casatables::{GlueDataType, Table};
+
+use crate::dysco::DyscoParameters;
+
+/// Longest string accepted while scanning, to reject random bytes that look like a length.
+const MAX_NAME_LENGTH: usize = 256;
+
+/// Value that starts a file written with AipsIO.
+const AIPSIO_MAGIC: u32 = 0xbebebebe;
+
+/// Reader for the AipsIO encoding casacore uses for table.dat and the data manager files.
+///
+/// The bindings do not expose the data managers of a table. The column set in table.dat, which
+/// binds the columns to their data managers, is parsed; the other parts of the layout that are
+/// needed are picked out of these files by matching the encoding of known names.
+struct AipsIo<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> AipsIo<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        AipsIo {
+            data,
+            pos: 0,
+            big_endian,
+        }
+    }
+
+    /// Open a file, detecting its byte order from the type name of its first object.
+    fn detect(data: &'a [u8]) -> Self {
+        let big_endian = data.get(8..12).is_none_or(|b| {
+            let len = u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize;
+            len > 0 && len <= MAX_NAME_LENGTH
+        });
+        AipsIo::new(data, big_endian)
+    }
+
+    fn at(&self, pos: usize) -> Self {
+        AipsIo {
+            data: self.data,
+            pos,
+            big_endian: self.big_endian,
+        }
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(self.pos..self.pos + 4)?.try_into().ok()?;
+        self.pos += 4;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.u32().map(|v| v as i32)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.i64().map(|v| v as u64)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        let byte = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(byte != 0)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        let bytes: [u8; 8] = self.data.get(self.pos..self.pos + 8)?.try_into().ok()?;
+        self.pos += 8;
+        Some(if self.big_endian {
+            i64::from_be_bytes(bytes)
+        } else {
+            i64::from_le_bytes(bytes)
+        })
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        if len > MAX_NAME_LENGTH {
+            return None;
+        }
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    /// Skip an object, which starts with its length; returns its type name.
+    fn skip_object(&mut self) -> Option<String> {
+        let start = self.pos;
+        let len = self.u32()? as usize;
+        let type_name = self.string()?;
+        if len < 4 || start + len > self.data.len() {
+            return None;
+        }
+        self.pos = start + len;
+        Some(type_name)
+    }
+
+    /// A string that could be the name of a data manager or group.
+    fn name(&mut self) -> Option<String> {
+        self.string()
+            .filter(|s| s.chars().all(|c| c.is_ascii_graphic()))
+    }
+
+    fn encode_string(&self, s: &str) -> Vec<u8> {
+        let len = s.len() as u32;
+        let mut encoded = if self.big_endian {
+            len.to_be_bytes().to_vec()
+        } else {
+            len.to_le_bytes().to_vec()
+        };
+        encoded.extend_from_slice(s.as_bytes());
+        encoded
+    }
+
+    /// Positions just after each occurrence of the encoded string `s`.
+    fn find_string(&self, s: &str) -> Vec<usize> {
+        let needle = self.encode_string(s);
+        self.data
+            .windows(needle.len())
+            .enumerate()
+            .filter(|(_, w)| *w == needle.as_slice())
+            .map(|(i, _)| i + needle.len())
+            .collect()
+    }
+
+    /// Read the contents of an IPosition object, following its type name.
+    fn shape(&mut self) -> Option<Vec<i64>> {
+        let version = self.u32()?;
+        let n = self.u32()? as usize;
+        if n > 16 {
+            return None;
+        }
+        (0..n)
+            .map(|_| {
+                if version == 1 {
+                    self.u32().map(|v| v as i64)
+                } else {
+                    self.i64()
+                }
+            })
+            .collect()
+    }
+}
+
+/// The data managers of a table and the columns bound to them.
+#[derive(Debug, Default, PartialEq)]
+struct ColumnSet {
+    /// Data managers as (type, sequence number), in the order of table.dat.
+    managers: Vec<(String, u32)>,
+    /// Sequence number of the data manager of each column.
+    bindings: HashMap<String, u32>,
+}
+
+/// Parse the column set of table.dat, which follows the table description.
+///
+/// `columns` are the names of the columns in the order of the table description, with whether
+/// they hold arrays, as the column set does not record that itself.
+fn column_set(io: &AipsIo, columns: &[(String, bool)]) -> Result<ColumnSet> {
+    let layout = || anyhow::anyhow!("unexpected layout of table.dat");
+    let mut r = io.at(0);
+    if r.u32() != Some(AIPSIO_MAGIC) {
+        bail!("table.dat is not an AipsIO file");
+    }
+    r.u32().ok_or_else(layout)?;
+    if r.string().as_deref() != Some("Table") {
+        bail!("table.dat does not describe a table");
+    }
+    // Version 3 has 64-bit row numbers.
+    let version = r.u32().ok_or_else(layout)?;
+    let n_rows = if version > 2 {
+        r.u64()
+    } else {
+        r.u32().map(u64::from)
+    }
+    .ok_or_else(layout)?;
+    // The byte order and the type of table, e.g. PlainTable.
+    r.u32().ok_or_else(layout)?;
+    r.string().ok_or_else(layout)?;
+    if r.skip_object().as_deref() != Some("TableDesc") {
+        bail!("table.dat has no table description");
+    }
+
+    // Older column sets start with the number of rows instead of a negative version.
+    let set_version = r.i32().ok_or_else(layout)?;
+    let set_rows = match set_version {
+        -3 => r.u64(),
+        -2 => r.u32().map(u64::from),
+        v if v >= 0 => Some(v as u64),
+        v => bail!("unknown version {} of the column set in table.dat", -v),
+    }
+    .ok_or_else(layout)?;
+    if set_rows != n_rows {
+        bail!(
+            "the column set in table.dat has {} rows instead of {}",
+            set_rows,
+            n_rows
+        );
+    }
+    if set_version < 0 {
+        // The counter that numbers new data managers.
+        r.u32().ok_or_else(layout)?;
+    }
+    let n_managers = r.u32().ok_or_else(layout)?;
+    if n_managers as usize > MAX_NAME_LENGTH {
+        return Err(layout());
+    }
+    let mut managers = Vec::new();
+    for _ in 0..n_managers {
+        let data_manager = r.name().ok_or_else(layout)?;
+        managers.push((data_manager, r.u32().ok_or_else(layout)?));
+    }
+
+    // Each column has a version, its name from version 2 on, and then the version of its data,
+    // the sequence number of its data manager and, for arrays, its fixed shape if it has one.
+    let mut bindings = HashMap::new();
+    for (name, is_array) in columns {
+        let version = r.u32().ok_or_else(layout)?;
+        let name = if version > 1 {
+            r.string().ok_or_else(layout)?
+        } else {
+            name.clone()
+        };
+        r.u32().ok_or_else(layout)?;
+        let seq = r.u32().ok_or_else(layout)?;
+        if !managers.iter().any(|(_, s)| *s == seq) {
+            bail!("column {} is bound to unknown data manager {}", name, seq);
+        }
+        if *is_array && r.bool().ok_or_else(layout)? {
+            r.skip_object().ok_or_else(layout)?;
+        }
+        bindings.insert(name, seq);
+    }
+    Ok(ColumnSet { managers, bindings })
+}
+
+/// Data manager type and group from the description of a column.
+fn column_group(io: &AipsIo, column: &str) -> Option<(String, String)> {
+    // The description holds the name, comment, data manager type and data manager group.
+    io.find_string(column).into_iter().find_map(|pos| {
+        let mut r = io.at(pos);
+        r.string()?;
+        let data_manager = r.name().filter(|s| !s.is_empty())?;
+        let group = r.name()?;
+        Some((data_manager, group))
+    })
+}
+
+/// Tile shapes of the hypercubes of a tiled storage manager, from the contents of its file.
+fn tile_shapes(data: &[u8]) -> Vec<Vec<i64>> {
+    let io = AipsIo::detect(data);
+    let cubes = io.find_string("TSMCube");
+    let positions = io.find_string("IPosition");
+    let mut shapes = Vec::new();
+    for (i, cube) in cubes.iter().enumerate() {
+        // A cube holds the shape of the cube, followed by the shape of its tiles.
+        let next_cube = cubes.get(i + 1).copied().unwrap_or(usize::MAX);
+        let tile_shape = positions
+            .iter()
+            .filter(|p| *p > cube && **p < next_cube)
+            .nth(1)
+            .and_then(|p| io.at(*p).shape());
+        if let Some(tile_shape) = tile_shape {
+            if !shapes.contains(&tile_shape) {
+                shapes.push(tile_shape);
+            }
+        }
+    }
+    shapes
+}
+
+/// Sequence number of the data manager a file belongs to, e.g. 3 for table.f3_TSM0.
+fn file_sequence_number(name: &str) -> Option<u32> {
+    let digits: String = name
+        .strip_prefix("table.f")?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn element_size(data_type: GlueDataType) -> Option<u64> {
+    match data_type {
+        GlueDataType::TpBool
+        | GlueDataType::TpUChar
+        | GlueDataType::TpChar
+        | GlueDataType::TpArrayBool
+        | GlueDataType::TpArrayUChar
+        | GlueDataType::TpArrayChar => Some(1),
+        GlueDataType::TpShort
+        | GlueDataType::TpUShort
+        | GlueDataType::TpArrayShort
+        | GlueDataType::TpArrayUShort => Some(2),
+        GlueDataType::TpInt
+        | GlueDataType::TpUInt
+        | GlueDataType::TpFloat
+        | GlueDataType::TpArrayInt
+        | GlueDataType::TpArrayUInt
+        | GlueDataType::TpArrayFloat => Some(4),
+        GlueDataType::TpDouble
+        | GlueDataType::TpComplex
+        | GlueDataType::TpInt64
+        | GlueDataType::TpArrayDouble
+        | GlueDataType::TpArrayComplex
+        | GlueDataType::TpArrayInt64 => Some(8),
+        GlueDataType::TpDComplex | GlueDataType::TpArrayDComplex => Some(16),
+        _ => None,
+    }
+}
+
+/// How a column is stored on disk.
+pub struct ColumnStorage {
+    pub column: String,
+    pub data_manager: String,
+    pub group: String,
+    pub tile_shape: String,
+    /// Bytes of the files of the data manager, which may be shared with other columns.
+    pub bytes_on_disk: Option<u64>,
+    /// Uncompressed over on-disk size, for Dysco-compressed columns.
+    pub compression_ratio: Option<f64>,
+    /// Description of the compression settings, for Dysco-compressed columns.
+    pub dysco_settings: Option<String>,
+}
+
+/// A file of the table, with the data manager it belongs to.
+pub struct DataFile {
+    pub name: String,
+    pub bytes: u64,
+    pub data_manager: String,
+}
+
+/// The storage managers and on-disk files of a table.
+pub struct StorageLayout {
+    pub columns: Vec<ColumnStorage>,
+    pub files: Vec<DataFile>,
+    pub selected: usize,
+}
+
+impl StorageLayout {
+    pub fn read(table_path: &str, table: &mut Table) -> Result<Self> {
+        let dir = Path::new(table_path);
+        let table_dat = fs::read(dir.join("table.dat"))
+            .with_context(|| format!("failed to read {}/table.dat", table_path))?;
+        let io = AipsIo::detect(&table_dat);
+        let mut descriptions = Vec::new();
+        for column in table.column_names()? {
+            let desc = table.get_col_desc(&column)?;
+            descriptions.push((column, desc));
+        }
+        let array_columns: Vec<(String, bool)> = descriptions
+            .iter()
+            .map(|(column, desc)| (column.clone(), !desc.is_scalar()))
+            .collect();
+        // Without the column set, the data managers named in the column descriptions are shown.
+        let column_set = column_set(&io, &array_columns).unwrap_or_default();
+        let manager_types: HashMap<u32, String> = column_set
+            .managers
+            .iter()
+            .cloned()
+            .map(|(t, s)| (s, t))
+            .collect();
+
+        let mut files = Vec::new();
+        let mut bytes_per_manager: HashMap<u32, u64> = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with("table.") || entry.path().is_dir() {
+                continue;
+            }
+            let bytes = entry.metadata()?.len();
+            let seq = file_sequence_number(&name);
+            if let Some(seq) = seq {
+                *bytes_per_manager.entry(seq).or_default() += bytes;
+            }
+            files.push(DataFile {
+                data_manager: seq
+                    .and_then(|s| manager_types.get(&s))
+                    .cloned()
+                    .unwrap_or_default(),
+                name,
+                bytes,
+            });
+        }
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Uncompressed size of the columns of each data manager, to compare with Dysco files.
+        let mut uncompressed_per_manager: HashMap<u32, Option<u64>> = HashMap::new();
+        let mut columns = Vec::new();
+        for (column, desc) in descriptions {
+            let seq = column_set.bindings.get(&column).copied();
+            let (desc_manager, group) = column_group(&io, &column).unwrap_or_default();
+            let data_manager = seq
+                .and_then(|s| manager_types.get(&s))
+                .cloned()
+                .unwrap_or(desc_manager);
+            let tile_shape = match seq {
+                Some(seq) if data_manager.starts_with("Tiled") => {
+                    tile_shapes(&fs::read(dir.join(format!("table.f{}", seq))).unwrap_or_default())
+                        .iter()
+                        .map(|s| format!("{:?}", s))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                }
+                _ => String::new(),
+            };
+            if let Some(seq) = seq {
+                let n_elements = if desc.is_scalar() {
+                    Some(1)
+                } else {
+                    desc.shape().map(|s| s.iter().product::<u64>())
+                };
+                let size = element_size(desc.data_type())
+                    .zip(n_elements)
+                    .map(|(size, n)| size * n * table.n_rows());
+                let total = uncompressed_per_manager.entry(seq).or_insert(Some(0));
+                *total = total.zip(size).map(|(a, b)| a + b);
+            }
+            columns.push((
+                seq,
+                ColumnStorage {
+                    column,
+                    data_manager,
+                    group,
+                    tile_shape,
+                    bytes_on_disk: seq.and_then(|s| bytes_per_manager.get(&s)).copied(),
+                    compression_ratio: None,
+                    dysco_settings: None,
+                },
+                // Weights are compressed with their own bit rate.
+                matches!(
+                    desc.data_type(),
+                    GlueDataType::TpFloat | GlueDataType::TpArrayFloat
+                ),
+            ));
+        }
+        for (seq, column, is_weight) in columns.iter_mut() {
+            if column.data_manager != "DyscoStMan" {
+                continue;
+            }
+            if let Some(seq) = seq {
+                column.dysco_settings = Some(
+                    match DyscoParameters::read(&dir.join(format!("table.f{}", seq))) {
+                        Ok(parameters) => parameters.describe(*is_weight),
+                        Err(e) => format!("Failed to read the Dysco settings: {:#}", e),
+                    },
+                );
+            }
+            let uncompressed =
+                seq.and_then(|s| uncompressed_per_manager.get(&s).copied().flatten());
+            column.compression_ratio = uncompressed
+                .zip(column.bytes_on_disk)
+                .filter(|(_, on_disk)| *on_disk > 0)
+                .map(|(uncompressed, on_disk)| uncompressed as f64 / on_disk as f64);
+        }
+
+        Ok(StorageLayout {
+            columns: columns.into_iter().map(|(_, c, _)| c).collect(),
+            files,
+            selected: 0,
+        })
+    }
+
+    pub fn move_selection(&mut self, amount: usize, forwards: bool) {
+        if forwards {
+            self.selected = (self.selected + amount).min(self.columns.len().saturating_sub(1));
+        } else {
+            self.selected = self.selected.saturating_sub(amount);
+        }
+    }
+}
+
+/// Format a number of bytes with a binary prefix, e.g. 1.5 GiB.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer for the AipsIO encoding, to build table files laid out the way casacore writes them.
+    struct Writer {
+        data: Vec<u8>,
+        starts: Vec<usize>,
+        big_endian: bool,
+    }
+
+    impl Writer {
+        fn new(big_endian: bool) -> Self {
+            Writer {
+                data: Vec::new(),
+                starts: Vec::new(),
+                big_endian,
+            }
+        }
+
+        fn u32(&mut self, value: u32) -> &mut Self {
+            let bytes = if self.big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            };
+            self.data.extend_from_slice(&bytes);
+            self
+        }
+
+        fn i32(&mut self, value: i32) -> &mut Self {
+            self.u32(value as u32)
+        }
+
+        fn bool(&mut self, value: bool) -> &mut Self {
+            self.data.push(value as u8);
+            self
+        }
+
+        fn f64(&mut self, value: f64) -> &mut Self {
+            let bytes = if self.big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            };
+            self.data.extend_from_slice(&bytes);
+            self
+        }
+
+        fn string(&mut self, s: &str) -> &mut Self {
+            self.u32(s.len() as u32);
+            self.data.extend_from_slice(s.as_bytes());
+            self
+        }
+
+        /// Start an object; its length is filled in by `end`.
+        fn start(&mut self, type_name: &str, version: u32) -> &mut Self {
+            if self.starts.is_empty() {
+                self.u32(0xbebebebe);
+            }
+            self.starts.push(self.data.len());
+            self.u32(0).string(type_name).u32(version)
+        }
+
+        fn end(&mut self) -> &mut Self {
+            let start = self.starts.pop().unwrap();
+            let len = (self.data.len() - start) as u32;
+            let bytes = if self.big_endian {
+                len.to_be_bytes()
+            } else {
+                len.to_le_bytes()
+            };
+            self.data[start..start + 4].copy_from_slice(&bytes);
+            self
+        }
+
+        fn shape(&mut self, shape: &[u32]) -> &mut Self {
+            self.start("IPosition", 1).u32(shape.len() as u32);
+            for n in shape {
+                self.u32(*n);
+            }
+            self.end()
+        }
+
+        /// A keyword set with a single string field.
+        fn record(&mut self, field: Option<(&str, &str)>) -> &mut Self {
+            self.start("TableRecord", 1).start("RecordDesc", 2);
+            match field {
+                Some((name, _)) => self.i32(1).string(name).i32(25).string(""),
+                None => self.i32(0),
+            };
+            self.end();
+            if let Some((_, value)) = field {
+                self.string(value);
+            }
+            self.i32(0).end()
+        }
+    }
+
+    struct TestColumn {
+        name: &'static str,
+        comment: &'static str,
+        data_manager: &'static str,
+        group: &'static str,
+        array: bool,
+        seq: u32,
+    }
+
+    const COLUMNS: [TestColumn; 4] = [
+        TestColumn {
+            name: "ANTENNA1",
+            comment: "ID of first antenna in interferometer",
+            data_manager: "StandardStMan",
+            group: "StandardStMan",
+            array: false,
+            seq: 0,
+        },
+        TestColumn {
+            name: "TIME",
+            comment: "Modified Julian Day",
+            data_manager: "IncrementalStMan",
+            group: "ISMData",
+            array: false,
+            seq: 1,
+        },
+        TestColumn {
+            name: "FLAG",
+            comment: "The data flags, array of bools with same shape as data",
+            data_manager: "TiledColumnStMan",
+            group: "TiledFlag",
+            array: true,
+            seq: 2,
+        },
+        TestColumn {
+            name: "DATA",
+            comment: "The data column",
+            data_manager: "DyscoStMan",
+            group: "DyscoData",
+            array: true,
+            seq: 3,
+        },
+    ];
+
+    const MANAGERS: [(&str, u32); 4] = [
+        ("StandardStMan", 0),
+        ("IncrementalStMan", 1),
+        ("TiledColumnStMan", 2),
+        ("DyscoStMan", 3),
+    ];
+
+    /// A table.dat with the table description, followed by the data managers and the columns.
+    /// The column set claims to have `column_set_rows` rows; the table has 1000.
+    fn table_dat(big_endian: bool, column_set_rows: u32) -> Vec<u8> {
+        let mut w = Writer::new(big_endian);
+        w.start("Table", 2).u32(1000).u32(!big_endian as u32);
+        w.string("PlainTable").start("TableDesc", 2);
+        w.string("").string("1.0").string("");
+        w.record(Some(("MS_VERSION", "2.0"))).record(None);
+        w.u32(1).u32(COLUMNS.len() as u32);
+        for column in &COLUMNS {
+            let class = if column.array {
+                "ArrayColumnDesc<Complex>"
+            } else {
+                "ScalarColumnDesc<Double  >"
+            };
+            w.u32(1).string(class).u32(1);
+            w.string(column.name).string(column.comment);
+            w.string(column.data_manager).string(column.group);
+            w.i32(if column.array { 21 } else { 6 }).i32(0);
+            w.i32(if column.array { -1 } else { 0 }).shape(&[]).i32(0);
+            w.record(column.array.then_some(("UNIT", "Jy")));
+            if column.array {
+                w.bool(false);
+            } else {
+                w.f64(0.0);
+            }
+        }
+        w.end();
+        w.i32(-2).u32(column_set_rows).u32(MANAGERS.len() as u32);
+        w.u32(MANAGERS.len() as u32);
+        for (data_manager, seq) in MANAGERS {
+            w.string(data_manager).u32(seq);
+        }
+        for column in &COLUMNS {
+            w.u32(2).string(column.name).u32(1).u32(column.seq);
+            if column.array {
+                w.bool(true).shape(&[4, 64]);
+            }
+        }
+        for _ in MANAGERS {
+            w.u32(0);
+        }
+        w.end().data.clone()
+    }
+
+    fn test_columns() -> Vec<(String, bool)> {
+        COLUMNS
+            .iter()
+            .map(|column| (column.name.to_string(), column.array))
+            .collect()
+    }
+
+    fn check_bindings(big_endian: bool) {
+        let data = table_dat(big_endian, 1000);
+        let io = AipsIo::detect(&data);
+        assert_eq!(io.big_endian, big_endian);
+        let column_set = column_set(&io, &test_columns()).unwrap();
+        let expected: Vec<(String, u32)> = MANAGERS
+            .iter()
+            .map(|(t, seq)| (t.to_string(), *seq))
+            .collect();
+        assert_eq!(column_set.managers, expected);
+        assert_eq!(column_set.bindings.len(), COLUMNS.len());
+        for column in &COLUMNS {
+            assert_eq!(
+                column_set.bindings.get(column.name),
+                Some(&column.seq),
+                "{}",
+                column.name
+            );
+            assert_eq!(
+                column_group(&io, column.name),
+                Some((column.data_manager.to_string(), column.group.to_string())),
+                "{}",
+                column.name
+            );
+        }
+    }
+
+    #[test]
+    fn columns_are_bound_to_their_data_managers() {
+        check_bindings(true);
+    }
+
+    #[test]
+    fn little_endian_tables() {
+        check_bindings(false);
+    }
+
+    #[test]
+    fn column_sets_that_do_not_match_the_table_are_rejected() {
+        let data = table_dat(true, 999);
+        assert!(column_set(&AipsIo::detect(&data), &test_columns()).is_err());
+        // Reading an array column as a scalar one loses the position of the next column.
+        let data = table_dat(true, 1000);
+        let mut columns = test_columns();
+        columns[2].1 = false;
+        assert!(column_set(&AipsIo::detect(&data), &columns).is_err());
+    }
+
+    #[test]
+    fn tile_shapes_of_the_hypercubes() {
+        let mut w = Writer::new(true);
+        w.start("TiledColumnStMan", 2).u32(0).string("TiledFlag");
+        // The default tile shape comes before the cubes and is not one of them.
+        w.shape(&[4, 32, 128]).u32(2);
+        for (cube, tile) in [
+            ([4, 64, 1000], [4, 64, 16]),
+            ([4, 64, 500], [4, 64, 16]),
+            ([4, 16, 100], [4, 16, 50]),
+        ] {
+            w.start("TSMCube", 1).u32(3).shape(&cube).shape(&tile);
+            w.record(None).end();
+        }
+        w.end();
+        assert_eq!(tile_shapes(&w.data), vec![vec![4, 64, 16], vec![4, 16, 50]]);
+    }
+
+    #[test]
+    fn sequence_numbers_of_data_files() {
+        assert_eq!(file_sequence_number("table.f3_TSM0"), Some(3));
+        assert_eq!(file_sequence_number("table.f12"), Some(12));
+        assert_eq!(file_sequence_number("table.dat"), None);
+        assert_eq!(file_sequence_number("table.f"), None);
+    }
+}
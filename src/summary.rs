@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use rubbl_casatables::{Table, TableOpenMode};
+
+use crate::measures::mjd_seconds_to_date;
+
+/// Names of the Stokes/correlation types as used in the CORR_TYPE column of the POLARIZATION table.
+pub fn stokes_name(corr_type: i32) -> &'static str {
+    match corr_type {
+        1 => "I",
+        2 => "Q",
+        3 => "U",
+        4 => "V",
+        5 => "RR",
+        6 => "RL",
+        7 => "LR",
+        8 => "LL",
+        9 => "XX",
+        10 => "XY",
+        11 => "YX",
+        12 => "YY",
+        _ => "??",
+    }
+}
+
+/// Open a subtable of the given Measurement Set.
+pub fn open_subtable(ms: &str, name: &str) -> Result<Table> {
+    let path = format!("{}/{}", ms.trim_end_matches('/'), name);
+    Table::open(&path, TableOpenMode::Read).with_context(|| format!("failed to open {}", path))
+}
+
+/// Format a time in MJD seconds the way msoverview does, e.g. 08-Mar-2018/11:46:12.0.
+fn format_mjd_seconds(mjd_seconds: f64) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (year, month, day, hour, minute, second) = mjd_seconds_to_date(mjd_seconds);
+    format!(
+        "{:02}-{}-{}/{:02}:{:02}:{:04.1}",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        hour,
+        minute,
+        second
+    )
+}
+
+/// Format an angle in radians as sexagesimal hours, e.g. 12:30:49.423.
+fn format_ra(radians: f64) -> String {
+    let hours = radians.to_degrees().rem_euclid(360.0) / 15.0;
+    let h = hours.floor();
+    let m = ((hours - h) * 60.0).floor();
+    let s = ((hours - h) * 60.0 - m) * 60.0;
+    format!("{:02}:{:02}:{:06.3}", h, m, s)
+}
+
+/// Format an angle in radians as sexagesimal degrees, e.g. +12.23.28.04.
+fn format_dec(radians: f64) -> String {
+    let degrees = radians.to_degrees();
+    let sign = if degrees < 0.0 { '-' } else { '+' };
+    let degrees = degrees.abs();
+    let d = degrees.floor();
+    let m = ((degrees - d) * 60.0).floor();
+    let s = ((degrees - d) * 60.0 - m) * 60.0;
+    format!("{}{:02}.{:02}.{:05.2}", sign, d, m, s)
+}
+
+/// Build a plain-text overview of a Measurement Set, modelled on msoverview and listobs.
+pub fn summarise(ms: &str) -> Result<String> {
+    let ms = ms.trim_end_matches('/');
+    let mut main =
+        Table::open(ms, TableOpenMode::Read).with_context(|| format!("failed to open {}", ms))?;
+    let mut buf = String::new();
+
+    buf.push_str(&format!("Measurement Set: {}\n", ms));
+    buf.push_str(&format!("Number of rows: {}\n", main.n_rows()));
+
+    let mut obs = open_subtable(ms, "OBSERVATION")?;
+    for row in 0..obs.n_rows() {
+        let telescope = obs.get_cell::<String>("TELESCOPE_NAME", row)?;
+        let observer = obs.get_cell::<String>("OBSERVER", row)?;
+        let project = obs.get_cell::<String>("PROJECT", row)?;
+        buf.push_str(&format!(
+            "Observation {}: telescope {}, observer {}, project {}\n",
+            row, telescope, observer, project
+        ));
+    }
+
+    if main.n_rows() > 0 {
+        let times = main.get_col_as_vec::<f64>("TIME")?;
+        let start = times.iter().cloned().fold(f64::INFINITY, f64::min);
+        let end = times.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let mut unique_times = times.clone();
+        unique_times.sort_by(|a, b| a.total_cmp(b));
+        unique_times.dedup();
+        let interval = main.get_cell::<f64>("INTERVAL", 0)?;
+        buf.push_str(&format!(
+            "Observed from {} to {} (UTC), {:.1} s\n",
+            format_mjd_seconds(start - interval / 2.0),
+            format_mjd_seconds(end + interval / 2.0),
+            end - start + interval
+        ));
+        buf.push_str(&format!("Integration time: {:.5} s\n", interval));
+        buf.push_str(&format!("Number of time slots: {}\n", unique_times.len()));
+    }
+    buf.push('\n');
+
+    let mut fields = open_subtable(ms, "FIELD")?;
+    buf.push_str(&format!("Fields: {}\n", fields.n_rows()));
+    buf.push_str(&format!(
+        "  {:>3}  {:<20} {:>14} {:>14}\n",
+        "ID", "Name", "RA", "Dec"
+    ));
+    for row in 0..fields.n_rows() {
+        let name = fields.get_cell::<String>("NAME", row)?;
+        let dir = fields.get_cell_as_vec::<f64>("PHASE_DIR", row)?;
+        let (ra, dec) = if dir.len() >= 2 {
+            (format_ra(dir[0]), format_dec(dir[1]))
+        } else {
+            ("-".to_string(), "-".to_string())
+        };
+        buf.push_str(&format!(
+            "  {:>3}  {:<20} {:>14} {:>14}\n",
+            row, name, ra, dec
+        ));
+    }
+    buf.push('\n');
+
+    let mut spws = open_subtable(ms, "SPECTRAL_WINDOW")?;
+    let mut n_chan_total = 0;
+    buf.push_str(&format!("Spectral windows (subbands): {}\n", spws.n_rows()));
+    buf.push_str(&format!(
+        "  {:>3}  {:>6}  {:>16}  {:>16}  {:>16}\n",
+        "ID", "#Chans", "Ref freq (MHz)", "Chan wid (kHz)", "Total BW (kHz)"
+    ));
+    for row in 0..spws.n_rows() {
+        let n_chan = spws.get_cell::<i32>("NUM_CHAN", row)?;
+        let ref_freq = spws.get_cell::<f64>("REF_FREQUENCY", row)?;
+        let total_bw = spws.get_cell::<f64>("TOTAL_BANDWIDTH", row)?;
+        let chan_width = spws.get_cell_as_vec::<f64>("CHAN_WIDTH", row)?;
+        n_chan_total += n_chan;
+        buf.push_str(&format!(
+            "  {:>3}  {:>6}  {:>16.6}  {:>16.6}  {:>16.6}\n",
+            row,
+            n_chan,
+            ref_freq / 1e6,
+            chan_width.first().copied().unwrap_or(0.0) / 1e3,
+            total_bw / 1e3
+        ));
+    }
+    buf.push_str(&format!("Total number of channels: {}\n", n_chan_total));
+    buf.push('\n');
+
+    let mut pols = open_subtable(ms, "POLARIZATION")?;
+    buf.push_str(&format!("Polarisation setups: {}\n", pols.n_rows()));
+    for row in 0..pols.n_rows() {
+        let n_corr = pols.get_cell::<i32>("NUM_CORR", row)?;
+        let corr_types = pols.get_cell_as_vec::<i32>("CORR_TYPE", row)?;
+        let names: Vec<&str> = corr_types.iter().map(|c| stokes_name(*c)).collect();
+        buf.push_str(&format!(
+            "  {:>3}  {} correlations: {}\n",
+            row,
+            n_corr,
+            names.join(" ")
+        ));
+    }
+    buf.push('\n');
+
+    let mut antennas = open_subtable(ms, "ANTENNA")?;
+    buf.push_str(&format!("Stations: {}\n", antennas.n_rows()));
+    let mut names = Vec::new();
+    for row in 0..antennas.n_rows() {
+        names.push(antennas.get_cell::<String>("NAME", row)?);
+    }
+    for chunk in names.chunks(8) {
+        let line: Vec<String> = chunk.iter().map(|n| format!("{: <10}", n)).collect();
+        buf.push_str(&format!("  {}\n", line.join(" ").trim_end()));
+    }
+
+    Ok(buf)
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use rubbl_casatables::{Table, TableOpenMode};
+
+/// A table in the Tables panel.
+pub struct TableNode {
+    /// Name of the keyword that refers to the table, or MAIN for the Measurement Set itself.
+    pub name: String,
+    pub path: String,
+    pub depth: usize,
+    pub expanded: bool,
+    /// Names of the table-valued keywords, once the table has been looked into.
+    pub subtables: Option<Vec<String>>,
+}
+
+impl TableNode {
+    fn new(name: &str, path: String, depth: usize) -> Self {
+        TableNode {
+            name: name.to_string(),
+            path,
+            depth,
+            expanded: false,
+            subtables: None,
+        }
+    }
+
+    /// Whether the table may have subtables; unknown until it has been expanded once.
+    pub fn has_subtables(&self) -> bool {
+        self.subtables.as_ref().is_none_or(|s| !s.is_empty())
+    }
+}
+
+/// The tables of a Measurement Set as a tree, flattened in display order.
+///
+/// Subtables are found through table-valued keywords and are expected to live in the directory
+/// of their parent, as is the case for the subtables of a Measurement Set.
+pub struct TableTree {
+    pub nodes: Vec<TableNode>,
+}
+
+impl TableTree {
+    /// Tree with the MAIN table expanded to show the given first-level subtables.
+    pub fn new(ms_name: &str, subtables: Vec<String>) -> Self {
+        let mut main = TableNode::new("MAIN", ms_name.to_string(), 0);
+        main.expanded = true;
+        let mut nodes = vec![main];
+        for name in subtables.iter() {
+            nodes.push(TableNode::new(name, format!("{}/{}", ms_name, name), 1));
+        }
+        nodes[0].subtables = Some(subtables);
+        TableTree { nodes }
+    }
+
+    /// Show the subtables of a table, looking them up the first time.
+    pub fn expand(&mut self, index: usize) -> Result<()> {
+        if self.nodes[index].expanded {
+            return Ok(());
+        }
+        let subtables = match &self.nodes[index].subtables {
+            Some(subtables) => subtables.clone(),
+            None => {
+                let mut t = Table::open(&self.nodes[index].path, TableOpenMode::Read)?;
+                let subtables = t.table_keyword_names()?;
+                self.nodes[index].subtables = Some(subtables.clone());
+                subtables
+            }
+        };
+        let node = &mut self.nodes[index];
+        node.expanded = true;
+        let children: Vec<TableNode> = subtables
+            .iter()
+            .map(|name| TableNode::new(name, format!("{}/{}", node.path, name), node.depth + 1))
+            .collect();
+        self.nodes.splice(index + 1..index + 1, children);
+        Ok(())
+    }
+
+    /// Hide the subtables of a table, including any that were expanded below them.
+    pub fn collapse(&mut self, index: usize) {
+        let depth = self.nodes[index].depth;
+        let end = self.nodes[index + 1..]
+            .iter()
+            .position(|n| n.depth <= depth)
+            .map_or(self.nodes.len(), |i| index + 1 + i);
+        self.nodes.drain(index + 1..end);
+        self.nodes[index].expanded = false;
+    }
+
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        let depth = self.nodes[index].depth;
+        self.nodes[..index].iter().rposition(|n| n.depth < depth)
+    }
+
+    /// Names of the tables from MAIN down to the given table.
+    pub fn breadcrumb(&self, index: usize) -> Vec<&str> {
+        let mut names = vec![self.nodes[index].name.as_str()];
+        let mut current = index;
+        while let Some(parent) = self.parent(current) {
+            names.push(&self.nodes[parent].name);
+            current = parent;
+        }
+        names.reverse();
+        names
+    }
+}
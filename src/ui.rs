@@ -1,12 +1,26 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
+    symbols,
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{
+        canvas::{Canvas, Points},
+        Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem, ListState,
+        Paragraph, Row, Table, TableState, Tabs, Wrap,
+    },
     Frame,
 };
 
 use crate::app::{App, CurrentScreen, CurrentlyEditing};
+use crate::diff::format_diff;
+use crate::foreign_keys::format_foreign_key;
+use crate::measures::format_epoch;
+use crate::storage::format_bytes;
+use crate::uvplot::StationType;
+use crate::waterfall::Bin;
+
+/// Frames of the spinner shown while column values are being loaded or a table is opened.
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
 pub fn ui(frame: &mut Frame, app: &mut App) {
     // Create the layout sections.
@@ -23,10 +37,14 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         .borders(Borders::ALL)
         .style(Style::default());
 
-    let title = Paragraph::new(Text::styled(
-        "LOFAR MSOxide",
-        Style::default().fg(Color::Green),
-    ))
+    let breadcrumb = app.tables.breadcrumb(app.current_table).join(" > ");
+    let title = Paragraph::new(Line::from(vec![
+        Span::styled("LOFAR MSOxide", Style::default().fg(Color::Green)),
+        Span::styled(
+            format!("  {}", breadcrumb),
+            Style::default().fg(Color::Yellow),
+        ),
+    ]))
     .block(title_block)
     .centered()
     .bold();
@@ -35,51 +53,110 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
     let mut table_items = Vec::<ListItem>::new();
     let mut column_items = Vec::<ListItem>::new();
 
-    for key in app.tables.iter() {
-        table_items.push(ListItem::new(Line::from(Span::styled(
-            format!("{: <25}", key),
-            Style::default().fg(Color::Yellow),
-        ))));
+    // While searching, the searched list only shows the matches, best first.
+    let searching = matches!(app.current_screen, CurrentScreen::Search);
+    let searching_tables = searching && matches!(app.currently_editing, CurrentlyEditing::Table);
+    let searching_columns = searching && matches!(app.currently_editing, CurrentlyEditing::Column);
+
+    let table_marker = |i: usize| {
+        let node = &app.tables.nodes[i];
+        let marker = if !node.has_subtables() {
+            "  "
+        } else if node.expanded {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        format!("{}{}", "  ".repeat(node.depth), marker)
+    };
+    if searching_tables {
+        for (i, positions) in app.search_matches.iter() {
+            let name = &app.tables.nodes[*i].name;
+            table_items.push(highlighted_item(table_marker(*i), name, positions));
+        }
+    } else {
+        for (i, node) in app.tables.nodes.iter().enumerate() {
+            table_items.push(ListItem::new(Line::from(Span::styled(
+                format!("{}{: <25}", table_marker(i), node.name),
+                Style::default().fg(Color::Yellow),
+            ))));
+        }
     }
 
-    for key in app.columns.iter() {
-        column_items.push(ListItem::new(Line::from(Span::styled(
-            format!("{: <25}", key),
-            Style::default().fg(Color::Yellow),
-        ))));
+    if searching_columns {
+        for (i, positions) in app.search_matches.iter() {
+            column_items.push(highlighted_item(String::new(), &app.columns[*i], positions));
+        }
+    } else {
+        for key in app.columns.iter() {
+            column_items.push(ListItem::new(Line::from(Span::styled(
+                format!("{: <25}", key),
+                Style::default().fg(Color::Yellow),
+            ))));
+        }
     }
 
     let [left, right] =
         Layout::horizontal([Constraint::Percentage(25), Constraint::Percentage(75)])
             .areas(chunks[1]);
-    let [info_head, info_body] = Layout::vertical([Constraint::Percentage(15), Constraint::Percentage(85)]).areas(right);
+    let [info_head, info_body] =
+        Layout::vertical([Constraint::Percentage(15), Constraint::Percentage(85)]).areas(right);
     let [top_left, bottom_left] =
         Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(left);
     // Adapt the number of lines we'll read in the info panel
     // based on how many lines are shown in the terminal.
     app.line_height = right.height;
 
+    let search_title = |name: &str| format!(" {} (search: {}_) ", name, app.search_input);
     let mut solset_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Tables ")
+        .title(if searching_tables {
+            search_title("Tables")
+        } else {
+            " Tables ".to_string()
+        })
         .style(Style::default());
     let mut soltab_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Fields ")
+        .title(if searching_columns {
+            search_title("Fields")
+        } else if app.opening_table.is_some() {
+            format!(
+                " Fields {} opening table ",
+                SPINNER[app.spinner % SPINNER.len()]
+            )
+        } else {
+            " Fields ".to_string()
+        })
         .style(Style::default());
     let info_block_head = Block::default()
         .borders(Borders::ALL)
         .title(" Column Information ")
         .style(Style::default());
+    let values_name = match &app.values_unit {
+        Some(unit) => format!("Column Values ({})", unit),
+        None => "Column Values".to_string(),
+    };
+    let values_title = if app.is_loading() {
+        format!(
+            " {} {} loading (Esc to cancel) ",
+            values_name,
+            SPINNER[app.spinner % SPINNER.len()]
+        )
+    } else {
+        format!(" {} ", values_name)
+    };
     let mut info_block_body = Block::default()
         .borders(Borders::ALL)
-        .title(" Column Values ")
+        .title(values_title)
         .style(Style::default());
     let active_style = Style::default().bg(Color::White).fg(Color::Black);
     match &app.currently_editing {
         CurrentlyEditing::Table => solset_block = solset_block.border_style(active_style),
         CurrentlyEditing::Column => soltab_block = soltab_block.border_style(active_style),
-        CurrentlyEditing::Information => info_block_body = info_block_body.border_style(active_style),
+        CurrentlyEditing::Information => {
+            info_block_body = info_block_body.border_style(active_style)
+        }
     }
 
     let table_list = List::new(table_items)
@@ -87,7 +164,11 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         .highlight_style(Style::default().bold())
         .highlight_symbol(">> ");
     let mut table_list_state = ListState::default();
-    table_list_state.select(Some(app.current_table));
+    table_list_state.select(Some(if searching_tables {
+        app.search_selected
+    } else {
+        app.current_table
+    }));
 
     let column_list = List::new(column_items)
         .block(soltab_block)
@@ -95,7 +176,11 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         .highlight_symbol(">> ")
         .style(Style::default().fg(Color::White));
     let mut column_list_state = ListState::default();
-    column_list_state.select(Some(app.current_column));
+    column_list_state.select(Some(if searching_columns {
+        app.search_selected
+    } else {
+        app.current_column
+    }));
 
     let info_text_head = Paragraph::new(app.text_buffer_head.clone())
         .block(info_block_head)
@@ -111,22 +196,125 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
     //frame.render_widget(info_text, right);
     frame.render_widget(info_text_head, info_head);
     frame.render_widget(info_text, info_body);
-    let current_navigation_text = vec![
+    let mut current_navigation_text = vec![
         // The first half of the text
         match app.current_screen {
             CurrentScreen::Main | CurrentScreen::Help => {
                 Span::styled("Normal Mode", Style::default().fg(Color::Green))
             }
             CurrentScreen::Exiting => Span::styled("Exiting", Style::default().fg(Color::LightRed)),
+            CurrentScreen::Error => Span::styled("Error", Style::default().fg(Color::LightRed)),
+            CurrentScreen::Inspector => {
+                Span::styled("Cell Inspector", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::Plot => Span::styled("Plot", Style::default().fg(Color::LightBlue)),
+            CurrentScreen::Waterfall => {
+                Span::styled("Waterfall", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::UvCoverage => {
+                Span::styled("UV Coverage", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::FlagStats => {
+                Span::styled("Flag Statistics", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::ColumnStats => {
+                Span::styled("Column Statistics", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::Keywords => {
+                Span::styled("Keywords", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::Storage => {
+                Span::styled("Storage Layout", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::Query => Span::styled("Query", Style::default().fg(Color::Yellow)),
+            CurrentScreen::GoTo => Span::styled("Go To", Style::default().fg(Color::Yellow)),
+            CurrentScreen::StatsRange => {
+                Span::styled("Statistics Rows", Style::default().fg(Color::Yellow))
+            }
+            CurrentScreen::Search => Span::styled("Search", Style::default().fg(Color::Yellow)),
+            CurrentScreen::Export => Span::styled("Export", Style::default().fg(Color::Yellow)),
+            CurrentScreen::Diff => {
+                Span::styled("Compare", Style::default().fg(Color::LightBlue))
+            }
+            CurrentScreen::CompareColumns => {
+                Span::styled("Compare Columns", Style::default().fg(Color::Yellow))
+            }
+            CurrentScreen::Comparison => {
+                Span::styled("Column Comparison", Style::default().fg(Color::LightBlue))
+            }
         }
         .to_owned(),
         // A white divider bar to separate the two sections
         Span::styled(" | ", Style::default().fg(Color::White)),
         // The final section of the text, with hints on what the user is editing
-        {
-            match &app.currently_editing {
+        match app.current_screen {
+            CurrentScreen::Inspector => Span::styled(
+                "<left/right> axis / <[ ]> slide range / <{ }> resize range / <s> single / <a> all / <n/p> next/prev row",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Plot => Span::styled(
+                "<a/p> amplitude/phase / <[ ]> antenna 1 / <{ }> antenna 2 / <c> correlation / << >> slide channels / <- +> resize channels / <f> flagged",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Waterfall => Span::styled(
+                "<[ ]> antenna 1 / <{ }> antenna 2 / <c> correlation",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::UvCoverage => Span::styled(
+                "<c> core / <r> remote / <i> international stations / <f> unflagged rows only",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::FlagStats => Span::styled(
+                "<left/right> switch table / <up/down> move / <s> sort by name/percentage",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::ColumnStats => Span::styled(
+                "<s> whole column / <S> range of rows, from the main screen / <Esc> cancel",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Keywords => Span::styled(
+                "<up/down> move / <right/left> expand/collapse / <Enter> toggle",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Storage => Span::styled(
+                "<up/down> move",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Search => Span::styled(
+                "type to search by name / <up/down> move / <Enter> select",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::GoTo => Span::styled(
+                "row number, e.g. 1200 / percentage, e.g. 50% / UTC time, e.g. 2018-03-08T11:46:12 or 11:46",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::StatsRange => Span::styled(
+                "start:end with the end excluded, e.g. 1000:2000 / either may be left out, e.g. 1000: / <Enter> compute",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Export => Span::styled(
+                "<Tab> next field / <space> complex format / <Enter> export / .csv .tsv .npy .npz .arrow .parquet / no columns for all",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Diff => Span::styled(
+                "<up/down> scroll rows / <n/p> next/previous differing row / <[ ]> scroll differences",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::CompareColumns => Span::styled(
+                "e.g. DATA - MODEL_DATA for the difference or CORRECTED_DATA / DATA for the ratio / <Tab> table per row / <Enter> compare",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Comparison => Span::styled(
+                "<left/right> switch table / <up/down> move / <v> plot / <w> waterfall of the combination",
+                Style::default().fg(Color::LightGreen),
+            ),
+            CurrentScreen::Query => Span::styled(
+                "e.g. ANTENNA1 = CS002 AND ANTENNA2 = RS503 AND SCAN_NUMBER IN [3:5] / <Enter> apply, empty to clear",
+                Style::default().fg(Color::LightGreen),
+            ),
+            _ => match &app.currently_editing {
                 CurrentlyEditing::Table => Span::styled(
-                    "<up/down> move / <Enter> Select / <Tab> switch panel / <?> help",
+                    "<up/down> move / <right/left> subtables / <Enter> Select / <Tab> switch panel / <?> help",
                     Style::default().fg(Color::LightGreen),
                 ),
                 CurrentlyEditing::Column => Span::styled(
@@ -134,12 +322,25 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
                     Style::default().fg(Color::LightGreen),
                 ),
                 CurrentlyEditing::Information => Span::styled(
-                    "<up/down> scroll text / <Tab> switch panel / <?> help",
+                    "<up/down> scroll text / <t> raw/decoded values / <s/S> statistics / <Tab> switch panel / <?> help",
                     Style::default().fg(Color::LightGreen),
                 ),
-            }
+            },
         },
     ];
+    // Show the active filter right after the mode, so long key hints do not push it out of view.
+    if let Some(filter) = &app.filter {
+        current_navigation_text.splice(
+            1..1,
+            [
+                Span::styled(" | ", Style::default().fg(Color::White)),
+                Span::styled(
+                    format!("Filter: {} ({} rows)", filter.query, filter.rows.len()),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ],
+        );
+    }
 
     let mode_footer = Paragraph::new(Line::from(current_navigation_text))
         .block(Block::default().borders(Borders::ALL));
@@ -150,6 +351,29 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
                 Span::styled("<q> to quit", Style::default().fg(Color::Red))
             }
             CurrentScreen::Exiting => Span::styled("<q> to quit", Style::default().fg(Color::Red)),
+            CurrentScreen::Error => {
+                Span::styled("<q/Esc/Enter> to close", Style::default().fg(Color::Red))
+            }
+            CurrentScreen::Query
+            | CurrentScreen::GoTo
+            | CurrentScreen::StatsRange
+            | CurrentScreen::Search
+            | CurrentScreen::Export
+            | CurrentScreen::CompareColumns => {
+                Span::styled("<Esc> to cancel", Style::default().fg(Color::Red))
+            }
+            CurrentScreen::Inspector
+            | CurrentScreen::Plot
+            | CurrentScreen::Waterfall
+            | CurrentScreen::UvCoverage
+            | CurrentScreen::FlagStats
+            | CurrentScreen::ColumnStats
+            | CurrentScreen::Keywords
+            | CurrentScreen::Storage
+            | CurrentScreen::Diff
+            | CurrentScreen::Comparison => {
+                Span::styled("<q/Esc> to close", Style::default().fg(Color::Red))
+            }
         }
     };
 
@@ -186,6 +410,23 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         frame.render_widget(exit_paragraph, area);
     }
 
+    if let CurrentScreen::Error = app.current_screen {
+        let popup_block = Block::default()
+            .title(" Error (q/Esc/Enter to close) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+
+        let error_text = Text::styled(app.error_message.clone(), Style::default().fg(Color::Red));
+        let error_paragraph = Paragraph::new(error_text)
+            .block(popup_block)
+            .centered()
+            .wrap(Wrap { trim: false });
+
+        let area = centered_rect(50, 20, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(error_paragraph, area);
+    }
+
     if let CurrentScreen::Help = app.current_screen {
         let popup_block = Block::default()
             .title(" Help (q/Esc to exit)")
@@ -193,7 +434,7 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
             .style(Style::default().bg(Color::DarkGray));
 
         let help_text = Text::styled(
-            "Tab - cycle through panels\nup/down/j/k - select entry or move through data by one line\nJ/K - move through data by 10 lines\nPgUp/PgDown - move the text (useful for long rows spanning multiple lines)\nEnter - load data from field\nq - quit program",
+            "Tab - cycle through panels\nup/down/j/k - select entry or move through data by one line\nJ/K - move through data by 10 lines\nHome/End - go to the first/last row\ng - go to a row number, a percentage of the rows or a UTC time\nPgUp/PgDown - move the text (useful for long rows spanning multiple lines)\nright/left/l/h - expand/collapse subtables, or go up to the parent table\nn - search the Tables or Fields list by name\nEnter - load data from field\ni - inspect the array cell at the top row of Column Values\nv - plot amplitude/phase against time of a complex column\nw - show a time-frequency waterfall of a complex column\nu - show the UV coverage of the table\nf - show flag statistics per station, baseline, channel, correlation and time\ns - compute statistics of the selected column over all rows\nS - compute statistics of the selected column over a range of rows, start:end\ne - export columns over a range of all or the filtered rows to CSV, TSV, NumPy, Arrow or Parquet\nc - compare the selected column with the second MS side by side (lofar-msexplorer a.ms b.ms)\nx - compare two columns, e.g. DATA - MODEL_DATA or CORRECTED_DATA / DATA, per baseline and channel, and per row after Tab\nm - browse the keywords and TableInfo of the table and selected column\nd - show the storage managers and files on disk of the table\n/ - filter rows with a query, e.g. ANTENNA1 = CS002 AND SCAN_NUMBER BETWEEN 3 AND 5\nt - toggle between raw values and decoded values (e.g. TIME as UTC, ANTENNA1 as station name)\nEsc - cancel loading of column values\nq - quit program",
             Style::default().fg(Color::White),
         );
         // the `trim: false` will stop the text from being cut off when over the edge of the block
@@ -206,6 +447,829 @@ pub fn ui(frame: &mut Frame, app: &mut App) {
         frame.render_widget(Clear, area);
         frame.render_widget(help_paragraph, area);
     }
+
+    if let CurrentScreen::Inspector = app.current_screen {
+        render_inspector(frame, app);
+    }
+
+    if let CurrentScreen::Plot = app.current_screen {
+        render_plot(frame, app);
+    }
+
+    if let CurrentScreen::Waterfall = app.current_screen {
+        render_waterfall(frame, app);
+    }
+
+    if let CurrentScreen::UvCoverage = app.current_screen {
+        render_uv_plot(frame, app);
+    }
+
+    if let CurrentScreen::FlagStats = app.current_screen {
+        render_flag_stats(frame, app);
+    }
+
+    if app.reading_view.is_some() {
+        render_reading_view(frame, app);
+    }
+
+    if let CurrentScreen::ColumnStats = app.current_screen {
+        render_column_stats(frame, app);
+    }
+
+    if let CurrentScreen::Keywords = app.current_screen {
+        render_keywords(frame, app);
+    }
+
+    if let CurrentScreen::Storage = app.current_screen {
+        render_storage(frame, app);
+    }
+
+    if let CurrentScreen::Export = app.current_screen {
+        render_export(frame, app);
+    }
+
+    if let CurrentScreen::Diff = app.current_screen {
+        render_diff(frame, app);
+    }
+
+    if let CurrentScreen::Comparison = app.current_screen {
+        render_comparison(frame, app);
+    }
+
+    if let CurrentScreen::CompareColumns = app.current_screen {
+        let popup_block = Block::default()
+            .title(" Compare two columns (Enter to compare, Esc to cancel) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+        let per_row = if app.compare_per_row { "on" } else { "off" };
+        let compare_paragraph = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("x ", Style::default().fg(Color::Yellow)),
+                Span::styled(app.compare_input.clone(), Style::default().fg(Color::White)),
+                Span::styled("_", Style::default().fg(Color::Yellow)),
+            ]),
+            Line::from(Span::styled(
+                format!("Table per row: {} (Tab to toggle)", per_row),
+                Style::default().fg(Color::Gray),
+            )),
+        ])
+        .block(popup_block);
+        let area = centered_rect(50, 10, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(compare_paragraph, area);
+    }
+
+    if let CurrentScreen::GoTo = app.current_screen {
+        let popup_block = Block::default()
+            .title(" Go to row, percentage or time (Enter to go, Esc to cancel) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+        let goto_paragraph = Paragraph::new(Line::from(vec![
+            Span::styled("g ", Style::default().fg(Color::Yellow)),
+            Span::styled(app.goto_input.clone(), Style::default().fg(Color::White)),
+            Span::styled("_", Style::default().fg(Color::Yellow)),
+        ]))
+        .block(popup_block);
+        let area = centered_rect(50, 10, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(goto_paragraph, area);
+    }
+
+    if let CurrentScreen::StatsRange = app.current_screen {
+        let popup_block = Block::default()
+            .title(" Statistics over rows start:end (Enter to compute, Esc to cancel) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+        let range_paragraph = Paragraph::new(Line::from(vec![
+            Span::styled("S ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                app.stats_range_input.clone(),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled("_", Style::default().fg(Color::Yellow)),
+        ]))
+        .block(popup_block);
+        let area = centered_rect(50, 10, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(range_paragraph, area);
+    }
+
+    if let CurrentScreen::Query = app.current_screen {
+        let popup_block = Block::default()
+            .title(" Filter rows (Enter to apply, Esc to cancel) ")
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::DarkGray));
+        let query_paragraph = Paragraph::new(Line::from(vec![
+            Span::styled("/ ", Style::default().fg(Color::Yellow)),
+            Span::styled(app.query_input.clone(), Style::default().fg(Color::White)),
+            Span::styled("_", Style::default().fg(Color::Yellow)),
+        ]))
+        .block(popup_block)
+        .wrap(Wrap { trim: false });
+        let area = centered_rect(70, 10, frame.area());
+        frame.render_widget(Clear, area);
+        frame.render_widget(query_paragraph, area);
+    }
+}
+
+fn render_inspector(frame: &mut Frame, app: &App) {
+    let Some(inspector) = &app.inspector else {
+        return;
+    };
+    let area = centered_rect(90, 90, frame.area());
+    let header_height = inspector.axes.len() as u16 + 3;
+    let [head, body] =
+        Layout::vertical([Constraint::Length(header_height), Constraint::Min(1)]).areas(area);
+
+    let head_block = Block::default()
+        .title(" Cell Inspector (q/Esc to exit) ")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let head_text = Paragraph::new(inspector.render_header())
+        .block(head_block)
+        .style(Style::default().fg(Color::White));
+
+    let body_block = Block::default()
+        .title(" Values ")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    let body_text = Paragraph::new(inspector.render_values())
+        .block(body_block)
+        .style(Style::default().fg(Color::White))
+        .scroll((inspector.scroll, 0));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(head_text, head);
+    frame.render_widget(body_text, body);
+}
+
+fn render_plot(frame: &mut Frame, app: &App) {
+    let Some(plot) = &app.plot else {
+        return;
+    };
+    let selection = &plot.source.selection;
+    let antennas = app.labels("ANTENNA1");
+    let title = format!(
+        " {} of {} for baseline {} - {}, correlation {}, channels {}-{} ",
+        plot.quantity.name(),
+        plot.source.label(),
+        format_foreign_key(selection.antenna1, antennas),
+        format_foreign_key(selection.antenna2, antennas),
+        selection.correlation,
+        selection.chan_start,
+        selection.chan_end - 1
+    );
+
+    let mut datasets = vec![Dataset::default()
+        .name("unflagged")
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Scatter)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&plot.points)];
+    if plot.show_flagged {
+        datasets.push(
+            Dataset::default()
+                .name("flagged")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(Color::Red))
+                .data(&plot.flagged_points),
+        );
+    }
+
+    let (x_bounds, y_bounds) = plot.bounds();
+    // Label the time axis with the UTC time of day.
+    let time_label = |t: f64| Span::raw(format_epoch(plot.time_origin + t)[11..19].to_string());
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Time (UTC)")
+                .style(Style::default().fg(Color::Gray))
+                .bounds(x_bounds)
+                .labels(vec![
+                    time_label(x_bounds[0]),
+                    time_label((x_bounds[0] + x_bounds[1]) / 2.0),
+                    time_label(x_bounds[1]),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(plot.quantity.name())
+                .style(Style::default().fg(Color::Gray))
+                .bounds(y_bounds)
+                .labels(vec![
+                    Span::raw(format!("{:.3}", y_bounds[0])),
+                    Span::raw(format!("{:.3}", (y_bounds[0] + y_bounds[1]) / 2.0)),
+                    Span::raw(format!("{:.3}", y_bounds[1])),
+                ]),
+        );
+
+    let area = centered_rect(95, 90, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(chart, area);
+}
+
+/// Map a value in [0, 1] onto a viridis-like colour scale.
+fn colour_scale(value: f64) -> Color {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (68.0, 1.0, 84.0),
+        (59.0, 82.0, 139.0),
+        (33.0, 145.0, 140.0),
+        (94.0, 201.0, 98.0),
+        (253.0, 231.0, 37.0),
+    ];
+    let position = value.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (position.floor() as usize).min(STOPS.len() - 2);
+    let f = position - i as f64;
+    let (r0, g0, b0) = STOPS[i];
+    let (r1, g1, b1) = STOPS[i + 1];
+    Color::Rgb(
+        (r0 + f * (r1 - r0)) as u8,
+        (g0 + f * (g1 - g0)) as u8,
+        (b0 + f * (b1 - b0)) as u8,
+    )
+}
+
+fn render_waterfall(frame: &mut Frame, app: &App) {
+    let Some(waterfall) = &app.waterfall else {
+        return;
+    };
+    let selection = &waterfall.source.selection;
+    let antennas = app.labels("ANTENNA1");
+    let title = format!(
+        " Amplitude of {} for baseline {} - {}, correlation {}, scale {:.3} - {:.3}, flagged in magenta ",
+        waterfall.source.label(),
+        format_foreign_key(selection.antenna1, antennas),
+        format_foreign_key(selection.antenna2, antennas),
+        selection.correlation,
+        waterfall.scale.0,
+        waterfall.scale.1
+    );
+    let area = centered_rect(95, 90, frame.area());
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let [body, freq_axis] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+    let [time_axis, heatmap] =
+        Layout::horizontal([Constraint::Length(9), Constraint::Min(1)]).areas(body);
+
+    let bins = waterfall.bin(heatmap.width as usize, heatmap.height as usize);
+    let n_time = waterfall.times.len();
+    let mut time_labels = Vec::new();
+    let mut lines = Vec::new();
+    for (r, row) in bins.iter().enumerate() {
+        let spans: Vec<Span> = row
+            .iter()
+            .map(|bin| match bin {
+                Bin::Empty => Span::raw(" "),
+                Bin::Flagged => Span::styled(" ", Style::default().bg(Color::Magenta)),
+                Bin::Value(v) => Span::styled(" ", Style::default().bg(colour_scale(*v))),
+            })
+            .collect();
+        lines.push(Line::from(spans));
+        // Label every fourth time slot with its UTC time of day.
+        if r % 4 == 0 {
+            let t = waterfall.times[r * n_time / bins.len()];
+            time_labels.push(Line::from(format_epoch(t)[11..19].to_string()));
+        } else {
+            time_labels.push(Line::from(""));
+        }
+    }
+    frame.render_widget(Paragraph::new(lines), heatmap);
+    frame.render_widget(
+        Paragraph::new(time_labels).style(Style::default().fg(Color::Gray)),
+        time_axis,
+    );
+
+    if let (Some(first), Some(last)) = (waterfall.chan_freq.first(), waterfall.chan_freq.last()) {
+        let [left, middle, right] = Layout::horizontal([
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+        ])
+        .areas(Rect {
+            x: heatmap.x,
+            width: bins.first().map_or(heatmap.width, |row| row.len() as u16),
+            ..freq_axis
+        });
+        let style = Style::default().fg(Color::Gray);
+        frame.render_widget(
+            Paragraph::new(format!("{:.3} MHz", first / 1e6)).style(style),
+            left,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("{:.3} MHz", (first + last) / 2e6))
+                .style(style)
+                .centered(),
+            middle,
+        );
+        frame.render_widget(
+            Paragraph::new(format!("{:.3} MHz", last / 1e6))
+                .style(style)
+                .right_aligned(),
+            right,
+        );
+    }
+}
+
+fn render_uv_plot(frame: &mut Frame, app: &App) {
+    let Some(uv_plot) = &app.uv_plot else {
+        return;
+    };
+    const COLOURS: [Color; 3] = [Color::Yellow, Color::Cyan, Color::Red];
+    let types = [
+        StationType::Core,
+        StationType::Remote,
+        StationType::International,
+    ];
+    let shown: Vec<String> = types
+        .iter()
+        .filter(|t| uv_plot.show[**t as usize])
+        .map(|t| t.name().to_string())
+        .collect();
+    let title = format!(
+        " UV coverage at {:.3} MHz in wavelengths, stations: {}{}; baselines to core/remote/international in yellow/cyan/red ",
+        uv_plot.ref_frequency / 1e6,
+        shown.join(", "),
+        if uv_plot.unflagged_only {
+            ", unflagged rows only"
+        } else {
+            ""
+        }
+    );
+    let bound = uv_plot.max_uv;
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black)),
+        )
+        .marker(symbols::Marker::Braille)
+        .x_bounds([-bound, bound])
+        .y_bounds([-bound, bound])
+        .paint(|ctx| {
+            for (points, colour) in uv_plot.points.iter().zip(COLOURS.iter()) {
+                ctx.draw(&Points {
+                    coords: points,
+                    color: *colour,
+                });
+            }
+            ctx.print(-bound, bound, format!("{:.0} lambda", bound));
+        });
+
+    let area = centered_rect(95, 90, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(canvas, area);
+}
+
+/// Progress of the table loader while it reads the data of a view.
+fn render_reading_view(frame: &mut Frame, app: &App) {
+    let Some(description) = &app.reading_view else {
+        return;
+    };
+    let progress = app.table_loader.progress();
+    let label = if progress > 0.0 {
+        format!("{:.0}%", 100.0 * progress)
+    } else {
+        SPINNER[app.spinner % SPINNER.len()].to_string()
+    };
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .title(format!(
+                    " Reading the data to {} (Esc to cancel) ",
+                    description
+                ))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().bg(Color::DarkGray))
+        .gauge_style(Style::default().fg(Color::LightGreen).bg(Color::DarkGray))
+        .label(label)
+        .ratio(progress.clamp(0.0, 1.0));
+    let area = centered_rect(60, 15, frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(gauge, area);
+}
+
+fn render_flag_stats(frame: &mut Frame, app: &App) {
+    let Some(flag_stats) = &app.flag_stats else {
+        return;
+    };
+    let area = centered_rect(80, 90, frame.area());
+    let [tabs_area, table_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+
+    let titles: Vec<&str> = flag_stats.tables.iter().map(|t| t.title).collect();
+    let tabs = Tabs::new(titles)
+        .block(
+            Block::default()
+                .title(format!(
+                    " Flag statistics: {:.2}% of {} samples flagged ",
+                    flag_stats.total.percentage(),
+                    flag_stats.total.total
+                ))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow).bold())
+        .select(app.flag_table);
+
+    let Some(table) = flag_stats.tables.get(app.flag_table) else {
+        return;
+    };
+    let rows: Vec<Row> = table
+        .rows
+        .iter()
+        .map(|(name, count)| {
+            Row::new(vec![
+                name.clone(),
+                format!("{:.2}%", count.percentage()),
+                count.flagged.to_string(),
+                count.total.to_string(),
+            ])
+        })
+        .collect();
+    let sort_order = if app.flag_by_percentage {
+        "flagged percentage"
+    } else {
+        "name"
+    };
+    let flag_table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(Row::new(vec![table.title, "Flagged", "Flagged samples", "Samples"]).bold())
+    .block(
+        Block::default()
+            .title(format!(" Sorted by {} (s to toggle) ", sort_order))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+    .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+    let mut table_state = TableState::default();
+    table_state.select(Some(app.flag_row));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(tabs, tabs_area);
+    frame.render_stateful_widget(flag_table, table_area, &mut table_state);
+}
+
+fn render_column_stats(frame: &mut Frame, app: &App) {
+    let area = centered_rect(80, 50, frame.area());
+    frame.render_widget(Clear, area);
+    if let Some(job) = &app.stats_job {
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .title(format!(" Computing statistics of {} ", job.column))
+                    .borders(Borders::ALL),
+            )
+            .style(Style::default().bg(Color::DarkGray))
+            .gauge_style(Style::default().fg(Color::LightGreen).bg(Color::DarkGray))
+            .label(format!(
+                "row {} of {} ({:.0}%)",
+                job.next_row - job.start_row,
+                job.end_row - job.start_row,
+                100.0 * job.progress()
+            ))
+            .ratio(job.progress().clamp(0.0, 1.0));
+        frame.render_widget(gauge, centered_rect(100, 30, area));
+        return;
+    }
+    let Some(column_stats) = &app.column_stats else {
+        return;
+    };
+    let stats_paragraph = Paragraph::new(column_stats.to_text())
+        .block(
+            Block::default()
+                .title(" Column statistics (q/Esc to exit) ")
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+    frame.render_widget(stats_paragraph, area);
+}
+
+fn render_comparison(frame: &mut Frame, app: &App) {
+    let area = centered_rect(80, 90, frame.area());
+    frame.render_widget(Clear, area);
+    if let Some(job) = &app.comparison_job {
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .title(format!(" Comparing {} ", job.pair.label()))
+                    .borders(Borders::ALL),
+            )
+            .style(Style::default().bg(Color::DarkGray))
+            .gauge_style(Style::default().fg(Color::LightGreen).bg(Color::DarkGray))
+            .label(format!(
+                "row {} of {} ({:.0}%)",
+                job.next_row,
+                job.end_row,
+                100.0 * job.progress()
+            ))
+            .ratio(job.progress().clamp(0.0, 1.0));
+        frame.render_widget(gauge, centered_rect(100, 15, area));
+        return;
+    }
+    let Some(comparison) = &app.comparison else {
+        return;
+    };
+    let [tabs_area, table_area] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+
+    let titles: Vec<&str> = comparison.tables.iter().map(|t| t.title).collect();
+    let tabs = Tabs::new(titles)
+        .block(
+            Block::default()
+                .title(format!(
+                    " {}: mean amplitude {:.4e}, RMS {:.4e}, phase {:.2} deg over {} samples ",
+                    comparison.pair.label(),
+                    comparison.total.mean_amplitude(),
+                    comparison.total.rms_amplitude(),
+                    comparison.total.mean_phase(),
+                    comparison.total.count
+                ))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow).bold())
+        .select(app.comparison_table);
+
+    let Some(table) = comparison.tables.get(app.comparison_table) else {
+        return;
+    };
+    let rows: Vec<Row> = table
+        .rows
+        .iter()
+        .map(|(name, sum)| {
+            Row::new(vec![
+                name.clone(),
+                format!("{:.6e}", sum.mean_amplitude()),
+                format!("{:.6e}", sum.rms_amplitude()),
+                format!("{:.3}", sum.mean_phase()),
+                sum.count.to_string(),
+            ])
+        })
+        .collect();
+    let mut notes = vec![format!("{} flagged samples left out", comparison.n_flagged)];
+    if comparison.n_skipped > 0 {
+        notes.push(format!(
+            "{} non-finite samples skipped",
+            comparison.n_skipped
+        ));
+    }
+    if let Some((query, n_matching)) = &comparison.filter {
+        notes.push(format!("{} rows matching {}", n_matching, query));
+    }
+    let comparison_table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(36),
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+            Constraint::Percentage(14),
+            Constraint::Percentage(14),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            table.title,
+            "Mean amp",
+            "RMS amp",
+            "Phase (deg)",
+            "Samples",
+        ])
+        .bold(),
+    )
+    .block(
+        Block::default()
+            .title(format!(" {} ", notes.join(", ")))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+    .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+    let mut table_state = TableState::default();
+    table_state.select(Some(app.comparison_row));
+
+    frame.render_widget(tabs, tabs_area);
+    frame.render_stateful_widget(comparison_table, table_area, &mut table_state);
+}
+
+fn render_export(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 30, frame.area());
+    frame.render_widget(Clear, area);
+    if let Some(job) = &app.export_job {
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .title(format!(
+                        " Exporting {} (Esc to cancel) ",
+                        job.columns.join(", ")
+                    ))
+                    .borders(Borders::ALL),
+            )
+            .style(Style::default().bg(Color::DarkGray))
+            .gauge_style(Style::default().fg(Color::LightGreen).bg(Color::DarkGray))
+            .label(format!(
+                "row {} of {} ({:.0}%)",
+                job.next_row - job.start_row,
+                job.end_row - job.start_row,
+                100.0 * job.progress()
+            ))
+            .ratio(job.progress().clamp(0.0, 1.0));
+        frame.render_widget(gauge, centered_rect(100, 40, area));
+        return;
+    }
+    let popup_block = Block::default()
+        .title(" Export (Enter to export, Esc to cancel) ")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+    if let Some(message) = &app.export_message {
+        let message_paragraph = Paragraph::new(message.clone())
+            .block(popup_block.title(" Export (q/Esc/Enter to close) "))
+            .style(Style::default().fg(Color::White))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(message_paragraph, area);
+        return;
+    }
+    let Some(form) = &app.export_form else {
+        return;
+    };
+    let field = |index: usize, label: &str, value: String| {
+        let selected = form.field == index;
+        let label_style = if selected {
+            Style::default().fg(Color::Yellow).bold()
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let mut spans = vec![
+            Span::styled(format!("{:<10}", label), label_style),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ];
+        if selected && index < 3 {
+            spans.push(Span::styled("_", Style::default().fg(Color::Yellow)));
+        }
+        Line::from(spans)
+    };
+    let rows = match &app.filter {
+        Some(filter) => format!("the {} rows matching {}", filter.rows.len(), filter.query),
+        None => format!("all {} rows", app.n_rows),
+    };
+    let epochs = if app.raw_values {
+        "raw values"
+    } else {
+        "UTC timestamps"
+    };
+    let text = vec![
+        field(0, "Columns", form.columns.clone()),
+        field(1, "Rows", form.rows.clone()),
+        field(2, "File", form.path.clone()),
+        field(3, "Complex", form.complex.name().to_string()),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "Rows as start:end count {}; leave empty to export all of them.",
+                rows
+            ),
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(Span::styled(
+            format!(
+                "CSV and TSV get epochs such as TIME as {} (t toggles this).",
+                epochs
+            ),
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+    let form_paragraph = Paragraph::new(text)
+        .block(popup_block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(form_paragraph, area);
+}
+
+fn render_diff(frame: &mut Frame, app: &App) {
+    let Some(view) = &app.diff_view else {
+        return;
+    };
+    let area = centered_rect(95, 90, frame.area());
+    let differences = app.ms_differences.as_deref().unwrap_or_default();
+    let structure_height = (differences.len().max(1) as u16 + 2).min(area.height / 3);
+    let [structure_area, status_area, panes_area] = Layout::vertical([
+        Constraint::Length(structure_height),
+        Constraint::Length(3),
+        Constraint::Min(1),
+    ])
+    .areas(area);
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(panes_area);
+
+    let structure_text: Vec<Line> = if differences.is_empty() {
+        vec![Line::from(Span::styled(
+            "No differences in tables, columns, keywords or row counts",
+            Style::default().fg(Color::LightGreen),
+        ))]
+    } else {
+        differences
+            .iter()
+            .map(|d| {
+                Line::from(Span::styled(
+                    d.clone(),
+                    Style::default().fg(Color::LightRed),
+                ))
+            })
+            .collect()
+    };
+    let structure = Paragraph::new(structure_text)
+        .block(
+            Block::default()
+                .title(format!(
+                    " {} structural differences with {} ",
+                    differences.len(),
+                    app.compare_ms.as_deref().unwrap_or_default()
+                ))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .scroll((view.structure_scroll, 0));
+
+    let column_diff = view.column_diff();
+    let mut status = match (&view.job, column_diff) {
+        (Some(job), Some(diff)) => format!(
+            "Comparing {}: row {} of {} ({:.0}%), {} differ so far",
+            view.column,
+            job.next_row,
+            job.n_rows(),
+            100.0 * job.progress(),
+            diff.rows.len()
+        ),
+        (None, Some(diff)) => diff.summary(),
+        _ => String::new(),
+    };
+    if let Some(row_diff) = column_diff.and_then(|d| d.row(view.scroll)) {
+        status.push_str(&format!(
+            "   Row {}: {} of {} elements differ, max abs diff {}",
+            row_diff.row,
+            row_diff.n_differing,
+            row_diff.n_elements,
+            format_diff(row_diff.max_abs_diff)
+        ));
+    }
+    let status_paragraph = Paragraph::new(status)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+
+    // Rows that differ are shown in red in both panes.
+    let pane = |lines: &[String], title: String| {
+        let offset = view.scroll.saturating_sub(view.first_row) as usize;
+        let text: Vec<Line> = lines
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(i, line)| {
+                let row = view.first_row + i as u64;
+                let style = if column_diff.and_then(|d| d.row(row)).is_some() {
+                    Style::default().fg(Color::LightRed)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                Line::from(Span::styled(line.clone(), style))
+            })
+            .collect();
+        Paragraph::new(text)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .style(Style::default().bg(Color::DarkGray))
+    };
+    let path = &app.tables.nodes[app.shown_table].path;
+    let pane_a = pane(&view.lines.0, format!(" {}: {} ", path, view.column));
+    let pane_b = pane(
+        &view.lines.1,
+        format!(" {}: {} ", view.other_path, view.column),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(structure, structure_area);
+    frame.render_widget(status_paragraph, status_area);
+    frame.render_widget(pane_a, left);
+    frame.render_widget(pane_b, right);
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -230,3 +1294,196 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1] // Return the middle chunk
 }
+
+fn render_keywords(frame: &mut Frame, app: &App) {
+    let Some(keywords) = &app.keywords else {
+        return;
+    };
+    let area = centered_rect(85, 90, frame.area());
+    let [tree_area, value_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(8)]).areas(area);
+
+    let visible = keywords.visible();
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|(path, depth)| {
+            let node = keywords.node(path);
+            let marker = if node.children.is_empty() {
+                "  "
+            } else if node.expanded {
+                "▾ "
+            } else {
+                "▸ "
+            };
+            // Multi-line values such as the readme only show their first line here.
+            let value = node.value.lines().next().unwrap_or_default().to_string();
+            Row::new(vec![
+                format!("{}{}{}", "  ".repeat(*depth), marker, node.name),
+                node.dtype.clone(),
+                value,
+            ])
+        })
+        .collect();
+    let keyword_table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(35),
+            Constraint::Percentage(15),
+            Constraint::Percentage(50),
+        ],
+    )
+    .header(Row::new(vec!["Keyword", "Type", "Value"]).bold())
+    .block(
+        Block::default()
+            .title(format!(
+                " Keywords of {} (q/Esc to close) ",
+                app.tables.nodes[app.shown_table].name
+            ))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+    .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+    let mut table_state = TableState::default();
+    table_state.select(Some(keywords.selected));
+
+    let selected_value = visible
+        .get(keywords.selected)
+        .map(|(path, _)| keywords.node(path))
+        .map_or(String::new(), |node| {
+            format!("{}: {}", node.name, node.value)
+        });
+    let value_text = Paragraph::new(selected_value)
+        .block(Block::default().title(" Value ").borders(Borders::ALL))
+        .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(keyword_table, tree_area, &mut table_state);
+    frame.render_widget(value_text, value_area);
+}
+
+fn render_storage(frame: &mut Frame, app: &App) {
+    let Some(storage) = &app.storage else {
+        return;
+    };
+    let area = centered_rect(90, 90, frame.area());
+    let files_height = (storage.files.len() as u16 + 3).min(area.height / 3);
+    let selected = storage.columns.get(storage.selected);
+    let settings = selected.and_then(|c| c.dysco_settings.as_ref());
+    let settings_height = settings.map_or(0, |s| s.lines().count() as u16 + 2);
+    let [columns_area, settings_area, files_area] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(settings_height),
+        Constraint::Length(files_height),
+    ])
+    .areas(area);
+
+    let rows: Vec<Row> = storage
+        .columns
+        .iter()
+        .map(|c| {
+            Row::new(vec![
+                c.column.clone(),
+                c.data_manager.clone(),
+                c.group.clone(),
+                c.tile_shape.clone(),
+                c.bytes_on_disk.map(format_bytes).unwrap_or_default(),
+                c.compression_ratio
+                    .map(|r| format!("{:.2}x", r))
+                    .unwrap_or_default(),
+            ])
+        })
+        .collect();
+    let column_table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(20),
+            Constraint::Percentage(17),
+            Constraint::Percentage(17),
+            Constraint::Percentage(20),
+            Constraint::Percentage(14),
+            Constraint::Percentage(12),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Column",
+            "Data manager",
+            "Group",
+            "Tile shape",
+            "On disk",
+            "Compression",
+        ])
+        .bold(),
+    )
+    .block(
+        Block::default()
+            .title(format!(
+                " Storage layout of {} (q/Esc to close) ",
+                app.tables.nodes[app.shown_table].name
+            ))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().bg(Color::DarkGray).fg(Color::White))
+    .highlight_style(Style::default().bg(Color::White).fg(Color::Black));
+    let mut table_state = TableState::default();
+    table_state.select(Some(storage.selected));
+
+    let total: u64 = storage.files.iter().map(|f| f.bytes).sum();
+    let file_rows: Vec<Row> = storage
+        .files
+        .iter()
+        .map(|f| {
+            Row::new(vec![
+                f.name.clone(),
+                f.data_manager.clone(),
+                format_bytes(f.bytes),
+            ])
+        })
+        .collect();
+    let file_table = Table::new(
+        file_rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+        ],
+    )
+    .header(Row::new(vec!["File", "Data manager", "Size"]).bold())
+    .block(
+        Block::default()
+            .title(format!(" Files ({} in total) ", format_bytes(total)))
+            .borders(Borders::ALL),
+    )
+    .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(column_table, columns_area, &mut table_state);
+    if let (Some(column), Some(settings)) = (selected, settings) {
+        let settings_text = Paragraph::new(settings.clone())
+            .block(
+                Block::default()
+                    .title(format!(" Dysco settings of {} ", column.column))
+                    .borders(Borders::ALL),
+            )
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+        frame.render_widget(settings_text, settings_area);
+    }
+    frame.render_widget(file_table, files_area);
+}
+
+/// A list entry with the characters matched by a search highlighted.
+fn highlighted_item<'a>(prefix: String, name: &str, positions: &[usize]) -> ListItem<'a> {
+    let normal = Style::default().fg(Color::Yellow);
+    let matched = Style::default().fg(Color::LightRed).bold();
+    let mut spans = vec![Span::styled(prefix, normal)];
+    for (i, c) in name.chars().enumerate() {
+        let style = if positions.contains(&i) {
+            matched
+        } else {
+            normal
+        };
+        spans.push(Span::styled(c.to_string(), style));
+    }
+    ListItem::new(Line::from(spans))
+}
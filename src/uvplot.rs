@@ -0,0 +1,202 @@
+use anyhow::{bail, Result};
+use rubbl_casatables::Table;
+
+use crate::reader::{Block, CellValues, ColumnReader, JOB_CACHE_BYTES, JOB_ROWS_PER_STEP};
+use crate::summary::open_subtable;
+
+/// Speed of light in m/s.
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/// Type of a LOFAR station, derived from its name.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StationType {
+    Core,
+    Remote,
+    International,
+}
+
+impl StationType {
+    pub fn from_name(name: &str) -> StationType {
+        if name.starts_with("CS") {
+            StationType::Core
+        } else if name.starts_with("RS") {
+            StationType::Remote
+        } else {
+            StationType::International
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StationType::Core => "core",
+            StationType::Remote => "remote",
+            StationType::International => "international",
+        }
+    }
+}
+
+/// UV coverage of a table with a UVW column, in wavelengths.
+pub struct UvPlot {
+    pub ref_frequency: f64,
+    pub show: [bool; 3],
+    pub unflagged_only: bool,
+    /// Points per baseline class, indexed by the furthest out station type of the baseline.
+    pub points: [Vec<(f64, f64)>; 3],
+    pub max_uv: f64,
+    u: Vec<f64>,
+    v: Vec<f64>,
+    baseline_types: Vec<(StationType, StationType)>,
+    flagged: Vec<bool>,
+}
+
+/// Reference frequency of the spectral window used by the first row of MAIN.
+fn reference_frequency(ms_name: &str, table: &mut Table) -> Result<f64> {
+    let ddid = table.get_cell::<i32>("DATA_DESC_ID", 0)?;
+    let spw = open_subtable(ms_name, "DATA_DESCRIPTION")?
+        .get_cell::<i32>("SPECTRAL_WINDOW_ID", ddid as u64)?;
+    Ok(open_subtable(ms_name, "SPECTRAL_WINDOW")?.get_cell::<f64>("REF_FREQUENCY", spw as u64)?)
+}
+
+/// Reads the UVW and flags of all rows in steps, so progress can be drawn in between.
+pub struct UvPlotJob {
+    next_row: u64,
+    n_rows: u64,
+    ref_frequency: f64,
+    has_flag: bool,
+    reader: ColumnReader,
+    u: Vec<f64>,
+    v: Vec<f64>,
+    baseline_types: Vec<(StationType, StationType)>,
+    flagged: Vec<bool>,
+}
+
+impl UvPlotJob {
+    pub fn new(ms_name: &str, table: &mut Table, station_names: &[String]) -> Result<Self> {
+        if table.n_rows() == 0 {
+            bail!("Table has no rows");
+        }
+        let columns = table.column_names()?;
+        if !columns.iter().any(|c| c == "UVW") {
+            bail!("Table has no UVW column");
+        }
+        let has_flag = columns.iter().any(|c| c == "FLAG");
+        let has_flag_row = columns.iter().any(|c| c == "FLAG_ROW");
+        let ref_frequency = reference_frequency(ms_name, table)?;
+        let antenna1 = table.get_col_as_vec::<i32>("ANTENNA1")?;
+        let antenna2 = table.get_col_as_vec::<i32>("ANTENNA2")?;
+        let flagged = if has_flag_row {
+            table.get_col_as_vec::<bool>("FLAG_ROW")?
+        } else {
+            vec![false; table.n_rows() as usize]
+        };
+
+        let station_type = |a: i32| {
+            usize::try_from(a)
+                .ok()
+                .and_then(|i| station_names.get(i))
+                .map_or(StationType::International, |n| StationType::from_name(n))
+        };
+        let baseline_types = antenna1
+            .iter()
+            .zip(antenna2.iter())
+            .map(|(a1, a2)| (station_type(*a1), station_type(*a2)))
+            .collect();
+
+        Ok(UvPlotJob {
+            next_row: 0,
+            n_rows: table.n_rows(),
+            ref_frequency,
+            has_flag,
+            reader: ColumnReader::new(JOB_CACHE_BYTES),
+            u: Vec::with_capacity(table.n_rows() as usize),
+            v: Vec::with_capacity(table.n_rows() as usize),
+            baseline_types,
+            flagged,
+        })
+    }
+
+    /// Fraction of the rows read so far.
+    pub fn progress(&self) -> f64 {
+        self.next_row as f64 / self.n_rows.max(1) as f64
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_row >= self.n_rows
+    }
+
+    /// Read the next batch of rows.
+    pub fn step(&mut self, table: &mut Table) -> Result<()> {
+        let scale = self.ref_frequency / SPEED_OF_LIGHT;
+        let end = (self.next_row + JOB_ROWS_PER_STEP).min(self.n_rows);
+        for row in self.next_row..end {
+            match self.reader.cell_values(table, "UVW", row)? {
+                Some(CellValues::Real(uvw)) if uvw.len() >= 2 => {
+                    self.u.push(uvw[0] * scale);
+                    self.v.push(uvw[1] * scale);
+                }
+                _ => bail!("Row {} of UVW does not hold u, v and w", row),
+            }
+            // A row whose samples are all flagged counts as flagged too.
+            if self.has_flag && !self.flagged[row as usize] {
+                if let (Block::Bool(cells), index) = self.reader.cell(table, "FLAG", row)? {
+                    self.flagged[row as usize] = cells
+                        .view(index)
+                        .is_some_and(|f| !f.is_empty() && f.iter().all(|f| *f));
+                }
+            }
+        }
+        self.next_row = end;
+        Ok(())
+    }
+
+    pub fn finish(self) -> UvPlot {
+        let mut plot = UvPlot {
+            ref_frequency: self.ref_frequency,
+            show: [true; 3],
+            unflagged_only: false,
+            points: [Vec::new(), Vec::new(), Vec::new()],
+            max_uv: 1.0,
+            u: self.u,
+            v: self.v,
+            baseline_types: self.baseline_types,
+            flagged: self.flagged,
+        };
+        plot.update_points();
+        plot
+    }
+}
+
+impl UvPlot {
+    pub fn toggle_station_type(&mut self, station_type: StationType) {
+        self.show[station_type as usize] = !self.show[station_type as usize];
+        self.update_points();
+    }
+
+    pub fn toggle_unflagged_only(&mut self) {
+        self.unflagged_only = !self.unflagged_only;
+        self.update_points();
+    }
+
+    /// Select the points to draw based on the station type and flag filters.
+    ///
+    /// Baselines are only drawn if the types of both stations are enabled.
+    /// Every baseline is drawn together with its mirror image at (-u, -v).
+    pub fn update_points(&mut self) {
+        self.points = [Vec::new(), Vec::new(), Vec::new()];
+        let mut max_uv: f64 = 0.0;
+        for (i, (t1, t2)) in self.baseline_types.iter().enumerate() {
+            if !self.show[*t1 as usize] || !self.show[*t2 as usize] {
+                continue;
+            }
+            if self.unflagged_only && self.flagged[i] {
+                continue;
+            }
+            let (u, v) = (self.u[i], self.v[i]);
+            let class = (*t1).max(*t2) as usize;
+            self.points[class].push((u, v));
+            self.points[class].push((-u, -v));
+            max_uv = max_uv.max(u.abs()).max(v.abs());
+        }
+        self.max_uv = if max_uv > 0.0 { max_uv * 1.05 } else { 1.0 };
+    }
+}
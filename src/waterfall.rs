@@ -0,0 +1,164 @@
+use anyhow::Result;
+use rubbl_casatables::Table;
+use rubbl_core::ndarray::Array2;
+
+use crate::inspector::spectral_setup;
+use crate::plot::VisibilitySource;
+
+/// Content of one cell of the rendered waterfall.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bin {
+    Empty,
+    Flagged,
+    /// Amplitude scaled to [0, 1] between the colour scale limits.
+    Value(f64),
+}
+
+/// Amplitude per time slot and channel (dynamic spectrum) for a single baseline.
+pub struct Waterfall {
+    pub source: VisibilitySource,
+    pub times: Vec<f64>,
+    pub chan_freq: Vec<f64>,
+    /// Amplitudes with shape (ntime, nchan); NaN where no data is present.
+    pub amplitudes: Array2<f64>,
+    pub flags: Array2<bool>,
+    /// Limits of the colour scale, taken from the 1st and 99th percentile of unflagged data.
+    pub scale: (f64, f64),
+}
+
+impl Waterfall {
+    /// Read the data of the selected baseline; see `reload` for `progress`.
+    pub fn new(
+        ms_name: &str,
+        table: &mut Table,
+        source: VisibilitySource,
+        progress: &dyn Fn(f64) -> Result<()>,
+    ) -> Result<Self> {
+        let (chan_freq, _) = spectral_setup(ms_name, table, 0).unwrap_or_default();
+        let mut waterfall = Waterfall {
+            source,
+            times: Vec::new(),
+            chan_freq,
+            amplitudes: Array2::zeros((0, 0)),
+            flags: Array2::from_elem((0, 0), false),
+            scale: (0.0, 1.0),
+        };
+        waterfall.reload(table, progress)?;
+        Ok(waterfall)
+    }
+
+    /// Read the data of the selected baseline and correlation again.
+    ///
+    /// `progress` is told the fraction of rows done before each row; an error from it stops
+    /// reading.
+    pub fn reload(
+        &mut self,
+        table: &mut Table,
+        progress: &dyn Fn(f64) -> Result<()>,
+    ) -> Result<()> {
+        let selection = self.source.selection.clone();
+        let rows = self
+            .source
+            .index
+            .rows(selection.antenna1, selection.antenna2);
+
+        // Group the rows of this baseline by time slot.
+        let mut times: Vec<f64> = rows
+            .iter()
+            .map(|&r| self.source.index.time[r as usize])
+            .collect();
+        times.sort_by(|a, b| a.total_cmp(b));
+        times.dedup();
+
+        let n_chan = selection.n_chan;
+        let mut amplitudes = Array2::from_elem((times.len(), n_chan), f64::NAN);
+        let mut flags = Array2::from_elem((times.len(), n_chan), false);
+        for (i, &row) in rows.iter().enumerate() {
+            progress(i as f64 / rows.len() as f64)?;
+            let data = self.source.read(table, row)?;
+            // Cells may vary in shape; skip rows without the selected correlation.
+            if selection.correlation >= data.shape()[1] {
+                continue;
+            }
+            let row_flags = self.source.read_flags(table, row, data.shape())?;
+            let time = self.source.index.time[row as usize];
+            let slot = times.partition_point(|t| *t < time);
+            for chan in 0..n_chan.min(data.shape()[0]) {
+                amplitudes[[slot, chan]] = data[[chan, selection.correlation]].norm();
+                flags[[slot, chan]] = row_flags[[chan, selection.correlation]];
+            }
+        }
+
+        let mut unflagged: Vec<f64> = amplitudes
+            .iter()
+            .zip(flags.iter())
+            .filter(|(a, f)| a.is_finite() && !**f)
+            .map(|(a, _)| *a)
+            .collect();
+        unflagged.sort_by(|a, b| a.total_cmp(b));
+        self.scale = if unflagged.is_empty() {
+            (0.0, 1.0)
+        } else {
+            let low = unflagged[unflagged.len() / 100];
+            let high = unflagged[(unflagged.len() * 99 / 100).min(unflagged.len() - 1)];
+            if high > low {
+                (low, high)
+            } else {
+                (low, low + 1.0)
+            }
+        };
+        self.times = times;
+        self.amplitudes = amplitudes;
+        self.flags = flags;
+        Ok(())
+    }
+
+    /// Bin the waterfall into at most `height` time rows and `width` channel columns.
+    ///
+    /// A bin is shown as flagged only if all samples in it are flagged.
+    pub fn bin(&self, width: usize, height: usize) -> Vec<Vec<Bin>> {
+        let (n_time, n_chan) = self.amplitudes.dim();
+        if width == 0 || height == 0 || n_time == 0 || n_chan == 0 {
+            return Vec::new();
+        }
+        let n_rows = n_time.min(height);
+        let n_cols = n_chan.min(width);
+        let (low, high) = self.scale;
+        (0..n_rows)
+            .map(|r| {
+                let t0 = r * n_time / n_rows;
+                let t1 = ((r + 1) * n_time / n_rows).max(t0 + 1);
+                (0..n_cols)
+                    .map(|c| {
+                        let c0 = c * n_chan / n_cols;
+                        let c1 = ((c + 1) * n_chan / n_cols).max(c0 + 1);
+                        let mut sum = 0.0;
+                        let mut n = 0;
+                        let mut n_flagged = 0;
+                        for t in t0..t1 {
+                            for ch in c0..c1 {
+                                let a = self.amplitudes[[t, ch]];
+                                if !a.is_finite() {
+                                    continue;
+                                }
+                                if self.flags[[t, ch]] {
+                                    n_flagged += 1;
+                                } else {
+                                    sum += a;
+                                    n += 1;
+                                }
+                            }
+                        }
+                        if n > 0 {
+                            Bin::Value((((sum / n as f64) - low) / (high - low)).clamp(0.0, 1.0))
+                        } else if n_flagged > 0 {
+                            Bin::Flagged
+                        } else {
+                            Bin::Empty
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}